@@ -0,0 +1,19 @@
+-- Watchlists de cada usuario del gateway, con sus símbolos ordenados
+CREATE TABLE watchlists (
+    id          BIGSERIAL PRIMARY KEY,
+    owner       TEXT NOT NULL,
+    name        TEXT NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT watchlists_owner_name_key UNIQUE (owner, name)
+);
+
+CREATE TABLE watchlist_items (
+    watchlist_id  BIGINT NOT NULL REFERENCES watchlists (id) ON DELETE CASCADE,
+    position      INTEGER NOT NULL,
+    symbol        TEXT NOT NULL,
+    timeframe     TEXT NOT NULL,
+    notes         TEXT NOT NULL DEFAULT '',
+    PRIMARY KEY (watchlist_id, position),
+    CONSTRAINT watchlist_items_symbol_key UNIQUE (watchlist_id, symbol)
+);
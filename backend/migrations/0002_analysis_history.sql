@@ -0,0 +1,22 @@
+-- Resultado de cada análisis de inBestia, para seguir la evolución de las puntuaciones
+CREATE TABLE analysis_history (
+    id                   BIGSERIAL PRIMARY KEY,
+    symbol               TEXT NOT NULL,
+    -- Nombre canónico (1d, 4h...) sea cual sea el alias pedido
+    timeframe            TEXT NOT NULL,
+    analyzed_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+    -- Body de la petición tal como se envió a inBestia
+    params               JSONB NOT NULL,
+    score                DOUBLE PRECISION NOT NULL,
+    technical_score      DOUBLE PRECISION NOT NULL,
+    fundamental_score    DOUBLE PRECISION,
+    trend_score          DOUBLE PRECISION NOT NULL,
+    momentum_score       DOUBLE PRECISION NOT NULL,
+    volatility_score     DOUBLE PRECISION NOT NULL,
+    volume_score         DOUBLE PRECISION NOT NULL,
+    suggested_operation  TEXT NOT NULL,
+    explanation          TEXT NOT NULL,
+    trace_id             TEXT
+);
+
+CREATE INDEX analysis_history_symbol_idx ON analysis_history (symbol, timeframe, analyzed_at);
@@ -0,0 +1,11 @@
+-- Snapshots de inBestia (estado de proveedores, calidad de datos) tomados por los jobs programados
+CREATE TABLE job_snapshots (
+    id         BIGSERIAL PRIMARY KEY,
+    job        TEXT NOT NULL,
+    -- providers_status | data_quality
+    kind       TEXT NOT NULL,
+    taken_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    data       JSONB NOT NULL
+);
+
+CREATE INDEX job_snapshots_job_idx ON job_snapshots (job, taken_at);
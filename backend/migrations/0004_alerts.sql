@@ -0,0 +1,46 @@
+-- Reglas de alerta de cada usuario, evaluadas sobre cada análisis nuevo
+CREATE TABLE alert_rules (
+    id             BIGSERIAL PRIMARY KEY,
+    owner          TEXT NOT NULL,
+    name           TEXT NOT NULL,
+    -- NULL = cualquier símbolo / timeframe (canónico)
+    symbol         TEXT,
+    timeframe      TEXT,
+    expression     TEXT NOT NULL,
+    cooldown_secs  INTEGER NOT NULL,
+    enabled        BOOLEAN NOT NULL DEFAULT true,
+    created_at     TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at     TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT alert_rules_owner_name_key UNIQUE (owner, name)
+);
+
+CREATE INDEX alert_rules_enabled_idx ON alert_rules (symbol, timeframe) WHERE enabled;
+
+-- Estado de cada regla por símbolo y timeframe: último análisis visto (para `crosses` y
+-- `changes`), si la condición se cumplía (sólo se dispara al pasar a cumplirse) y el
+-- último disparo (cooldown)
+CREATE TABLE alert_state (
+    rule_id        BIGINT NOT NULL REFERENCES alert_rules (id) ON DELETE CASCADE,
+    symbol         TEXT NOT NULL,
+    timeframe      TEXT NOT NULL,
+    previous       JSONB NOT NULL,
+    active         BOOLEAN NOT NULL,
+    last_fired_at  TIMESTAMPTZ,
+    PRIMARY KEY (rule_id, symbol, timeframe)
+);
+
+-- Registro de alertas disparadas; se conserva aunque se borre la regla
+CREATE TABLE fired_alerts (
+    id          BIGSERIAL PRIMARY KEY,
+    rule_id     BIGINT REFERENCES alert_rules (id) ON DELETE SET NULL,
+    owner       TEXT NOT NULL,
+    rule_name   TEXT NOT NULL,
+    expression  TEXT NOT NULL,
+    symbol      TEXT NOT NULL,
+    timeframe   TEXT NOT NULL,
+    fired_at    TIMESTAMPTZ NOT NULL,
+    analysis    JSONB NOT NULL,
+    previous    JSONB
+);
+
+CREATE INDEX fired_alerts_owner_idx ON fired_alerts (owner, fired_at);
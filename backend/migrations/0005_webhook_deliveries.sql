@@ -0,0 +1,21 @@
+-- Cola durable de entregas de webhooks: pendientes (con sus reintentos), entregadas y
+-- muertas (dead letter, agotados los intentos)
+CREATE TABLE webhook_deliveries (
+    id              UUID PRIMARY KEY,
+    -- Nombre del webhook en WEBHOOKS_FILE; la URL y el secreto se leen al entregar
+    webhook         TEXT NOT NULL,
+    event           TEXT NOT NULL,
+    payload         JSONB NOT NULL,
+    -- pending | delivered | dead
+    status          TEXT NOT NULL DEFAULT 'pending',
+    attempts        INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_status     INTEGER,
+    last_error      TEXT,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT now(),
+    delivered_at    TIMESTAMPTZ
+);
+
+CREATE INDEX webhook_deliveries_due_idx ON webhook_deliveries (next_attempt_at)
+    WHERE status = 'pending';
+CREATE INDEX webhook_deliveries_created_idx ON webhook_deliveries (created_at);
@@ -0,0 +1,481 @@
+//! Lenguaje de las reglas de alerta, evaluado sobre cada `AnalysisResponse`.
+//!
+//! ```text
+//! score crosses above 70
+//! suggested_operation changes from "HOLD" to "BUY"
+//! volatility_score > 80 and not (trend_score < 40 or momentum_score <= 20)
+//! ```
+//!
+//! - Comparaciones `>`, `>=`, `<`, `<=`, `==`, `!=` de un campo con un número o, para
+//!   `suggested_operation`, con una cadena entre comillas.
+//! - `crosses above N` / `crosses below N`: el análisis anterior del mismo símbolo y
+//!   timeframe estaba en o por debajo (por encima) de N y el nuevo ya lo supera.
+//! - `changes [from "X"] [to "Y"]`: `suggested_operation` cambió respecto al anterior.
+//! - `and`, `or`, `not` y paréntesis. Las palabras clave no distinguen mayúsculas.
+
+use ghost_shared::AnalysisResponse;
+use std::fmt;
+
+/// Campos numéricos de la respuesta que admiten las reglas
+pub const NUMERIC_FIELDS: [&str; 7] = [
+    "score",
+    "technical_score",
+    "fundamental_score",
+    "trend_score",
+    "momentum_score",
+    "volatility_score",
+    "volume_score",
+];
+
+/// Campos de texto de la respuesta que admiten las reglas
+pub const TEXT_FIELDS: [&str; 1] = ["suggested_operation"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl CompareOp {
+    fn holds<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            CompareOp::Gt => left > right,
+            CompareOp::Ge => left >= right,
+            CompareOp::Lt => left < right,
+            CompareOp::Le => left <= right,
+            CompareOp::Eq => left == right,
+            CompareOp::Ne => left != right,
+        }
+    }
+}
+
+/// Expresión ya validada
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number {
+        field: &'static str,
+        op: CompareOp,
+        value: f64,
+    },
+    Text {
+        field: &'static str,
+        op: CompareOp,
+        value: String,
+    },
+    CrossesAbove {
+        field: &'static str,
+        value: f64,
+    },
+    CrossesBelow {
+        field: &'static str,
+        value: f64,
+    },
+    Changes {
+        field: &'static str,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+/// Error de sintaxis o de tipos, con la posición (en caracteres, desde 1) donde se detectó
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "posición {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parsea y valida una regla
+pub fn parse(source: &str) -> Result<Expr, ParseError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end: source.chars().count() + 1,
+    };
+    let expr = parser.or()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some((token, at)) => Err(ParseError {
+            position: at,
+            message: format!("sobra {} al final de la regla", token),
+        }),
+    }
+}
+
+impl Expr {
+    /// ¿Se cumple para `current`? `previous` es el análisis anterior del mismo símbolo y
+    /// timeframe; sin él `crosses` y `changes` no se cumplen.
+    pub fn matches(&self, current: &AnalysisResponse, previous: Option<&AnalysisResponse>) -> bool {
+        match self {
+            Expr::Number { field, op, value } => {
+                number(current, field).is_some_and(|actual| op.holds(actual, *value))
+            }
+            Expr::Text { field, op, value } => op.holds(text(current, field), value.as_str()),
+            Expr::CrossesAbove { field, value } => {
+                match (
+                    previous.and_then(|p| number(p, field)),
+                    number(current, field),
+                ) {
+                    (Some(before), Some(now)) => before <= *value && now > *value,
+                    _ => false,
+                }
+            }
+            Expr::CrossesBelow { field, value } => {
+                match (
+                    previous.and_then(|p| number(p, field)),
+                    number(current, field),
+                ) {
+                    (Some(before), Some(now)) => before >= *value && now < *value,
+                    _ => false,
+                }
+            }
+            Expr::Changes { field, from, to } => previous.is_some_and(|previous| {
+                let (before, now) = (text(previous, field), text(current, field));
+                before != now
+                    && from.as_deref().is_none_or(|from| from == before)
+                    && to.as_deref().is_none_or(|to| to == now)
+            }),
+            Expr::And(left, right) => {
+                left.matches(current, previous) && right.matches(current, previous)
+            }
+            Expr::Or(left, right) => {
+                left.matches(current, previous) || right.matches(current, previous)
+            }
+            Expr::Not(inner) => !inner.matches(current, previous),
+        }
+    }
+}
+
+fn number(response: &AnalysisResponse, field: &str) -> Option<f64> {
+    match field {
+        "score" => Some(response.score),
+        "technical_score" => Some(response.technical_score),
+        "fundamental_score" => response.fundamental_score,
+        "trend_score" => Some(response.trend_score),
+        "momentum_score" => Some(response.momentum_score),
+        "volatility_score" => Some(response.volatility_score),
+        "volume_score" => Some(response.volume_score),
+        _ => None,
+    }
+}
+
+fn text<'a>(response: &'a AnalysisResponse, field: &str) -> &'a str {
+    match field {
+        "suggested_operation" => &response.suggested_operation,
+        _ => "",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Str(String),
+    Op(CompareOp),
+    LParen,
+    RParen,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Str(s) => write!(f, "\"{}\"", s),
+            Token::Op(op) => f.write_str(match op {
+                CompareOp::Gt => "'>'",
+                CompareOp::Ge => "'>='",
+                CompareOp::Lt => "'<'",
+                CompareOp::Le => "'<='",
+                CompareOp::Eq => "'=='",
+                CompareOp::Ne => "'!='",
+            }),
+            Token::LParen => f.write_str("'('"),
+            Token::RParen => f.write_str("')'"),
+        }
+    }
+}
+
+/// Tokens con su posición (en caracteres, desde 1)
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let at = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let token = match c {
+            '(' => {
+                i += 1;
+                Token::LParen
+            }
+            ')' => {
+                i += 1;
+                Token::RParen
+            }
+            '>' | '<' | '=' | '!' => {
+                let double = chars.get(i + 1) == Some(&'=');
+                let op = match (c, double) {
+                    ('>', false) => CompareOp::Gt,
+                    ('>', true) => CompareOp::Ge,
+                    ('<', false) => CompareOp::Lt,
+                    ('<', true) => CompareOp::Le,
+                    ('=', true) => CompareOp::Eq,
+                    ('!', true) => CompareOp::Ne,
+                    _ => {
+                        return Err(ParseError {
+                            position: at,
+                            message: format!("operador '{}' desconocido (¿'{}='?)", c, c),
+                        })
+                    }
+                };
+                i += if double { 2 } else { 1 };
+                Token::Op(op)
+            }
+            '"' | '\'' => {
+                let close = chars[i + 1..]
+                    .iter()
+                    .position(|&q| q == c)
+                    .ok_or(ParseError {
+                        position: at,
+                        message: "cadena sin cerrar".to_string(),
+                    })?;
+                let value: String = chars[i + 1..i + 1 + close].iter().collect();
+                i += close + 2;
+                Token::Str(value)
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '.' => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let literal: String = chars[start..i].iter().collect();
+                let value = literal.parse::<f64>().map_err(|_| ParseError {
+                    position: at,
+                    message: format!("número inválido '{}'", literal),
+                })?;
+                Token::Number(value)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                Token::Ident(chars[start..i].iter().collect::<String>().to_lowercase())
+            }
+            other => {
+                return Err(ParseError {
+                    position: at,
+                    message: format!("carácter inesperado '{}'", other),
+                })
+            }
+        };
+        tokens.push((token, at));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Posición que se informa cuando la regla termina antes de tiempo
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<(&Token, usize)> {
+        self.tokens.get(self.pos).map(|(token, at)| (token, *at))
+    }
+
+    fn next(&mut self) -> Option<(Token, usize)> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some((Token::Ident(word), _)) if word == keyword)
+    }
+
+    fn error<T>(&self, expected: &str) -> Result<T, ParseError> {
+        Err(match self.peek() {
+            Some((token, at)) => ParseError {
+                position: at,
+                message: format!("se esperaba {}, no {}", expected, token),
+            },
+            None => ParseError {
+                position: self.end,
+                message: format!("se esperaba {} y la regla terminó", expected),
+            },
+        })
+    }
+
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.and()?;
+        while self.at_keyword("or") {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.unary()?;
+        while self.at_keyword("and") {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.at_keyword("not") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if matches!(self.peek(), Some((Token::LParen, _))) {
+            self.pos += 1;
+            let expr = self.or()?;
+            if !matches!(self.peek(), Some((Token::RParen, _))) {
+                return self.error("')'");
+            }
+            self.pos += 1;
+            return Ok(expr);
+        }
+        self.condition()
+    }
+
+    fn condition(&mut self) -> Result<Expr, ParseError> {
+        let Some((Token::Ident(name), at)) = self.peek().map(|(t, at)| (t.clone(), at)) else {
+            return self.error("un campo");
+        };
+        let numeric = NUMERIC_FIELDS.iter().find(|field| **field == name).copied();
+        let textual = TEXT_FIELDS.iter().find(|field| **field == name).copied();
+        let Some(field) = numeric.or(textual) else {
+            return Err(ParseError {
+                position: at,
+                message: format!(
+                    "campo '{}' desconocido; campos válidos: {}, {}",
+                    name,
+                    NUMERIC_FIELDS.join(", "),
+                    TEXT_FIELDS.join(", ")
+                ),
+            });
+        };
+        self.pos += 1;
+
+        if self.at_keyword("crosses") {
+            if numeric.is_none() {
+                return Err(ParseError {
+                    position: at,
+                    message: format!(
+                        "'crosses' sólo se aplica a campos numéricos, no a '{}'",
+                        field
+                    ),
+                });
+            }
+            self.pos += 1;
+            let above = if self.at_keyword("above") {
+                true
+            } else if self.at_keyword("below") {
+                false
+            } else {
+                return self.error("'above' o 'below'");
+            };
+            self.pos += 1;
+            let value = self.number()?;
+            return Ok(if above {
+                Expr::CrossesAbove { field, value }
+            } else {
+                Expr::CrossesBelow { field, value }
+            });
+        }
+
+        if self.at_keyword("changes") {
+            if textual.is_none() {
+                return Err(ParseError {
+                    position: at,
+                    message: format!(
+                        "'changes' sólo se aplica a {}; para '{}' usa 'crosses'",
+                        TEXT_FIELDS.join(", "),
+                        field
+                    ),
+                });
+            }
+            self.pos += 1;
+            let mut from = None;
+            let mut to = None;
+            if self.at_keyword("from") {
+                self.pos += 1;
+                from = Some(self.string()?);
+            }
+            if self.at_keyword("to") {
+                self.pos += 1;
+                to = Some(self.string()?);
+            }
+            return Ok(Expr::Changes { field, from, to });
+        }
+
+        let op = match self.peek() {
+            Some((Token::Op(op), _)) => *op,
+            _ => return self.error("un operador (>, >=, <, <=, ==, !=), 'crosses' o 'changes'"),
+        };
+        let op_at = self.peek().map(|(_, at)| at).unwrap_or(self.end);
+        self.pos += 1;
+        if numeric.is_some() {
+            let value = self.number()?;
+            Ok(Expr::Number { field, op, value })
+        } else {
+            if !matches!(op, CompareOp::Eq | CompareOp::Ne) {
+                return Err(ParseError {
+                    position: op_at,
+                    message: format!("'{}' sólo admite == y !=", field),
+                });
+            }
+            let value = self.string()?;
+            Ok(Expr::Text { field, op, value })
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, ParseError> {
+        match self.peek() {
+            Some((Token::Number(value), _)) => {
+                let value = *value;
+                self.next();
+                Ok(value)
+            }
+            _ => self.error("un número"),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Some((Token::Str(value), _)) => {
+                let value = value.clone();
+                self.next();
+                Ok(value)
+            }
+            _ => self.error("una cadena entre comillas"),
+        }
+    }
+}
@@ -0,0 +1,406 @@
+pub mod expr;
+
+use axum::{http::StatusCode, response::Response};
+use chrono::{DateTime, Utc};
+use ghost_shared::responses::{AlertRule, FiredAlert};
+use ghost_shared::{AlertRuleRequest, AnalysisResponse};
+use sqlx::{types::Json, FromRow, PgPool};
+use std::fmt;
+use tracing::{error, info, warn};
+
+use crate::errors::error_response;
+use crate::webhooks::{WebhookEvent, Webhooks};
+
+/// Fallo de una operación sobre las reglas de alerta
+#[derive(Debug)]
+pub enum AlertError {
+    /// No existe o es de otro usuario
+    NotFound,
+    /// El usuario ya tiene una regla con ese nombre
+    NameTaken,
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for AlertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "alert rule not found"),
+            Self::NameTaken => write!(f, "alert rule name already in use"),
+            Self::Database(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AlertError {}
+
+impl From<sqlx::Error> for AlertError {
+    fn from(e: sqlx::Error) -> Self {
+        let name_taken = e
+            .as_database_error()
+            .and_then(|db| db.constraint())
+            .is_some_and(|constraint| constraint == "alert_rules_owner_name_key");
+        if name_taken {
+            Self::NameTaken
+        } else {
+            Self::Database(e)
+        }
+    }
+}
+
+impl AlertError {
+    /// Respuesta de error estándar del gateway para este fallo
+    pub fn to_response(&self, trace_id: &str) -> Response {
+        match self {
+            Self::NotFound => error_response(
+                StatusCode::NOT_FOUND,
+                "ALERT_RULE_NOT_FOUND",
+                "Regla de alerta no encontrada",
+                trace_id,
+            ),
+            Self::NameTaken => error_response(
+                StatusCode::CONFLICT,
+                "ALERT_RULE_NAME_TAKEN",
+                "Ya existe una regla de alerta con ese nombre",
+                trace_id,
+            ),
+            Self::Database(e) => {
+                error!(trace_id, error = %e, "alert query failed");
+                error_response(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "DATABASE_UNAVAILABLE",
+                    "La base de datos no está disponible",
+                    trace_id,
+                )
+            }
+        }
+    }
+}
+
+const RULE_COLUMNS: &str =
+    "id, name, symbol, timeframe, expression, cooldown_secs, enabled, created_at, updated_at";
+
+#[derive(FromRow)]
+struct RuleRow {
+    id: i64,
+    name: String,
+    symbol: Option<String>,
+    timeframe: Option<String>,
+    expression: String,
+    cooldown_secs: i32,
+    enabled: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<RuleRow> for AlertRule {
+    fn from(row: RuleRow) -> Self {
+        AlertRule {
+            id: row.id,
+            name: row.name,
+            symbol: row.symbol,
+            timeframe: row.timeframe,
+            expression: row.expression,
+            cooldown_secs: row.cooldown_secs.max(0) as u32,
+            enabled: row.enabled,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// Reglas de `owner`, por nombre
+pub async fn list(db: &PgPool, owner: &str) -> Result<Vec<AlertRule>, AlertError> {
+    let rows: Vec<RuleRow> = sqlx::query_as(&format!(
+        "SELECT {} FROM alert_rules WHERE owner = $1 ORDER BY name",
+        RULE_COLUMNS
+    ))
+    .bind(owner)
+    .fetch_all(db)
+    .await?;
+    Ok(rows.into_iter().map(AlertRule::from).collect())
+}
+
+pub async fn get(db: &PgPool, owner: &str, id: i64) -> Result<AlertRule, AlertError> {
+    let row: RuleRow = sqlx::query_as(&format!(
+        "SELECT {} FROM alert_rules WHERE id = $1 AND owner = $2",
+        RULE_COLUMNS
+    ))
+    .bind(id)
+    .bind(owner)
+    .fetch_optional(db)
+    .await?
+    .ok_or(AlertError::NotFound)?;
+    Ok(row.into())
+}
+
+/// Crea una regla (`request` ya normalizado y con la expresión validada)
+pub async fn create(
+    db: &PgPool,
+    owner: &str,
+    request: &AlertRuleRequest,
+) -> Result<AlertRule, AlertError> {
+    let row: RuleRow = sqlx::query_as(&format!(
+        "INSERT INTO alert_rules (owner, name, symbol, timeframe, expression, cooldown_secs, enabled)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING {}",
+        RULE_COLUMNS
+    ))
+    .bind(owner)
+    .bind(&request.name)
+    .bind(&request.symbol)
+    .bind(&request.timeframe)
+    .bind(&request.expression)
+    .bind(request.cooldown_secs as i32)
+    .bind(request.enabled)
+    .fetch_one(db)
+    .await?;
+    Ok(row.into())
+}
+
+/// Reemplaza la regla. Su estado se descarta: la nueva condición empieza de cero.
+pub async fn update(
+    db: &PgPool,
+    owner: &str,
+    id: i64,
+    request: &AlertRuleRequest,
+) -> Result<AlertRule, AlertError> {
+    let mut tx = db.begin().await?;
+    let row: RuleRow = sqlx::query_as(&format!(
+        "UPDATE alert_rules
+         SET name = $1, symbol = $2, timeframe = $3, expression = $4, cooldown_secs = $5,
+             enabled = $6, updated_at = now()
+         WHERE id = $7 AND owner = $8
+         RETURNING {}",
+        RULE_COLUMNS
+    ))
+    .bind(&request.name)
+    .bind(&request.symbol)
+    .bind(&request.timeframe)
+    .bind(&request.expression)
+    .bind(request.cooldown_secs as i32)
+    .bind(request.enabled)
+    .bind(id)
+    .bind(owner)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AlertError::NotFound)?;
+    sqlx::query("DELETE FROM alert_state WHERE rule_id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(row.into())
+}
+
+/// Elimina la regla; sus alertas disparadas se conservan en el registro
+pub async fn delete(db: &PgPool, owner: &str, id: i64) -> Result<(), AlertError> {
+    let deleted = sqlx::query("DELETE FROM alert_rules WHERE id = $1 AND owner = $2")
+        .bind(id)
+        .bind(owner)
+        .execute(db)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(AlertError::NotFound);
+    }
+    Ok(())
+}
+
+#[derive(FromRow)]
+struct FiredRow {
+    id: i64,
+    rule_id: Option<i64>,
+    rule_name: String,
+    expression: String,
+    symbol: String,
+    timeframe: String,
+    fired_at: DateTime<Utc>,
+    analysis: Json<AnalysisResponse>,
+    previous: Option<Json<AnalysisResponse>>,
+}
+
+/// Alertas disparadas de `owner`, la más reciente primero
+pub async fn fired(
+    db: &PgPool,
+    owner: &str,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<FiredAlert>, AlertError> {
+    let rows: Vec<FiredRow> = sqlx::query_as(
+        "SELECT id, rule_id, rule_name, expression, symbol, timeframe, fired_at, analysis, previous
+         FROM fired_alerts WHERE owner = $1
+         ORDER BY fired_at DESC, id DESC
+         LIMIT $2 OFFSET $3",
+    )
+    .bind(owner)
+    .bind(i64::from(limit))
+    .bind(i64::from(offset))
+    .fetch_all(db)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| FiredAlert {
+            id: row.id,
+            rule_id: row.rule_id,
+            rule_name: row.rule_name,
+            expression: row.expression,
+            symbol: row.symbol,
+            timeframe: row.timeframe,
+            fired_at: row.fired_at,
+            analysis: row.analysis.0,
+            previous: row.previous.map(|previous| previous.0),
+        })
+        .collect())
+}
+
+#[derive(FromRow)]
+struct MatchingRule {
+    id: i64,
+    owner: String,
+    name: String,
+    expression: String,
+    cooldown_secs: i32,
+}
+
+#[derive(FromRow)]
+struct StateRow {
+    previous: Json<AnalysisResponse>,
+    active: bool,
+    last_fired_at: Option<DateTime<Utc>>,
+}
+
+/// Evalúa las reglas habilitadas que aplican a un análisis nuevo de `symbol` en `timeframe`
+/// (mayúsculas y nombre canónico). Cada regla sólo se dispara cuando su condición pasa a
+/// cumplirse y no está en cooldown; cada disparo se encola para los webhooks suscritos a
+/// `alert_fired`. Devuelve cuántas alertas se dispararon.
+pub async fn evaluate(
+    db: &PgPool,
+    webhooks: &Webhooks,
+    symbol: &str,
+    timeframe: &str,
+    response: &AnalysisResponse,
+    analyzed_at: DateTime<Utc>,
+) -> Result<usize, sqlx::Error> {
+    let rules: Vec<MatchingRule> = sqlx::query_as(
+        "SELECT id, owner, name, expression, cooldown_secs FROM alert_rules
+         WHERE enabled
+           AND (symbol IS NULL OR symbol = $1)
+           AND (timeframe IS NULL OR timeframe = $2)
+         ORDER BY id",
+    )
+    .bind(symbol)
+    .bind(timeframe)
+    .fetch_all(db)
+    .await?;
+
+    let mut fired = 0;
+    for rule in rules {
+        // Las expresiones se validan al guardarlas; una que ya no parsee se ignora
+        let expression = match expr::parse(&rule.expression) {
+            Ok(expression) => expression,
+            Err(e) => {
+                warn!(rule_id = rule.id, error = %e, "alert rule skipped: invalid expression");
+                continue;
+            }
+        };
+
+        // Un advisory lock por regla, símbolo y timeframe ordena las evaluaciones
+        // concurrentes; `FOR UPDATE` no basta mientras la fila de estado no existe
+        let mut tx = db.begin().await?;
+        sqlx::query(
+            "SELECT pg_advisory_xact_lock(hashtextextended(format('alert:%s:%s:%s', $1, $2, $3), 0))",
+        )
+        .bind(rule.id)
+        .bind(symbol)
+        .bind(timeframe)
+        .execute(&mut *tx)
+        .await?;
+        let state: Option<StateRow> = sqlx::query_as(
+            "SELECT previous, active, last_fired_at FROM alert_state
+             WHERE rule_id = $1 AND symbol = $2 AND timeframe = $3",
+        )
+        .bind(rule.id)
+        .bind(symbol)
+        .bind(timeframe)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let previous = state.as_ref().map(|state| &state.previous.0);
+        let matches = expression.matches(response, previous);
+        let was_active = state.as_ref().is_some_and(|state| state.active);
+        let last_fired_at = state.as_ref().and_then(|state| state.last_fired_at);
+        let cooled_down = last_fired_at
+            .is_none_or(|at| (analyzed_at - at).num_seconds() >= i64::from(rule.cooldown_secs));
+        let fire = matches && !was_active && cooled_down;
+
+        sqlx::query(
+            "INSERT INTO alert_state (rule_id, symbol, timeframe, previous, active, last_fired_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (rule_id, symbol, timeframe) DO UPDATE
+             SET previous = EXCLUDED.previous, active = EXCLUDED.active,
+                 last_fired_at = EXCLUDED.last_fired_at",
+        )
+        .bind(rule.id)
+        .bind(symbol)
+        .bind(timeframe)
+        .bind(Json(response))
+        .bind(matches)
+        .bind(if fire {
+            Some(analyzed_at)
+        } else {
+            last_fired_at
+        })
+        .execute(&mut *tx)
+        .await?;
+
+        if fire {
+            let id: i64 = sqlx::query_scalar(
+                "INSERT INTO fired_alerts
+                     (rule_id, owner, rule_name, expression, symbol, timeframe, fired_at, analysis,
+                      previous)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                 RETURNING id",
+            )
+            .bind(rule.id)
+            .bind(&rule.owner)
+            .bind(&rule.name)
+            .bind(&rule.expression)
+            .bind(symbol)
+            .bind(timeframe)
+            .bind(analyzed_at)
+            .bind(Json(response))
+            .bind(previous.map(Json))
+            .fetch_one(&mut *tx)
+            .await?;
+            let alert = FiredAlert {
+                id,
+                rule_id: Some(rule.id),
+                rule_name: rule.name.clone(),
+                expression: rule.expression.clone(),
+                symbol: symbol.to_string(),
+                timeframe: timeframe.to_string(),
+                fired_at: analyzed_at,
+                analysis: response.clone(),
+                previous: previous.cloned(),
+            };
+            let mut data = serde_json::to_value(&alert).unwrap_or_default();
+            data["owner"] = rule.owner.clone().into();
+            webhooks
+                .enqueue(&mut tx, WebhookEvent::AlertFired, &data)
+                .await?;
+            info!(
+                rule_id = rule.id,
+                owner = %rule.owner,
+                rule = %rule.name,
+                symbol,
+                timeframe,
+                "alert fired"
+            );
+            fired += 1;
+        }
+        tx.commit().await?;
+    }
+    if fired > 0 {
+        webhooks.wake();
+    }
+    Ok(fired)
+}
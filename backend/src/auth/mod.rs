@@ -0,0 +1,204 @@
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use axum::http::{header, HeaderMap};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use uuid::Uuid;
+
+use crate::config::Config;
+
+/// Cookie HttpOnly con la sesión del navegador
+pub const SESSION_COOKIE: &str = "ghost_session";
+
+/// Usuario del gateway tal como se guarda en USERS_FILE
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserRecord {
+    pub username: String,
+    /// Hash argon2 en formato PHC (`cargo run --bin hash-password`)
+    pub password_hash: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UsersFile {
+    #[serde(default)]
+    users: Vec<UserRecord>,
+}
+
+/// Usuarios locales con contraseñas hasheadas con argon2
+#[derive(Debug, Default)]
+pub struct UserStore {
+    users: HashMap<String, String>,
+}
+
+impl UserStore {
+    /// Parsea un fichero TOML con entradas `[[users]]`
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        let file: UsersFile =
+            toml::from_str(source).map_err(|e| anyhow::anyhow!("Invalid users file: {}", e))?;
+        let mut users = HashMap::new();
+        for user in file.users {
+            PasswordHash::new(&user.password_hash).map_err(|e| {
+                anyhow::anyhow!("Invalid password hash for user '{}': {}", user.username, e)
+            })?;
+            if users
+                .insert(user.username.clone(), user.password_hash)
+                .is_some()
+            {
+                return Err(anyhow::anyhow!("Duplicated user '{}'", user.username));
+            }
+        }
+        Ok(Self { users })
+    }
+
+    /// Carga los usuarios de `path`; sin fichero no hay usuarios (nadie puede iniciar sesión)
+    pub fn load(path: Option<&str>) -> anyhow::Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+        let source = std::fs::read_to_string(Path::new(path))
+            .map_err(|e| anyhow::anyhow!("Cannot read users file {}: {}", path, e))?;
+        Self::parse(&source)
+    }
+
+    /// Comprueba usuario y contraseña. Con usuarios inexistentes también se
+    /// verifica un hash para no revelar por timing qué usuarios existen.
+    pub fn verify(&self, username: &str, password: &str) -> bool {
+        let stored = self.users.get(username);
+        let hash = stored.map(String::as_str).unwrap_or_else(|| {
+            DUMMY_HASH.get_or_init(|| hash_password("dummy").unwrap_or_default())
+        });
+        let valid = PasswordHash::new(hash).is_ok_and(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        });
+        stored.is_some() && valid
+    }
+
+    pub fn len(&self) -> usize {
+        self.users.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+}
+
+/// Hash de relleno para igualar el coste de la verificación de usuarios inexistentes
+static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+
+/// Genera el hash argon2 (PHC) de una contraseña
+pub fn hash_password(password: &str) -> anyhow::Result<String> {
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>())
+        .map_err(|e| anyhow::anyhow!("Cannot generate salt: {}", e))?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| anyhow::anyhow!("Cannot hash password: {}", e))
+}
+
+/// Claims del JWT de sesión
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// Nombre de usuario
+    pub sub: String,
+    pub iat: i64,
+    pub exp: i64,
+    /// Identificador de la sesión, para poder revocarla en el logout
+    pub jti: String,
+}
+
+/// Usuario autenticado; `require_session` lo deja en las extensiones de la request
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub username: String,
+}
+
+/// Emite y valida los JWT de sesión (HS256 con AUTH_JWT_SECRET)
+pub struct SessionManager {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    ttl_secs: i64,
+    /// Sesiones cerradas con logout antes de expirar: jti → exp
+    revoked: Mutex<HashMap<String, i64>>,
+}
+
+impl SessionManager {
+    pub fn new(secret: &str, ttl_secs: u64) -> Self {
+        Self {
+            encoding: EncodingKey::from_secret(secret.as_bytes()),
+            decoding: DecodingKey::from_secret(secret.as_bytes()),
+            ttl_secs: ttl_secs as i64,
+            revoked: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            config.auth_jwt_secret.as_deref().unwrap_or_default(),
+            config.auth_session_ttl_secs,
+        )
+    }
+
+    /// Emite un token para `username`
+    pub fn issue(&self, username: &str) -> anyhow::Result<(String, Claims)> {
+        let now = chrono::Utc::now().timestamp();
+        let claims = Claims {
+            sub: username.to_string(),
+            iat: now,
+            exp: now + self.ttl_secs,
+            jti: Uuid::new_v4().to_string(),
+        };
+        let token = encode(&Header::default(), &claims, &self.encoding)?;
+        Ok((token, claims))
+    }
+
+    /// Valida firma, expiración y revocación del token
+    pub fn validate(&self, token: &str) -> Option<Claims> {
+        let mut validation = Validation::default();
+        validation.leeway = 0;
+        let claims = decode::<Claims>(token, &self.decoding, &validation)
+            .ok()?
+            .claims;
+        let revoked = self.revoked.lock().unwrap_or_else(|e| e.into_inner());
+        (!revoked.contains_key(&claims.jti)).then_some(claims)
+    }
+
+    /// Revoca la sesión hasta su expiración natural
+    pub fn revoke(&self, claims: &Claims) {
+        let now = chrono::Utc::now().timestamp();
+        let mut revoked = self.revoked.lock().unwrap_or_else(|e| e.into_inner());
+        revoked.retain(|_, exp| *exp > now);
+        revoked.insert(claims.jti.clone(), claims.exp);
+    }
+
+    pub fn ttl_secs(&self) -> i64 {
+        self.ttl_secs
+    }
+}
+
+/// Extrae el token de sesión de `Authorization: Bearer` o de la cookie de sesión
+pub fn session_token(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim);
+    bearer.or_else(|| {
+        headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .find(|(name, _)| *name == SESSION_COOKIE)
+            .map(|(_, value)| value)
+    })
+}
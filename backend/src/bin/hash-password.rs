@@ -0,0 +1,16 @@
+//! Genera el hash argon2 de una contraseña para el fichero de usuarios (USERS_FILE).
+//!
+//! Uso: `echo -n 'contraseña' | cargo run --bin hash-password`
+
+use std::io::Read;
+
+fn main() -> anyhow::Result<()> {
+    let mut password = String::new();
+    std::io::stdin().read_to_string(&mut password)?;
+    let password = password.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        anyhow::bail!("empty password: pipe it through stdin");
+    }
+    println!("{}", ghost_backend::auth::hash_password(password)?);
+    Ok(())
+}
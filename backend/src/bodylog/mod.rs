@@ -0,0 +1,292 @@
+use axum::body::{Body, HttpBody};
+use futures_util::StreamExt;
+use serde_json::Value;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::config::{BodyLogMode, Config};
+use crate::routes::RouteSpec;
+
+/// Valor que sustituye a los campos redactados
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Un tramo de una ruta JSON de redacción
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// `.campo`
+    Key(String),
+    /// `[n]`
+    Index(usize),
+    /// `.*` o `[*]`: todos los hijos
+    Wildcard,
+    /// `..campo`: el campo a cualquier profundidad
+    Descendant(String),
+}
+
+/// Ruta JSON (subconjunto de JSONPath) cuyos valores se redactan en los logs:
+/// `$.password`, `$.accounts[*].iban`, `$.items[0].token`, `$..api_key`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactPath {
+    segments: Vec<Segment>,
+}
+
+impl FromStr for RedactPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rest = s
+            .trim()
+            .strip_prefix('$')
+            .ok_or_else(|| format!("'{}' must start with '$'", s))?;
+        let mut segments = Vec::new();
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("..") {
+                let (name, tail) = split_name(after);
+                if name.is_empty() {
+                    return Err(format!("'{}' has an empty field after '..'", s));
+                }
+                segments.push(Segment::Descendant(name.to_string()));
+                rest = tail;
+            } else if let Some(after) = rest.strip_prefix('.') {
+                let (name, tail) = split_name(after);
+                segments.push(match name {
+                    "" => return Err(format!("'{}' has an empty field after '.'", s)),
+                    "*" => Segment::Wildcard,
+                    name => Segment::Key(name.to_string()),
+                });
+                rest = tail;
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let (index, tail) = after
+                    .split_once(']')
+                    .ok_or_else(|| format!("'{}' has an unclosed '['", s))?;
+                segments.push(match index.trim() {
+                    "*" => Segment::Wildcard,
+                    n => Segment::Index(
+                        n.parse()
+                            .map_err(|_| format!("'{}' has an invalid index '[{}]'", s, n))?,
+                    ),
+                });
+                rest = tail;
+            } else {
+                return Err(format!("'{}' is not a valid path near '{}'", s, rest));
+            }
+        }
+
+        if segments.is_empty() {
+            return Err(format!("'{}' does not select any field", s));
+        }
+        Ok(Self { segments })
+    }
+}
+
+/// Separa un nombre de campo del resto de la ruta
+fn split_name(path: &str) -> (&str, &str) {
+    let end = path.find(['.', '[']).unwrap_or(path.len());
+    path.split_at(end)
+}
+
+impl RedactPath {
+    /// Sustituye por `[REDACTED]` todos los valores de `value` que selecciona la ruta
+    pub fn apply(&self, value: &mut Value) {
+        redact(value, &self.segments);
+    }
+}
+
+fn redact(value: &mut Value, path: &[Segment]) {
+    let Some((segment, rest)) = path.split_first() else {
+        *value = Value::String(REDACTED.to_string());
+        return;
+    };
+    match segment {
+        Segment::Key(key) => {
+            if let Some(child) = value.get_mut(key.as_str()) {
+                redact(child, rest);
+            }
+        }
+        Segment::Index(index) => {
+            if let Some(child) = value.get_mut(*index) {
+                redact(child, rest);
+            }
+        }
+        Segment::Wildcard => children(value).for_each(|child| redact(child, rest)),
+        Segment::Descendant(key) => {
+            if let Some(child) = value.get_mut(key.as_str()) {
+                redact(child, rest);
+            }
+            children(value).for_each(|child| redact(child, path));
+        }
+    }
+}
+
+fn children(value: &mut Value) -> Box<dyn Iterator<Item = &mut Value> + '_> {
+    match value {
+        Value::Object(map) => Box::new(map.values_mut()),
+        Value::Array(items) => Box::new(items.iter_mut()),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+/// Política de logging de bodies del proxy (LOG_BODIES y compañía)
+pub struct BodyLogPolicy {
+    mode: BodyLogMode,
+    sample_rate: f64,
+    truncate_bytes: usize,
+    max_bytes: usize,
+    redact: Vec<RedactPath>,
+}
+
+impl BodyLogPolicy {
+    /// Las rutas de redacción ya están validadas por `Config::validate`
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            mode: config.log_bodies,
+            sample_rate: config.log_body_sample_rate,
+            truncate_bytes: config.log_body_truncate_bytes,
+            max_bytes: config.log_body_max_bytes,
+            redact: config
+                .log_redact_paths
+                .iter()
+                .filter_map(|path| path.parse().ok())
+                .collect(),
+        }
+    }
+
+    /// Modo de logging para una request de `route`, ya aplicado el muestreo
+    pub fn mode_for(&self, route: &RouteSpec) -> BodyLogMode {
+        let mode = route.log_bodies.unwrap_or(self.mode);
+        if mode == BodyLogMode::Off || rand::random::<f64>() >= self.sample_rate {
+            return BodyLogMode::Off;
+        }
+        mode
+    }
+
+    /// Envuelve `body` para registrarlo (redactado y recortado) cuando termine de
+    /// transmitirse. Sólo se retienen en memoria los primeros LOG_BODY_MAX_BYTES.
+    pub fn capture(self: &Arc<Self>, body: Body, mode: BodyLogMode, context: LogContext) -> Body {
+        // Un body vacío se deja intacto: `proxy()` usa `is_end_stream` para decidir reintentos
+        if mode == BodyLogMode::Off || body.is_end_stream() {
+            return body;
+        }
+        let mut capture = BodyCapture {
+            policy: self.clone(),
+            mode,
+            context,
+            captured: Vec::new(),
+            total: 0,
+        };
+        Body::from_stream(body.into_data_stream().map(move |chunk| {
+            if let Ok(chunk) = &chunk {
+                capture.push(chunk);
+            }
+            chunk
+        }))
+    }
+
+    /// Texto que se registra para un body: JSON redactado, texto plano o un marcador.
+    /// `captured` son los primeros bytes de un body de `total` bytes.
+    pub fn render(
+        &self,
+        captured: &[u8],
+        total: usize,
+        content_type: Option<&str>,
+        mode: BodyLogMode,
+    ) -> String {
+        let content_type = content_type.unwrap_or_default().to_ascii_lowercase();
+        let looks_json = matches!(
+            captured.iter().find(|b| !b.is_ascii_whitespace()),
+            Some(b'{') | Some(b'[')
+        );
+
+        let text = if content_type.contains("json") || (content_type.is_empty() && looks_json) {
+            if captured.len() < total {
+                // Un JSON incompleto no se puede redactar: no se registra
+                return format!("<omitted: {} bytes exceed LOG_BODY_MAX_BYTES>", total);
+            }
+            match serde_json::from_slice::<Value>(captured) {
+                Ok(mut value) => {
+                    self.redact.iter().for_each(|path| path.apply(&mut value));
+                    value.to_string()
+                }
+                Err(_) => return format!("<omitted: {} bytes of invalid JSON>", total),
+            }
+        } else if content_type.starts_with("application/x-www-form-urlencoded")
+            || content_type.starts_with("multipart/")
+        {
+            return format!("<omitted: {} bytes of form data>", total);
+        } else {
+            match std::str::from_utf8(captured) {
+                Ok(text) => text.to_string(),
+                // El corte de LOG_BODY_MAX_BYTES puede partir un carácter multibyte
+                Err(e) if captured.len() < total && e.error_len().is_none() => {
+                    String::from_utf8_lossy(&captured[..e.valid_up_to()]).into_owned()
+                }
+                Err(_) => return format!("<binary: {} bytes>", total),
+            }
+        };
+
+        let limit = match mode {
+            BodyLogMode::Truncated => self.truncate_bytes,
+            _ => self.max_bytes,
+        };
+        if text.len() <= limit && captured.len() == total {
+            return text;
+        }
+        let mut end = limit.min(text.len());
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}… ({} bytes)", &text[..end], total)
+    }
+}
+
+/// Datos de la request que acompañan al body en el log
+#[derive(Debug, Clone)]
+pub struct LogContext {
+    pub trace_id: String,
+    pub route: String,
+    /// `request` o `response`
+    pub direction: &'static str,
+    pub content_type: Option<String>,
+}
+
+/// Acumula el principio de un body y lo registra al soltarse (fin del stream o cancelación)
+struct BodyCapture {
+    policy: Arc<BodyLogPolicy>,
+    mode: BodyLogMode,
+    context: LogContext,
+    captured: Vec<u8>,
+    total: usize,
+}
+
+impl BodyCapture {
+    fn push(&mut self, chunk: &[u8]) {
+        self.total += chunk.len();
+        let room = self.policy.max_bytes.saturating_sub(self.captured.len());
+        self.captured
+            .extend_from_slice(&chunk[..room.min(chunk.len())]);
+    }
+}
+
+impl Drop for BodyCapture {
+    fn drop(&mut self) {
+        if self.total == 0 {
+            return;
+        }
+        let body = self.policy.render(
+            &self.captured,
+            self.total,
+            self.context.content_type.as_deref(),
+            self.mode,
+        );
+        info!(
+            trace_id = %self.context.trace_id,
+            route = %self.context.route,
+            direction = self.context.direction,
+            bytes = self.total,
+            body = %body,
+            "proxied body"
+        );
+    }
+}
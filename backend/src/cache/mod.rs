@@ -0,0 +1,328 @@
+use axum::{
+    body::{to_bytes, Body, BodyDataStream, Bytes},
+    http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode},
+    response::Response,
+};
+use futures_util::StreamExt;
+use lru::LruCache;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::config::Config;
+use crate::proxy::{payload_too_large, proxy};
+use crate::routes::RouteSpec;
+use crate::trace::TraceContext;
+use crate::AppState;
+
+/// Cabecera que indica si la respuesta salió de la caché (`HIT`) o de inBestia (`MISS`)
+pub const X_CACHE: &str = "x-cache";
+
+/// Respuesta de inBestia guardada en caché
+#[derive(Clone)]
+struct CachedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    stored_at: Instant,
+    ttl: Duration,
+}
+
+impl CachedResponse {
+    fn is_expired(&self) -> bool {
+        self.stored_at.elapsed() >= self.ttl
+    }
+}
+
+struct CacheState {
+    entries: LruCache<String, CachedResponse>,
+    bytes: usize,
+}
+
+/// Caché LRU acotada por número de entradas y por bytes totales.
+///
+/// La clave es `MÉTODO ruta?query #hash-del-body`, con el body JSON normalizado
+/// (claves ordenadas, sin espacios) para que peticiones equivalentes compartan entrada.
+pub struct ResponseCache {
+    state: Mutex<CacheState>,
+    max_entries: usize,
+    max_bytes: usize,
+    max_entry_bytes: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Resumen de una entrada para el endpoint de administración
+#[derive(Debug, Serialize)]
+pub struct CacheEntryInfo {
+    pub key: String,
+    pub size_bytes: usize,
+    pub expires_in_secs: u64,
+}
+
+/// Estado de la caché para el endpoint de administración
+#[derive(Debug, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub bytes: usize,
+    pub max_entries: usize,
+    pub max_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub keys: Vec<CacheEntryInfo>,
+}
+
+impl ResponseCache {
+    pub fn new(max_entries: usize, max_bytes: usize, max_entry_bytes: usize) -> Self {
+        Self {
+            state: Mutex::new(CacheState {
+                entries: LruCache::unbounded(),
+                bytes: 0,
+            }),
+            max_entries,
+            max_bytes,
+            max_entry_bytes: max_entry_bytes.min(max_bytes),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            config.cache_max_entries,
+            config.cache_max_bytes,
+            config.cache_max_entry_bytes,
+        )
+    }
+
+    /// Calcula la clave de caché de una request
+    pub fn key(method: &Method, path_and_query: &str, body: &[u8]) -> String {
+        if body.is_empty() {
+            return format!("{} {}", method, path_and_query);
+        }
+        // JSON normalizado: serde_json ordena las claves de los objetos al re-serializar
+        let normalized = serde_json::from_slice::<serde_json::Value>(body)
+            .ok()
+            .and_then(|value| serde_json::to_vec(&value).ok());
+        let digest = Sha256::digest(normalized.as_deref().unwrap_or(body));
+        format!("{} {} #{}", method, path_and_query, hex::encode(digest))
+    }
+
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = match state.entries.get(key) {
+            Some(entry) if !entry.is_expired() => Some(entry.clone()),
+            Some(_) => {
+                if let Some(expired) = state.entries.pop(key) {
+                    state.bytes -= expired.body.len();
+                }
+                None
+            }
+            None => None,
+        };
+        match entry {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        entry
+    }
+
+    fn insert(&self, key: String, entry: CachedResponse) {
+        if entry.body.len() > self.max_entry_bytes {
+            return;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.bytes += entry.body.len();
+        if let Some(previous) = state.entries.put(key, entry) {
+            state.bytes -= previous.body.len();
+        }
+        while state.entries.len() > self.max_entries || state.bytes > self.max_bytes {
+            match state.entries.pop_lru() {
+                Some((_, evicted)) => state.bytes -= evicted.body.len(),
+                None => break,
+            }
+        }
+    }
+
+    /// Elimina las entradas cuya clave es `key` o empieza por `prefix`;
+    /// sin ninguno de los dos vacía la caché. Devuelve cuántas se eliminaron.
+    pub fn purge(&self, key: Option<&str>, prefix: Option<&str>) -> usize {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let doomed: Vec<String> = state
+            .entries
+            .iter()
+            .map(|(k, _)| k)
+            .filter(|k| match (key, prefix) {
+                (None, None) => true,
+                (key, prefix) => {
+                    key.is_some_and(|key| key == k.as_str())
+                        || prefix.is_some_and(|prefix| k.starts_with(prefix))
+                }
+            })
+            .cloned()
+            .collect();
+        for k in &doomed {
+            if let Some(entry) = state.entries.pop(k) {
+                state.bytes -= entry.body.len();
+            }
+        }
+        doomed.len()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        CacheStats {
+            entries: state.entries.len(),
+            bytes: state.bytes,
+            max_entries: self.max_entries,
+            max_bytes: self.max_bytes,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            keys: state
+                .entries
+                .iter()
+                .map(|(key, entry)| CacheEntryInfo {
+                    key: key.clone(),
+                    size_bytes: entry.body.len(),
+                    expires_in_secs: entry
+                        .ttl
+                        .saturating_sub(entry.stored_at.elapsed())
+                        .as_secs(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Sirve la request desde la caché o, si no está, la reenvía con `proxy()` y guarda
+/// la respuesta mientras se transmite al cliente. Sólo cachea GET y POST con 200.
+pub async fn cached_proxy(
+    s: AppState,
+    req: Request<Body>,
+    route: &RouteSpec,
+    ttl: Duration,
+) -> Response {
+    if req.method() != Method::GET && req.method() != Method::POST {
+        return proxy(s, req, route).await;
+    }
+
+    let max_size = route.body_limit(&s.settings().config);
+    let (parts, body) = req.into_parts();
+    let body = match to_bytes(body, max_size).await {
+        Ok(body) => body,
+        Err(_) => {
+            let trace = parts.extensions.get::<TraceContext>();
+            return payload_too_large(max_size, trace.map_or("", |t| t.trace_id.as_str()));
+        }
+    };
+    let path_q = parts
+        .uri
+        .path_and_query()
+        .map(|x| x.as_str())
+        .unwrap_or("/");
+    let key = ResponseCache::key(&parts.method, path_q, &body);
+
+    if let Some(hit) = s.cache.get(&key) {
+        debug!(key = %key, "cache hit");
+        let mut response = Response::new(Body::from(hit.body));
+        *response.status_mut() = hit.status;
+        *response.headers_mut() = hit.headers;
+        response.headers_mut().insert(
+            header::AGE,
+            HeaderValue::from(hit.stored_at.elapsed().as_secs()),
+        );
+        response
+            .headers_mut()
+            .insert(X_CACHE, HeaderValue::from_static("HIT"));
+        return response;
+    }
+
+    let response = proxy(
+        s.clone(),
+        Request::from_parts(parts, Body::from(body)),
+        route,
+    )
+    .await;
+    let (mut parts, body) = response.into_parts();
+    parts
+        .headers
+        .insert(X_CACHE, HeaderValue::from_static("MISS"));
+
+    let no_store = parts
+        .headers
+        .get(header::CACHE_CONTROL)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("no-store"));
+    if parts.status != StatusCode::OK || no_store {
+        return Response::from_parts(parts, body);
+    }
+
+    let mut headers = parts.headers.clone();
+    headers.remove(X_CACHE);
+    let tee = Tee {
+        stream: body.into_data_stream(),
+        buffer: Some(Vec::new()),
+        cache: s.cache.clone(),
+        key,
+        status: parts.status,
+        headers,
+        ttl,
+    };
+    Response::from_parts(
+        parts,
+        Body::from_stream(futures_util::stream::unfold(tee, Tee::next)),
+    )
+}
+
+/// Copia el body mientras se transmite; al terminar sin errores lo guarda en la caché
+struct Tee {
+    stream: BodyDataStream,
+    /// `None` si el body superó `max_entry_bytes` o falló: ya no se cachea
+    buffer: Option<Vec<u8>>,
+    cache: Arc<ResponseCache>,
+    key: String,
+    status: StatusCode,
+    headers: HeaderMap,
+    ttl: Duration,
+}
+
+impl Tee {
+    async fn next(mut self) -> Option<(Result<Bytes, axum::Error>, Self)> {
+        match self.stream.next().await {
+            Some(Ok(chunk)) => {
+                if let Some(buffer) = self.buffer.as_mut() {
+                    if buffer.len() + chunk.len() > self.cache.max_entry_bytes {
+                        self.buffer = None;
+                    } else {
+                        buffer.extend_from_slice(&chunk);
+                    }
+                }
+                Some((Ok(chunk), self))
+            }
+            Some(Err(e)) => {
+                self.buffer = None;
+                Some((Err(e), self))
+            }
+            None => {
+                if let Some(buffer) = self.buffer.take() {
+                    self.cache.insert(
+                        self.key,
+                        CachedResponse {
+                            status: self.status,
+                            headers: self.headers,
+                            body: Bytes::from(buffer),
+                            stored_at: Instant::now(),
+                            ttl: self.ttl,
+                        },
+                    );
+                }
+                None
+            }
+        }
+    }
+}
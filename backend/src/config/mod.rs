@@ -1,51 +1,617 @@
 use serde::{Deserialize, Serialize};
-use std::env;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::bodylog::RedactPath;
+use crate::jobs::{self, JobSpec};
+use crate::ratelimit::{ANALYSIS_CLASS, LOGIN_RULES};
+use crate::routes::{self, RouteSpec};
+use crate::webhooks::{self, WebhookSpec};
+
+mod secret;
+mod sources;
+
+pub use secret::Secret;
+pub use sources::{parse_override, ConfigSources};
+use sources::Loader;
+
+/// Estrategia para elegir réplica de inBestia en cada request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpstreamStrategy {
+    RoundRobin,
+    LeastLatency,
+}
+
+impl FromStr for UpstreamStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "round_robin" => Ok(Self::RoundRobin),
+            "least_latency" => Ok(Self::LeastLatency),
+            other => Err(format!("unknown strategy '{}' (expected round_robin or least_latency)", other)),
+        }
+    }
+}
+
+/// Qué se registra de los bodies que atraviesan el proxy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyLogMode {
+    /// Nada
+    Off,
+    /// Los primeros LOG_BODY_TRUNCATE_BYTES
+    Truncated,
+    /// El body entero, hasta LOG_BODY_MAX_BYTES
+    Full,
+}
+
+impl FromStr for BodyLogMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "truncated" => Ok(Self::Truncated),
+            "full" => Ok(Self::Full),
+            other => Err(format!("unknown body log mode '{}' (expected off, truncated or full)", other)),
+        }
+    }
+}
+
+/// Límite de un token bucket: `requests` cada `period_secs` (también es la ráfaga máxima)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitRule {
+    pub requests: u32,
+    pub period_secs: u64,
+}
+
+impl FromStr for RateLimitRule {
+    type Err = String;
+
+    /// Formato `requests/segundos`, p.ej. `20/60`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (requests, period) = s
+            .split_once('/')
+            .ok_or_else(|| format!("'{}' must be requests/seconds", s))?;
+        Ok(Self {
+            requests: requests.trim().parse().map_err(|_| format!("invalid request count in '{}'", s))?,
+            period_secs: period.trim().parse().map_err(|_| format!("invalid period in '{}'", s))?,
+        })
+    }
+}
+
+/// `Debug` se implementa aparte sobre `redacted` para que los secretos no acaben en logs
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Config {
-    pub api_base_url: String,
-    pub api_key: String,
+    /// Réplicas de inBestia (INBESTIA_API_URL admite varias separadas por comas)
+    pub api_base_urls: Vec<String>,
+    /// Clave principal de inBestia (INBESTIA_API_KEY o la primera de INBESTIA_API_KEY_FILE)
+    pub api_key: Secret,
+    /// Claves que se prueban, en orden, cuando inBestia rechaza la principal con 401
+    pub api_key_fallbacks: Vec<Secret>,
     pub bind_address: String,
     pub request_timeout_secs: u64,
     pub max_request_size: usize,
     pub cors_allowed_origins: Vec<String>,
     pub log_level: String,
+    /// Cabeceras del cliente que se reenvían a inBestia (vacío = todas salvo las denegadas)
+    pub proxy_request_headers_allow: Vec<String>,
+    /// Cabeceras del cliente que nunca llegan a inBestia
+    pub proxy_request_headers_deny: Vec<String>,
+    /// Cabeceras de inBestia que se devuelven al cliente (vacío = todas salvo las denegadas)
+    pub proxy_response_headers_allow: Vec<String>,
+    /// Cabeceras de inBestia que nunca llegan al cliente
+    pub proxy_response_headers_deny: Vec<String>,
+    /// Activa la caché de respuestas delante del proxy
+    pub cache_enabled: bool,
+    /// Número máximo de respuestas en caché
+    pub cache_max_entries: usize,
+    /// Tamaño total máximo de la caché en bytes
+    pub cache_max_bytes: usize,
+    /// Respuestas mayores que esto no se guardan en caché
+    pub cache_max_entry_bytes: usize,
+    /// Token para los endpoints `/api/admin/*` (sin token quedan deshabilitados)
+    pub admin_token: Option<String>,
+    /// Fallos consecutivos que abren el circuito de una ruta
+    pub breaker_failure_threshold: u32,
+    /// Segundos que el circuito permanece abierto antes de pasar a half-open
+    pub breaker_open_secs: u64,
+    /// Requests de prueba permitidas simultáneamente en half-open
+    pub breaker_half_open_max_calls: u32,
+    /// Reintentos para requests idempotentes (0 = sin reintentos)
+    pub retry_max_retries: u32,
+    /// Espera base del backoff exponencial en milisegundos
+    pub retry_base_delay_ms: u64,
+    /// Espera máxima entre reintentos en milisegundos
+    pub retry_max_delay_ms: u64,
+    /// Cómo se reparte el tráfico entre réplicas
+    pub upstream_strategy: UpstreamStrategy,
+    /// Intervalo de los health checks activos de cada réplica
+    pub upstream_health_interval_secs: u64,
+    /// Fallos consecutivos para sacar una réplica del pool
+    pub upstream_unhealthy_threshold: u32,
+    /// Health checks correctos consecutivos para readmitirla
+    pub upstream_healthy_threshold: u32,
+    /// Muestras de health check guardadas por réplica para `/api/health/history`
+    pub upstream_health_history: usize,
+    /// Rutas reenviadas a inBestia (manifiesto ROUTES_FILE o el embebido por defecto)
+    pub routes: Vec<RouteSpec>,
+    /// Fichero TOML con los usuarios del gateway y sus hashes argon2
+    pub users_file: Option<String>,
+    /// Secreto HS256 para firmar los JWT de sesión (mínimo 32 bytes)
+    pub auth_jwt_secret: Option<String>,
+    /// Duración de una sesión en segundos
+    pub auth_session_ttl_secs: u64,
+    /// Marca la cookie de sesión como `Secure` (sólo se envía por HTTPS o localhost)
+    pub auth_cookie_secure: bool,
+    /// Activa el rate limiting y las cuotas diarias
+    pub rate_limit_enabled: bool,
+    /// Token bucket por clase de ruta (`rate_limit_class` del manifiesto)
+    pub rate_limit_classes: BTreeMap<String, RateLimitRule>,
+    /// Requests diarias por cliente y clase; las clases sin entrada no tienen cuota
+    pub daily_quotas: BTreeMap<String, u64>,
+    /// Fichero donde se guardan los contadores de cuota (sin fichero no sobreviven reinicios)
+    pub quota_file: Option<String>,
+    /// API keys de clientes (`nombre=clave`) que identifican al cliente en el rate limiting
+    pub client_api_keys: BTreeMap<String, String>,
+    /// Logging de bodies del proxy (cada ruta puede sobrescribirlo con `log_bodies`)
+    pub log_bodies: BodyLogMode,
+    /// Fracción de requests (0.0-1.0) cuyos bodies se registran
+    pub log_body_sample_rate: f64,
+    /// Bytes registrados en modo `truncated`
+    pub log_body_truncate_bytes: usize,
+    /// Máximo de bytes retenidos y registrados por body en cualquier modo
+    pub log_body_max_bytes: usize,
+    /// Rutas JSON (`$.password`, `$..token`) cuyos valores se redactan en los logs
+    pub log_redact_paths: Vec<String>,
+    /// Máximo de símbolos × timeframes por petición a `/api/batch/analyze`
+    pub batch_max_items: usize,
+    /// Análisis de un lote que se lanzan a inBestia a la vez
+    pub batch_concurrency: usize,
+    /// Cada cuánto se sondean health, métricas y proveedores para `/api/live`
+    pub live_poll_interval_secs: u64,
+    /// Intervalo de los heartbeats de las conexiones de `/api/live`
+    pub live_heartbeat_secs: u64,
+    /// Eventos recientes que se guardan para reenviar a quien reconecta con Last-Event-ID
+    pub live_replay_events: usize,
+    /// PostgreSQL (`postgres://...`); sin ella no hay watchlists
+    pub database_url: Option<String>,
+    pub database_max_connections: u32,
+    /// Espera máxima por una conexión libre del pool
+    pub database_acquire_timeout_secs: u64,
+    /// Guarda cada análisis en `analysis_history` (requiere DATABASE_URL)
+    pub history_enabled: bool,
+    /// Jobs programados (manifiesto JOBS_FILE; sin él no hay ninguno)
+    pub jobs: Vec<JobSpec>,
+    /// Ejecuciones recientes que se guardan por job para `/api/jobs`
+    pub jobs_history_size: usize,
+    /// Webhooks salientes (manifiesto WEBHOOKS_FILE; requiere DATABASE_URL)
+    pub webhooks: Vec<WebhookSpec>,
+    /// Intentos por entrega antes de pasarla a dead letter
+    pub webhook_max_attempts: u32,
+    pub webhook_retry_base_delay_ms: u64,
+    pub webhook_retry_max_delay_ms: u64,
+    /// Timeout de cada POST a un webhook
+    pub webhook_timeout_secs: u64,
+    /// Cada cuánto se consulta el estado de los proveedores para `provider_status_changed`
+    pub webhook_providers_poll_secs: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            api_base_urls: Vec::new(),
+            api_key: Secret::default(),
+            api_key_fallbacks: Vec::new(),
+            bind_address: "127.0.0.1:8085".to_string(),
+            request_timeout_secs: 30,
+            max_request_size: 2 * 1024 * 1024, // 2MB
+            cors_allowed_origins: vec![
+                "http://127.0.0.1:3001".to_string(),
+                "http://localhost:3001".to_string(),
+            ],
+            log_level: "info".to_string(),
+            proxy_request_headers_allow: Vec::new(),
+            // El gateway es quien autentica contra inBestia: nunca reenviar credenciales del navegador
+            proxy_request_headers_deny: ["host", "authorization", "cookie", "x-api-key"]
+                .map(String::from)
+                .to_vec(),
+            proxy_response_headers_allow: Vec::new(),
+            proxy_response_headers_deny: vec!["set-cookie".to_string()],
+            cache_enabled: true,
+            cache_max_entries: 1024,
+            cache_max_bytes: 64 * 1024 * 1024, // 64MB
+            cache_max_entry_bytes: 4 * 1024 * 1024, // 4MB
+            admin_token: None,
+            breaker_failure_threshold: 5,
+            breaker_open_secs: 30,
+            breaker_half_open_max_calls: 1,
+            retry_max_retries: 2,
+            retry_base_delay_ms: 100,
+            retry_max_delay_ms: 2000,
+            upstream_strategy: UpstreamStrategy::RoundRobin,
+            upstream_health_interval_secs: 10,
+            upstream_unhealthy_threshold: 3,
+            upstream_healthy_threshold: 2,
+            upstream_health_history: 8640, // 24h a 10s por muestra
+            routes: routes::parse_manifest(routes::DEFAULT_MANIFEST, "routes.toml")
+                .expect("embedded routes.toml must be valid"),
+            users_file: None,
+            auth_jwt_secret: None,
+            auth_session_ttl_secs: 8 * 60 * 60, // 8h
+            auth_cookie_secure: true,
+            rate_limit_enabled: true,
+            rate_limit_classes: [
+                ("default", RateLimitRule { requests: 120, period_secs: 60 }),
+                ("analysis", RateLimitRule { requests: 20, period_secs: 60 }),
+            ]
+            .into_iter()
+            .chain(LOGIN_RULES)
+            .map(|(class, rule)| (class.to_string(), rule))
+            .collect(),
+            daily_quotas: BTreeMap::from([("analysis".to_string(), 1000)]),
+            quota_file: Some("quota_usage.json".to_string()),
+            client_api_keys: BTreeMap::new(),
+            log_bodies: BodyLogMode::Off,
+            log_body_sample_rate: 1.0,
+            log_body_truncate_bytes: 1024,
+            log_body_max_bytes: 64 * 1024, // 64KB
+            log_redact_paths: ["$..password", "$..token", "$..api_key", "$..secret", "$..authorization"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            batch_max_items: 20, // un lote entero cabe en el bucket de `analysis`
+            batch_concurrency: 4,
+            live_poll_interval_secs: 5,
+            live_heartbeat_secs: 15,
+            live_replay_events: 256,
+            database_url: None,
+            database_max_connections: 5,
+            database_acquire_timeout_secs: 5,
+            history_enabled: true,
+            jobs: Vec::new(),
+            jobs_history_size: 50,
+            webhooks: Vec::new(),
+            webhook_max_attempts: 8,
+            webhook_retry_base_delay_ms: 10_000,
+            webhook_retry_max_delay_ms: 60 * 60 * 1000, // 1h
+            webhook_timeout_secs: 10,
+            webhook_providers_poll_secs: 60,
+        }
+    }
 }
 
 impl Config {
+    /// Configuración del entorno del proceso y del fichero CONFIG_FILE, ya validada
     pub fn from_env() -> anyhow::Result<Self> {
-        Ok(Self {
-            api_base_url: env::var("INBESTIA_API_URL")
-                .map_err(|_| anyhow::anyhow!("INBESTIA_API_URL environment variable is required"))?,
-            api_key: env::var("INBESTIA_API_KEY")
-                .map_err(|_| anyhow::anyhow!("INBESTIA_API_KEY environment variable is required"))?,
-            bind_address: env::var("BIND_ADDR").unwrap_or_else(|_| "127.0.0.1:8085".to_string()),
-            request_timeout_secs: env::var("REQUEST_TIMEOUT")
-                .unwrap_or_else(|_| "30".to_string())
-                .parse()
-                .unwrap_or(30),
-            max_request_size: env::var("MAX_REQUEST_SIZE")
-                .unwrap_or_else(|_| "2097152".to_string()) // 2MB
-                .parse()
-                .unwrap_or(2 * 1024 * 1024),
-            cors_allowed_origins: env::var("CORS_ALLOWED_ORIGINS")
-                .unwrap_or_else(|_| "http://127.0.0.1:3001,http://localhost:3001".to_string())
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .collect(),
-            log_level: env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string()),
-        })
+        Self::load(&ConfigSources::from_env(None))
+    }
+
+    /// Configuración de `sources` sobre los valores por defecto, ya validada. El error
+    /// enumera todas las claves inválidas y todos los problemas de validación a la vez.
+    pub fn load(sources: &ConfigSources) -> anyhow::Result<Self> {
+        let defaults = Self::default();
+        let mut l = Loader::new(sources);
+        let (api_key, api_key_fallbacks) = load_api_keys(&mut l, defaults.api_key);
+
+        let config = Self {
+            api_base_urls: l.list("INBESTIA_API_URL", defaults.api_base_urls),
+            api_key,
+            api_key_fallbacks,
+            bind_address: l.string("BIND_ADDR", defaults.bind_address),
+            request_timeout_secs: l.value("REQUEST_TIMEOUT", defaults.request_timeout_secs),
+            max_request_size: l.value("MAX_REQUEST_SIZE", defaults.max_request_size),
+            cors_allowed_origins: l.list("CORS_ALLOWED_ORIGINS", defaults.cors_allowed_origins),
+            log_level: l.string("RUST_LOG", defaults.log_level),
+            proxy_request_headers_allow: l.list("PROXY_REQUEST_HEADERS_ALLOW", defaults.proxy_request_headers_allow),
+            proxy_request_headers_deny: l.list("PROXY_REQUEST_HEADERS_DENY", defaults.proxy_request_headers_deny),
+            proxy_response_headers_allow: l.list("PROXY_RESPONSE_HEADERS_ALLOW", defaults.proxy_response_headers_allow),
+            proxy_response_headers_deny: l.list("PROXY_RESPONSE_HEADERS_DENY", defaults.proxy_response_headers_deny),
+            cache_enabled: l.value("CACHE_ENABLED", defaults.cache_enabled),
+            cache_max_entries: l.value("CACHE_MAX_ENTRIES", defaults.cache_max_entries),
+            cache_max_bytes: l.value("CACHE_MAX_BYTES", defaults.cache_max_bytes),
+            cache_max_entry_bytes: l.value("CACHE_MAX_ENTRY_BYTES", defaults.cache_max_entry_bytes),
+            admin_token: l.optional("ADMIN_TOKEN", defaults.admin_token),
+            breaker_failure_threshold: l.value("BREAKER_FAILURE_THRESHOLD", defaults.breaker_failure_threshold),
+            breaker_open_secs: l.value("BREAKER_OPEN_SECS", defaults.breaker_open_secs),
+            breaker_half_open_max_calls: l.value("BREAKER_HALF_OPEN_MAX_CALLS", defaults.breaker_half_open_max_calls),
+            retry_max_retries: l.value("RETRY_MAX_RETRIES", defaults.retry_max_retries),
+            retry_base_delay_ms: l.value("RETRY_BASE_DELAY_MS", defaults.retry_base_delay_ms),
+            retry_max_delay_ms: l.value("RETRY_MAX_DELAY_MS", defaults.retry_max_delay_ms),
+            upstream_strategy: l.value("UPSTREAM_STRATEGY", defaults.upstream_strategy),
+            upstream_health_interval_secs: l.value("UPSTREAM_HEALTH_INTERVAL_SECS", defaults.upstream_health_interval_secs),
+            upstream_unhealthy_threshold: l.value("UPSTREAM_UNHEALTHY_THRESHOLD", defaults.upstream_unhealthy_threshold),
+            upstream_healthy_threshold: l.value("UPSTREAM_HEALTHY_THRESHOLD", defaults.upstream_healthy_threshold),
+            upstream_health_history: l.value("UPSTREAM_HEALTH_HISTORY", defaults.upstream_health_history),
+            routes: l.file("ROUTES_FILE", defaults.routes, routes::load_manifest),
+            users_file: l.optional("USERS_FILE", defaults.users_file),
+            auth_jwt_secret: l.optional("AUTH_JWT_SECRET", defaults.auth_jwt_secret),
+            auth_session_ttl_secs: l.value("AUTH_SESSION_TTL_SECS", defaults.auth_session_ttl_secs),
+            auth_cookie_secure: l.value("AUTH_COOKIE_SECURE", defaults.auth_cookie_secure),
+            rate_limit_enabled: l.value("RATE_LIMIT_ENABLED", defaults.rate_limit_enabled),
+            rate_limit_classes: l.map("RATE_LIMITS", defaults.rate_limit_classes),
+            daily_quotas: l.map("DAILY_QUOTAS", defaults.daily_quotas),
+            quota_file: l.optional("QUOTA_FILE", defaults.quota_file),
+            client_api_keys: l.map("CLIENT_API_KEYS", defaults.client_api_keys),
+            log_bodies: l.value("LOG_BODIES", defaults.log_bodies),
+            log_body_sample_rate: l.value("LOG_BODY_SAMPLE_RATE", defaults.log_body_sample_rate),
+            log_body_truncate_bytes: l.value("LOG_BODY_TRUNCATE_BYTES", defaults.log_body_truncate_bytes),
+            log_body_max_bytes: l.value("LOG_BODY_MAX_BYTES", defaults.log_body_max_bytes),
+            log_redact_paths: l.list("LOG_REDACT_PATHS", defaults.log_redact_paths),
+            batch_max_items: l.value("BATCH_MAX_ITEMS", defaults.batch_max_items),
+            batch_concurrency: l.value("BATCH_CONCURRENCY", defaults.batch_concurrency),
+            live_poll_interval_secs: l.value("LIVE_POLL_INTERVAL_SECS", defaults.live_poll_interval_secs),
+            live_heartbeat_secs: l.value("LIVE_HEARTBEAT_SECS", defaults.live_heartbeat_secs),
+            live_replay_events: l.value("LIVE_REPLAY_EVENTS", defaults.live_replay_events),
+            database_url: l.optional("DATABASE_URL", defaults.database_url),
+            database_max_connections: l.value("DATABASE_MAX_CONNECTIONS", defaults.database_max_connections),
+            database_acquire_timeout_secs: l.value("DATABASE_ACQUIRE_TIMEOUT_SECS", defaults.database_acquire_timeout_secs),
+            history_enabled: l.value("HISTORY_ENABLED", defaults.history_enabled),
+            jobs: l.file("JOBS_FILE", defaults.jobs, jobs::load_manifest),
+            jobs_history_size: l.value("JOBS_HISTORY_SIZE", defaults.jobs_history_size),
+            webhooks: l.file("WEBHOOKS_FILE", defaults.webhooks, webhooks::load_manifest),
+            webhook_max_attempts: l.value("WEBHOOK_MAX_ATTEMPTS", defaults.webhook_max_attempts),
+            webhook_retry_base_delay_ms: l.value("WEBHOOK_RETRY_BASE_DELAY_MS", defaults.webhook_retry_base_delay_ms),
+            webhook_retry_max_delay_ms: l.value("WEBHOOK_RETRY_MAX_DELAY_MS", defaults.webhook_retry_max_delay_ms),
+            webhook_timeout_secs: l.value("WEBHOOK_TIMEOUT_SECS", defaults.webhook_timeout_secs),
+            webhook_providers_poll_secs: l.value("WEBHOOK_PROVIDERS_POLL_SECS", defaults.webhook_providers_poll_secs),
+        };
+
+        let mut errors = l.finish();
+        errors.extend(config.problems());
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(invalid_configuration(&errors))
+        }
     }
 
+    /// Copia para `--print-config`: claves, tokens y contraseñas ocultos (las claves de
+    /// inBestia y los secretos de los webhooks son `Secret` y ya se ocultan solos)
+    pub fn redacted(&self) -> Self {
+        let hide = |secret: &Option<String>| secret.as_ref().map(|_| REDACTED.to_string());
+        let mut config = self.clone();
+        config.admin_token = hide(&self.admin_token);
+        config.auth_jwt_secret = hide(&self.auth_jwt_secret);
+        for key in config.client_api_keys.values_mut() {
+            *key = REDACTED.to_string();
+        }
+        config.database_url = self.database_url.as_deref().map(|url| match reqwest::Url::parse(url) {
+            Ok(mut url) if url.password().is_some() => {
+                let _ = url.set_password(Some(REDACTED));
+                url.to_string()
+            }
+            Ok(_) => url.to_string(),
+            Err(_) => REDACTED.to_string(),
+        });
+        config
+    }
+
+    /// Comprueba la configuración completa; el error enumera todos los problemas a la vez
     pub fn validate(&self) -> anyhow::Result<()> {
+        let errors = self.problems();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid_configuration(&errors))
+        }
+    }
+
+    /// Problemas de la configuración (vacío si es válida)
+    pub fn problems(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
         if self.api_key.is_empty() {
-            return Err(anyhow::anyhow!("API key cannot be empty"));
+            errors.push("INBESTIA_API_KEY is required".to_string());
+        }
+
+        if self.api_base_urls.is_empty() {
+            errors.push("INBESTIA_API_URL is required (at least one inBestia base URL)".to_string());
+        }
+
+        for (i, url) in self.api_base_urls.iter().enumerate() {
+            if !url.starts_with("http") {
+                errors.push("API base URL must start with http:// or https://".to_string());
+            }
+            if self.api_base_urls[..i].contains(url) {
+                errors.push(format!("Duplicated API base URL: {}", url));
+            }
+        }
+
+        for origin in &self.cors_allowed_origins {
+            let exact = reqwest::Url::parse(origin)
+                .is_ok_and(|url| url.origin().ascii_serialization() == *origin);
+            if origin != "*" && !exact {
+                errors.push(format!("Invalid CORS_ALLOWED_ORIGINS entry '{}': expected '*' or scheme://host[:port]", origin));
+            }
+        }
+
+        if self.bind_address.parse::<std::net::SocketAddr>().is_err() {
+            errors.push(format!("BIND_ADDR must be ip:port, not '{}'", self.bind_address));
+        }
+
+        if self.upstream_health_interval_secs == 0
+            || self.upstream_unhealthy_threshold == 0
+            || self.upstream_healthy_threshold == 0
+            || self.upstream_health_history == 0
+        {
+            errors.push("Upstream health check interval, thresholds and history must be greater than zero".to_string());
+        }
+
+        for name in self
+            .proxy_request_headers_allow
+            .iter()
+            .chain(&self.proxy_request_headers_deny)
+            .chain(&self.proxy_response_headers_allow)
+            .chain(&self.proxy_response_headers_deny)
+        {
+            if axum::http::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                errors.push(format!("Invalid header name in proxy header policy: {}", name));
+            }
+        }
+
+        if self.cache_enabled && (self.cache_max_entries == 0 || self.cache_max_bytes == 0) {
+            errors.push("Cache limits must be greater than zero when the cache is enabled".to_string());
+        }
+
+        if self.breaker_failure_threshold == 0 || self.breaker_half_open_max_calls == 0 {
+            errors.push("Circuit breaker threshold and half-open calls must be greater than zero".to_string());
+        }
+
+        if self.retry_base_delay_ms > self.retry_max_delay_ms {
+            errors.push("RETRY_BASE_DELAY_MS cannot be greater than RETRY_MAX_DELAY_MS".to_string());
+        }
+
+        match &self.auth_jwt_secret {
+            None => errors.push("AUTH_JWT_SECRET is required".to_string()),
+            Some(secret) if secret.len() < 32 => {
+                errors.push("AUTH_JWT_SECRET must be at least 32 bytes long".to_string());
+            }
+            Some(_) => {}
+        }
+
+        if self.auth_session_ttl_secs == 0 {
+            errors.push("AUTH_SESSION_TTL_SECS must be greater than zero".to_string());
+        }
+
+        if let Some((class, _)) = self
+            .rate_limit_classes
+            .iter()
+            .find(|(_, rule)| rule.requests == 0 || rule.period_secs == 0)
+        {
+            errors.push(format!("Rate limit for class '{}' must allow at least one request per non-zero period", class));
+        }
+
+        if let Some(class) = self
+            .daily_quotas
+            .keys()
+            .find(|class| !self.rate_limit_classes.contains_key(*class))
+        {
+            errors.push(format!("DAILY_QUOTAS references unknown rate limit class '{}'", class));
+        }
+
+        if !(0.0..=1.0).contains(&self.log_body_sample_rate) {
+            errors.push("LOG_BODY_SAMPLE_RATE must be between 0.0 and 1.0".to_string());
+        }
+
+        if self.log_body_max_bytes == 0 || self.log_body_truncate_bytes > self.log_body_max_bytes {
+            errors.push("LOG_BODY_MAX_BYTES must be greater than zero and at least LOG_BODY_TRUNCATE_BYTES".to_string());
         }
 
-        if !self.api_base_url.starts_with("http") {
-            return Err(anyhow::anyhow!("API base URL must start with http:// or https://"));
+        for path in &self.log_redact_paths {
+            if let Err(e) = path.parse::<RedactPath>() {
+                errors.push(format!("Invalid LOG_REDACT_PATHS entry: {}", e));
+            }
         }
 
-        Ok(())
+        if self.batch_max_items == 0 || self.batch_concurrency == 0 {
+            errors.push("BATCH_MAX_ITEMS and BATCH_CONCURRENCY must be greater than zero".to_string());
+        }
+        // Cada elemento del lote se cobra a `analysis`: un lote mayor que su bucket no pasaría nunca
+        if let Some(rule) = self
+            .rate_limit_classes
+            .get(ANALYSIS_CLASS)
+            .filter(|rule| self.rate_limit_enabled && self.batch_max_items > rule.requests as usize)
+        {
+            errors.push(format!(
+                "BATCH_MAX_ITEMS ({}) cannot be greater than the '{}' rate limit ({} requests)",
+                self.batch_max_items, ANALYSIS_CLASS, rule.requests
+            ));
+        }
+
+        if self.live_poll_interval_secs == 0 || self.live_heartbeat_secs == 0 || self.live_replay_events == 0 {
+            errors.push("LIVE_POLL_INTERVAL_SECS, LIVE_HEARTBEAT_SECS and LIVE_REPLAY_EVENTS must be greater than zero".to_string());
+        }
+
+        if let Some(url) = &self.database_url {
+            if !url.starts_with("postgres://") && !url.starts_with("postgresql://") {
+                errors.push("DATABASE_URL must start with postgres:// or postgresql://".to_string());
+            }
+        }
+        if self.database_max_connections == 0 || self.database_acquire_timeout_secs == 0 {
+            errors.push("DATABASE_MAX_CONNECTIONS and DATABASE_ACQUIRE_TIMEOUT_SECS must be greater than zero".to_string());
+        }
+        if self.jobs_history_size == 0 {
+            errors.push("JOBS_HISTORY_SIZE must be greater than zero".to_string());
+        }
+        errors.extend(jobs::validate_jobs(&self.jobs).err().unwrap_or_default());
+        errors.extend(webhooks::validate_webhooks(&self.webhooks).err().unwrap_or_default());
+        if !self.webhooks.is_empty() && self.database_url.is_none() {
+            errors.push("WEBHOOKS_FILE requires DATABASE_URL: deliveries are queued in PostgreSQL".to_string());
+        }
+        if self.webhook_max_attempts == 0 || self.webhook_timeout_secs == 0 || self.webhook_providers_poll_secs == 0 {
+            errors.push("WEBHOOK_MAX_ATTEMPTS, WEBHOOK_TIMEOUT_SECS and WEBHOOK_PROVIDERS_POLL_SECS must be greater than zero".to_string());
+        }
+        if self.webhook_retry_base_delay_ms > self.webhook_retry_max_delay_ms {
+            errors.push("WEBHOOK_RETRY_BASE_DELAY_MS cannot be greater than WEBHOOK_RETRY_MAX_DELAY_MS".to_string());
+        }
+
+        errors.extend(routes::validate_routes(&self.routes).err().unwrap_or_default());
+        if self.rate_limit_enabled {
+            for (i, route) in self.routes.iter().enumerate() {
+                if !self.rate_limit_classes.contains_key(&route.rate_limit_class) {
+                    errors.push(format!(
+                        "route #{} ({}): unknown rate_limit_class '{}'",
+                        i + 1,
+                        route.path,
+                        route.rate_limit_class
+                    ));
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+/// Los mismos valores que `--print-config`, con los secretos ocultos
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Ok(serde_json::Value::Object(fields)) = serde_json::to_value(self.redacted()) else {
+            return f.write_str("Config { .. }");
+        };
+        let mut debug = f.debug_struct("Config");
+        for (key, value) in &fields {
+            debug.field(key, &format_args!("{}", value));
+        }
+        debug.finish()
     }
-}
\ No newline at end of file
+}
+
+/// Valor que sustituye a los secretos en `--print-config`
+pub const REDACTED: &str = "***";
+
+/// INBESTIA_API_KEY o INBESTIA_API_KEY_FILE (una clave por línea: la principal y después
+/// las de reserva), más las de reserva de INBESTIA_API_KEY_FALLBACKS
+fn load_api_keys(l: &mut Loader, default: Secret) -> (Secret, Vec<Secret>) {
+    let key: Secret = l.value("INBESTIA_API_KEY", default);
+    let file = l.file("INBESTIA_API_KEY_FILE", Vec::new(), secret::read_keys);
+    let mut fallbacks = Vec::new();
+    let primary = match file.split_first() {
+        Some((primary, rest)) => {
+            if !key.is_empty() {
+                l.error("INBESTIA_API_KEY and INBESTIA_API_KEY_FILE cannot both be set".to_string());
+            }
+            fallbacks.extend_from_slice(rest);
+            primary.clone()
+        }
+        None => key,
+    };
+    fallbacks.extend(
+        l.list("INBESTIA_API_KEY_FALLBACKS", Vec::new())
+            .into_iter()
+            .map(Secret::from),
+    );
+    (primary, fallbacks)
+}
+
+fn invalid_configuration(errors: &[String]) -> anyhow::Error {
+    anyhow::anyhow!(
+        "Invalid configuration ({} errors):\n  - {}",
+        errors.len(),
+        errors.join("\n  - ")
+    )
+}
@@ -0,0 +1,327 @@
+//! Capas de configuración, de menor a mayor prioridad: fichero TOML (CONFIG_FILE o
+//! `--config`), variables de entorno y flags de línea de comandos.
+//!
+//! Todas las capas usan las mismas claves, los nombres de las variables de entorno
+//! (`REQUEST_TIMEOUT`); en el fichero pueden ir en minúsculas. Las listas se escriben como
+//! arrays TOML y los mapas (`RATE_LIMITS`, `DAILY_QUOTAS`...) como tablas:
+//!
+//! ```toml
+//! inbestia_api_url = ["http://inbestia-1:8080", "http://inbestia-2:8080"]
+//! request_timeout = 30
+//! rate_limits = { default = "120/60", analysis = "20/60" }
+//! ```
+
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// De dónde sale un valor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layer {
+    File,
+    Env,
+    Cli,
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Layer::File => "config file",
+            Layer::Env => "environment",
+            Layer::Cli => "command line",
+        })
+    }
+}
+
+/// Valores de cada capa, ya como texto, por clave
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSources {
+    file: BTreeMap<String, String>,
+    env: BTreeMap<String, String>,
+    cli: BTreeMap<String, String>,
+    /// Fichero TOML leído, si lo hay
+    file_path: Option<PathBuf>,
+    /// Fichero ilegible o valores que no se pueden expresar como una clave plana
+    problems: Vec<String>,
+}
+
+impl ConfigSources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Entorno del proceso más el fichero `config_file` o, si no se indica, el de
+    /// CONFIG_FILE
+    pub fn from_env(config_file: Option<&Path>) -> Self {
+        let sources =
+            Self::new().with_env(env::vars_os().filter_map(|(key, value)| {
+                Some((key.into_string().ok()?, value.into_string().ok()?))
+            }));
+        let path = config_file
+            .map(|path| path.to_string_lossy().into_owned())
+            .or_else(|| sources.env.get("CONFIG_FILE").cloned())
+            .filter(|path| !path.is_empty());
+        match path {
+            Some(path) => sources.with_file(Path::new(&path)),
+            None => sources,
+        }
+    }
+
+    pub fn with_env(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.env.extend(vars);
+        self
+    }
+
+    pub fn with_file(mut self, path: &Path) -> Self {
+        self.file_path = Some(path.to_path_buf());
+        match std::fs::read_to_string(path) {
+            Ok(source) => self.with_file_contents(&source, &path.display().to_string()),
+            Err(e) => {
+                self.problems
+                    .push(format!("Cannot read config file {}: {}", path.display(), e));
+                self
+            }
+        }
+    }
+
+    /// Fichero TOML ya leído; `origin` sólo aparece en los errores
+    pub fn with_file_contents(mut self, source: &str, origin: &str) -> Self {
+        let table: toml::Table = match toml::from_str(source) {
+            Ok(table) => table,
+            Err(e) => {
+                self.problems
+                    .push(format!("Invalid config file {}: {}", origin, e));
+                return self;
+            }
+        };
+        for (key, value) in table {
+            match flatten(&value) {
+                Ok(flat) => {
+                    self.file.insert(key.to_uppercase(), flat);
+                }
+                Err(e) => self
+                    .problems
+                    .push(format!("{} (config file {}): {}", key, origin, e)),
+            }
+        }
+        self
+    }
+
+    /// Valor de línea de comandos; la clave admite minúsculas y guiones (`request-timeout`)
+    pub fn with_override(mut self, key: &str, value: &str) -> Self {
+        self.cli.insert(
+            key.trim().to_uppercase().replace('-', "_"),
+            value.to_string(),
+        );
+        self
+    }
+
+    /// Ficheros de los que sale la configuración: el TOML, el manifiesto de rutas
+    /// (ROUTES_FILE) y las claves de inBestia (INBESTIA_API_KEY_FILE). Son los que se
+    /// vigilan para recargarla
+    pub fn files(&self) -> Vec<PathBuf> {
+        let referenced = ["ROUTES_FILE", "INBESTIA_API_KEY_FILE"]
+            .into_iter()
+            .filter_map(|key| self.lookup(key))
+            .map(|(path, _)| path)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from);
+        self.file_path
+            .clone()
+            .into_iter()
+            .chain(referenced)
+            .collect()
+    }
+
+    fn lookup(&self, key: &str) -> Option<(&str, Layer)> {
+        [
+            (&self.cli, Layer::Cli),
+            (&self.env, Layer::Env),
+            (&self.file, Layer::File),
+        ]
+        .into_iter()
+        .find_map(|(values, layer)| values.get(key).map(|value| (value.as_str(), layer)))
+    }
+}
+
+/// Parsea un `KEY=VALUE` de `--set`
+pub fn parse_override(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("'{}' must be KEY=VALUE", arg)),
+    }
+}
+
+/// Valor TOML como el texto equivalente de la variable de entorno
+fn flatten(value: &toml::Value) -> Result<String, String> {
+    let scalar = |value: &toml::Value| match value {
+        toml::Value::String(s) => Ok(s.clone()),
+        toml::Value::Integer(_)
+        | toml::Value::Float(_)
+        | toml::Value::Boolean(_)
+        | toml::Value::Datetime(_) => Ok(value.to_string()),
+        toml::Value::Array(_) | toml::Value::Table(_) => {
+            Err("nested arrays and tables are not supported".to_string())
+        }
+    };
+    match value {
+        toml::Value::Array(items) => Ok(items
+            .iter()
+            .map(scalar)
+            .collect::<Result<Vec<_>, _>>()?
+            .join(",")),
+        toml::Value::Table(entries) => Ok(entries
+            .iter()
+            .map(|(key, value)| Ok(format!("{}={}", key, scalar(value)?)))
+            .collect::<Result<Vec<_>, String>>()?
+            .join(",")),
+        other => scalar(other),
+    }
+}
+
+/// Lee las claves de la configuración anotando cada error en vez de parar en el primero:
+/// una clave inválida se queda con su valor por defecto
+pub(super) struct Loader<'a> {
+    sources: &'a ConfigSources,
+    used: HashSet<String>,
+    errors: Vec<String>,
+}
+
+impl<'a> Loader<'a> {
+    pub(super) fn new(sources: &'a ConfigSources) -> Self {
+        Self {
+            sources,
+            used: HashSet::new(),
+            errors: sources.problems.clone(),
+        }
+    }
+
+    fn raw(&mut self, key: &str) -> Option<(&'a str, Layer)> {
+        self.used.insert(key.to_string());
+        self.sources.lookup(key)
+    }
+
+    /// Error que no corresponde a una sola clave
+    pub(super) fn error(&mut self, message: String) {
+        self.errors.push(message);
+    }
+
+    fn invalid(&mut self, key: &str, layer: Layer, value: &str, error: impl fmt::Display) {
+        self.errors.push(format!(
+            "{} ({}) has an invalid value '{}': {}",
+            key, layer, value, error
+        ));
+    }
+
+    pub(super) fn value<T: FromStr>(&mut self, key: &str, default: T) -> T
+    where
+        T::Err: fmt::Display,
+    {
+        let Some((value, layer)) = self.raw(key) else {
+            return default;
+        };
+        match value.trim().parse() {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.invalid(key, layer, value, e);
+                default
+            }
+        }
+    }
+
+    pub(super) fn string(&mut self, key: &str, default: String) -> String {
+        self.raw(key)
+            .map(|(value, _)| value.to_string())
+            .unwrap_or(default)
+    }
+
+    /// Definida pero vacía equivale a `None`
+    pub(super) fn optional(&mut self, key: &str, default: Option<String>) -> Option<String> {
+        match self.raw(key) {
+            Some((value, _)) => Some(value.to_string()).filter(|value| !value.is_empty()),
+            None => default,
+        }
+    }
+
+    /// Lista separada por comas
+    pub(super) fn list(&mut self, key: &str, default: Vec<String>) -> Vec<String> {
+        self.raw(key)
+            .map(|(value, _)| split_list(value))
+            .unwrap_or(default)
+    }
+
+    /// `clave=valor,clave=valor`
+    pub(super) fn map<T: FromStr>(
+        &mut self,
+        key: &str,
+        default: BTreeMap<String, T>,
+    ) -> BTreeMap<String, T>
+    where
+        T::Err: fmt::Display,
+    {
+        let Some((value, layer)) = self.raw(key) else {
+            return default;
+        };
+        let mut map = BTreeMap::new();
+        for entry in split_list(value) {
+            let Some((name, raw)) = entry.split_once('=') else {
+                self.invalid(key, layer, &entry, "entries must be key=value");
+                continue;
+            };
+            match raw.trim().parse() {
+                Ok(parsed) => {
+                    map.insert(name.trim().to_string(), parsed);
+                }
+                Err(e) => self.invalid(key, layer, &entry, e),
+            }
+        }
+        map
+    }
+
+    /// Fichero que se carga con `load` (manifiestos de rutas, jobs...); vacío = `default`
+    pub(super) fn file<T>(
+        &mut self,
+        key: &str,
+        default: T,
+        load: impl FnOnce(&Path) -> anyhow::Result<T>,
+    ) -> T {
+        match self.raw(key) {
+            Some((path, _)) if !path.is_empty() => match load(Path::new(path)) {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    self.errors.push(format!("{}: {}", key, e));
+                    default
+                }
+            },
+            _ => default,
+        }
+    }
+
+    /// Errores acumulados, más las claves del fichero o de la línea de comandos que no
+    /// corresponden a ninguna opción (en el entorno hay muchas variables ajenas)
+    pub(super) fn finish(self) -> Vec<String> {
+        let mut errors = self.errors;
+        for (values, layer) in [
+            (&self.sources.file, Layer::File),
+            (&self.sources.cli, Layer::Cli),
+        ] {
+            for key in values.keys().filter(|key| !self.used.contains(*key)) {
+                errors.push(format!("{} ({}) is not a configuration key", key, layer));
+            }
+        }
+        errors
+    }
+}
+
+/// Separa una lista de valores separados por comas, descartando entradas vacías
+pub(super) fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
@@ -0,0 +1,153 @@
+use futures_util::future::join_all;
+use ghost_shared::responses::{
+    ConfluenceInterpretation, ConfluenceResponse, TimeframeSignal, TimeframesConfig,
+};
+use ghost_shared::{timeframes, AnalysisRequest};
+use std::time::Duration;
+
+use crate::inbestia::{InbestiaClient, InbestiaError};
+
+/// Timeframes del análisis multi-temporal, de corto a largo plazo
+pub const RECOMMENDED: [&str; 5] = ["15m", "1h", "4h", "1d", "1w"];
+
+/// Espera máxima por `/api/v1/timeframes/config`; sin él se usan los pesos por defecto
+const CONFIG_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Lleva una puntuación de inBestia (0-100, 50 neutral) a una señal de -100 a +100
+pub fn signal(score: f64) -> f64 {
+    ((score - 50.0) * 2.0).clamp(-100.0, 100.0)
+}
+
+/// Media de las señales ponderada por el peso de cada timeframe.
+/// `None` si no hay ninguna señal con peso.
+pub fn combine(signals: &[(f64, f64)]) -> Option<f64> {
+    let total_weight: f64 = signals.iter().map(|(weight, _)| weight).sum();
+    if total_weight <= 0.0 {
+        return None;
+    }
+    let weighted: f64 = signals.iter().map(|(weight, signal)| weight * signal).sum();
+    Some(weighted / total_weight)
+}
+
+/// Etiqueta y descripción de una confluencia de -100 a +100
+pub fn interpret(score: f64) -> ConfluenceInterpretation {
+    let (label, description) = match score {
+        s if s >= 60.0 => (
+            "strong_bullish",
+            "Confluencia alcista fuerte: los timeframes coinciden al alza",
+        ),
+        s if s >= 20.0 => (
+            "bullish",
+            "Sesgo alcista con acuerdo parcial entre timeframes",
+        ),
+        s if s > -20.0 => ("neutral", "Sin confluencia clara: señales mixtas o débiles"),
+        s if s > -60.0 => (
+            "bearish",
+            "Sesgo bajista con acuerdo parcial entre timeframes",
+        ),
+        _ => (
+            "strong_bearish",
+            "Confluencia bajista fuerte: los timeframes coinciden a la baja",
+        ),
+    };
+    ConfluenceInterpretation {
+        label: label.to_string(),
+        description: description.to_string(),
+    }
+}
+
+/// Peso de `timeframe` en la configuración de inBestia (por nombre o alias)
+fn configured_weight(config: &TimeframesConfig, timeframe: &str) -> Option<f64> {
+    config
+        .timeframes
+        .iter()
+        .find(|tf| tf.name == timeframe || tf.aliases.iter().any(|alias| alias == timeframe))
+        .map(|tf| tf.weight)
+}
+
+/// Analiza `symbol` en los timeframes recomendados en paralelo y combina las puntuaciones.
+///
+/// Los timeframes que fallan se listan con su error y no cuentan; sólo si fallan
+/// todos se devuelve el error (el primero).
+pub async fn analyze(
+    client: &InbestiaClient,
+    symbol: &str,
+) -> Result<ConfluenceResponse, InbestiaError> {
+    let analyses = join_all(RECOMMENDED.iter().map(|canonical| {
+        let request = AnalysisRequest {
+            symbol: symbol.to_string(),
+            timeframe: timeframes::resolve(canonical)
+                .map(|tf| tf.analysis.to_string())
+                .unwrap_or_else(|| canonical.to_string()),
+            include_fundamental: false,
+        };
+        async move { client.analyze(&request).await }
+    }));
+    let config = async {
+        client
+            .clone()
+            .with_timeout(CONFIG_TIMEOUT)
+            .timeframes_config()
+            .await
+            .ok()
+    };
+    let (analyses, config) = tokio::join!(analyses, config);
+
+    let mut signals = Vec::new();
+    let mut first_error = None;
+    let breakdown: Vec<TimeframeSignal> = RECOMMENDED
+        .iter()
+        .zip(analyses)
+        .map(|(canonical, analysis)| {
+            let weight = config
+                .as_ref()
+                .and_then(|config| configured_weight(config, canonical))
+                .or_else(|| timeframes::resolve(canonical).map(|tf| tf.weight))
+                .unwrap_or(0.0);
+            match analysis {
+                Ok(analysis) => {
+                    let signal = signal(analysis.score);
+                    signals.push((weight, signal));
+                    TimeframeSignal {
+                        timeframe: canonical.to_string(),
+                        weight,
+                        score: Some(analysis.score),
+                        signal: Some(signal),
+                        suggested_operation: Some(analysis.suggested_operation),
+                        error: None,
+                    }
+                }
+                Err(e) => {
+                    let error = e.item_error();
+                    first_error.get_or_insert(e);
+                    TimeframeSignal {
+                        timeframe: canonical.to_string(),
+                        weight,
+                        score: None,
+                        signal: None,
+                        suggested_operation: None,
+                        error: Some(error),
+                    }
+                }
+            }
+        })
+        .collect();
+
+    let score = match (combine(&signals), first_error) {
+        (Some(score), _) => score,
+        (None, Some(e)) => return Err(e),
+        (None, None) => 0.0,
+    };
+    Ok(ConfluenceResponse {
+        symbol: symbol.to_string(),
+        score,
+        interpretation: interpret(score),
+        weights_source: if config.is_some() {
+            "inbestia"
+        } else {
+            "default"
+        }
+        .to_string(),
+        timeframes: breakdown,
+    })
+}
@@ -0,0 +1,36 @@
+use sqlx::migrate::Migrator;
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::time::Duration;
+use tracing::info;
+
+use crate::config::Config;
+
+/// Migraciones versionadas de `backend/migrations`, embebidas en el binario
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Pool de conexiones a PostgreSQL, o `None` si no hay DATABASE_URL.
+/// Las conexiones se abren al usarse: arrancar no exige que la base de datos responda.
+pub fn connect(config: &Config) -> anyhow::Result<Option<PgPool>> {
+    let Some(url) = config.database_url.as_deref() else {
+        return Ok(None);
+    };
+    let pool = PgPoolOptions::new()
+        .max_connections(config.database_max_connections)
+        .acquire_timeout(Duration::from_secs(config.database_acquire_timeout_secs))
+        .connect_lazy(url)
+        .map_err(|e| anyhow::anyhow!("Invalid DATABASE_URL: {}", e))?;
+    Ok(Some(pool))
+}
+
+/// Aplica las migraciones pendientes
+pub async fn migrate(pool: &PgPool) -> anyhow::Result<()> {
+    MIGRATOR
+        .run(pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("Database migration failed: {}", e))?;
+    info!(
+        migrations = MIGRATOR.iter().count(),
+        "database schema up to date"
+    );
+    Ok(())
+}
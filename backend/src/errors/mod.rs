@@ -0,0 +1,41 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+
+use crate::validation::FieldError;
+
+/// Construye la respuesta de error estándar del gateway:
+/// `{"error": {"code": ..., "message": ..., "trace_id": ...}}`
+pub fn error_response(status: StatusCode, code: &str, message: &str, trace_id: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "error": {
+                "code": code,
+                "message": message,
+                "trace_id": trace_id
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// Respuesta 422 de un body que no cumple su esquema: el error estándar más
+/// `fields`, con un mensaje por campo inválido
+pub fn validation_error(trace_id: &str, fields: &[FieldError]) -> Response {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(json!({
+            "error": {
+                "code": "VALIDATION_FAILED",
+                "message": "El body no es válido",
+                "trace_id": trace_id,
+                "fields": fields
+            }
+        })),
+    )
+        .into_response()
+}
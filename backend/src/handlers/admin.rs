@@ -0,0 +1,35 @@
+use axum::{
+    extract::{Query, State},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+
+use crate::AppState;
+
+/// Estado de la caché de respuestas: tamaño, aciertos y claves guardadas
+pub async fn cache_stats(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.cache.stats())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PurgeParams {
+    /// Clave exacta a eliminar (tal como aparece en `GET /api/admin/cache`)
+    pub key: Option<String>,
+    /// Elimina todas las claves con este prefijo, p.ej. `POST /api/v1/analyze`
+    pub prefix: Option<String>,
+}
+
+/// Purga entradas de la caché; sin parámetros la vacía por completo
+pub async fn cache_purge(
+    State(state): State<AppState>,
+    Query(params): Query<PurgeParams>,
+) -> impl IntoResponse {
+    let purged = state
+        .cache
+        .purge(params.key.as_deref(), params.prefix.as_deref());
+    info!(key = ?params.key, prefix = ?params.prefix, purged, "cache purged");
+    Json(json!({ "purged": purged }))
+}
@@ -0,0 +1,216 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use ghost_shared::{timeframes, AlertRuleRequest};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+
+use crate::alerts::{self, expr, AlertError};
+use crate::auth::AuthUser;
+use crate::errors::validation_error;
+use crate::handlers::watchlists::database_not_configured;
+use crate::trace::TraceContext;
+use crate::validation::FieldError;
+use crate::AppState;
+
+/// Alertas por página si no se indica `limit`
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct FiredQuery {
+    pub limit: Option<String>,
+    pub offset: Option<String>,
+}
+
+/// Alertas disparadas por las reglas del usuario, la más reciente primero
+pub async fn fired(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<FiredQuery>,
+) -> Response {
+    let Some(db) = state.db.as_ref() else {
+        return database_not_configured(&trace);
+    };
+    let (limit, offset) = match parse_page(&query) {
+        Ok(page) => page,
+        Err(fields) => return validation_error(&trace.trace_id, &fields),
+    };
+    match alerts::fired(db, &user.username, limit, offset).await {
+        Ok(alerts) => Json(json!({ "alerts": alerts })).into_response(),
+        Err(e) => e.to_response(&trace.trace_id),
+    }
+}
+
+/// Reglas de alerta del usuario de la sesión
+pub async fn list_rules(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    Extension(user): Extension<AuthUser>,
+) -> Response {
+    let Some(db) = state.db.as_ref() else {
+        return database_not_configured(&trace);
+    };
+    respond(
+        alerts::list(db, &user.username).await,
+        StatusCode::OK,
+        &trace,
+    )
+}
+
+pub async fn create_rule(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    Extension(user): Extension<AuthUser>,
+    Json(request): Json<AlertRuleRequest>,
+) -> Response {
+    let Some(db) = state.db.as_ref() else {
+        return database_not_configured(&trace);
+    };
+    let request = match normalize(request) {
+        Ok(request) => request,
+        Err(fields) => return validation_error(&trace.trace_id, &fields),
+    };
+    let result = alerts::create(db, &user.username, &request).await;
+    if let Ok(rule) = &result {
+        info!(trace_id = %trace.trace_id, user = %user.username, id = rule.id, "alert rule created");
+    }
+    respond(result, StatusCode::CREATED, &trace)
+}
+
+pub async fn get_rule(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<i64>,
+) -> Response {
+    let Some(db) = state.db.as_ref() else {
+        return database_not_configured(&trace);
+    };
+    respond(
+        alerts::get(db, &user.username, id).await,
+        StatusCode::OK,
+        &trace,
+    )
+}
+
+/// Reemplaza la regla; la condición vuelve a evaluarse desde el siguiente análisis
+pub async fn update_rule(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<i64>,
+    Json(request): Json<AlertRuleRequest>,
+) -> Response {
+    let Some(db) = state.db.as_ref() else {
+        return database_not_configured(&trace);
+    };
+    let request = match normalize(request) {
+        Ok(request) => request,
+        Err(fields) => return validation_error(&trace.trace_id, &fields),
+    };
+    respond(
+        alerts::update(db, &user.username, id, &request).await,
+        StatusCode::OK,
+        &trace,
+    )
+}
+
+pub async fn delete_rule(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<i64>,
+) -> Response {
+    let Some(db) = state.db.as_ref() else {
+        return database_not_configured(&trace);
+    };
+    match alerts::delete(db, &user.username, id).await {
+        Ok(()) => {
+            info!(trace_id = %trace.trace_id, user = %user.username, id, "alert rule deleted");
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => e.to_response(&trace.trace_id),
+    }
+}
+
+fn respond<T: serde::Serialize>(
+    result: Result<T, AlertError>,
+    status: StatusCode,
+    trace: &TraceContext,
+) -> Response {
+    match result {
+        Ok(body) => (status, Json(body)).into_response(),
+        Err(e) => e.to_response(&trace.trace_id),
+    }
+}
+
+/// Símbolo en mayúsculas y timeframe por su nombre canónico; rechaza nombres en blanco y
+/// expresiones que no parsean (el esquema ya validó formato, timeframe y longitudes)
+fn normalize(mut request: AlertRuleRequest) -> Result<AlertRuleRequest, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    request.name = request.name.trim().to_string();
+    if request.name.is_empty() {
+        errors.push(FieldError {
+            field: "name".to_string(),
+            message: "El nombre no puede estar en blanco".to_string(),
+        });
+    }
+
+    request.symbol = request.symbol.map(|symbol| symbol.to_uppercase());
+    request.timeframe = request.timeframe.map(|timeframe| {
+        timeframes::resolve(&timeframe)
+            .map(|tf| tf.canonical.to_string())
+            .unwrap_or(timeframe)
+    });
+
+    request.expression = request.expression.trim().to_string();
+    if let Err(e) = expr::parse(&request.expression) {
+        errors.push(FieldError {
+            field: "expression".to_string(),
+            message: e.to_string(),
+        });
+    }
+
+    if errors.is_empty() {
+        Ok(request)
+    } else {
+        Err(errors)
+    }
+}
+
+fn parse_page(query: &FiredQuery) -> Result<(u32, u32), Vec<FieldError>> {
+    let mut errors = Vec::new();
+    let limit = match query.limit.as_deref().map(str::parse::<u32>) {
+        None => DEFAULT_LIMIT,
+        Some(Ok(limit)) if (1..=MAX_LIMIT).contains(&limit) => limit,
+        Some(_) => {
+            errors.push(FieldError {
+                field: "limit".to_string(),
+                message: format!("Debe estar entre 1 y {}", MAX_LIMIT),
+            });
+            DEFAULT_LIMIT
+        }
+    };
+    let offset = match query.offset.as_deref().map(str::parse::<u32>) {
+        None => 0,
+        Some(Ok(offset)) => offset,
+        Some(Err(_)) => {
+            errors.push(FieldError {
+                field: "offset".to_string(),
+                message: "Debe ser un entero no negativo".to_string(),
+            });
+            0
+        }
+    };
+    if errors.is_empty() {
+        Ok((limit, offset))
+    } else {
+        Err(errors)
+    }
+}
@@ -0,0 +1,144 @@
+use axum::{
+    extract::{rejection::JsonRejection, ConnectInfo, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use chrono::{TimeZone, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use std::net::SocketAddr;
+use tracing::{error, info, warn};
+
+use crate::auth::{session_token, AuthUser, SESSION_COOKIE};
+use crate::errors::error_response;
+use crate::middleware::{enforce, insert_rate_limit_headers};
+use crate::ratelimit::{ip_identity, login_identity, LOGIN_CLASS, LOGIN_IP_CLASS};
+use crate::trace::TraceContext;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+/// Inicia sesión: devuelve el JWT en el body y también como cookie HttpOnly.
+/// Los intentos se limitan por IP (clase `login_ip`) y por IP y usuario (clase `login`),
+/// también con RATE_LIMIT_ENABLED=false.
+pub async fn login(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    client: Option<ConnectInfo<SocketAddr>>,
+    body: Result<Json<LoginRequest>, JsonRejection>,
+) -> Response {
+    let trace_id = trace.trace_id;
+    let Ok(Json(credentials)) = body else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "INVALID_REQUEST",
+            "Se esperaba un JSON con username y password",
+            &trace_id,
+        );
+    };
+
+    // Se cobra antes de verificar: el límite por IP acota también el coste de argon2,
+    // por muchos usuarios distintos que se prueben
+    let ip = client.map(|ConnectInfo(addr)| addr.ip());
+    let mut decisions = Vec::new();
+    for (identity, class) in [
+        (ip_identity(ip), LOGIN_IP_CLASS),
+        (login_identity(ip, &credentials.username), LOGIN_CLASS),
+    ] {
+        match enforce(&state, &identity, class, 1, &trace_id) {
+            Ok(decision) => decisions.extend(decision),
+            Err(response) => return *response,
+        }
+    }
+
+    let mut response = authenticate(&state, credentials, &trace_id).await;
+    // Las cabeceras `RateLimit-*` son las del límite más cercano a agotarse
+    if let Some(decision) = decisions.iter().min_by_key(|decision| decision.remaining) {
+        insert_rate_limit_headers(&mut response, decision, None);
+    }
+    response
+}
+
+async fn authenticate(state: &AppState, credentials: LoginRequest, trace_id: &str) -> Response {
+    // argon2 es deliberadamente costoso: fuera del runtime async
+    let users = state.users.clone();
+    let username = credentials.username.clone();
+    let valid = tokio::task::spawn_blocking(move || {
+        users.verify(&credentials.username, &credentials.password)
+    })
+    .await
+    .unwrap_or(false);
+
+    if !valid {
+        warn!(username = %username, trace_id = %trace_id, "login failed");
+        return error_response(
+            StatusCode::UNAUTHORIZED,
+            "INVALID_CREDENTIALS",
+            "Usuario o contraseña incorrectos",
+            trace_id,
+        );
+    }
+
+    let (token, claims) = match state.sessions.issue(&username) {
+        Ok(issued) => issued,
+        Err(e) => {
+            error!(error = ?e, trace_id = %trace_id, "cannot issue session token");
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "INTERNAL_ERROR",
+                "No se pudo crear la sesión",
+                trace_id,
+            );
+        }
+    };
+    info!(username = %username, "user logged in");
+
+    let cookie = session_cookie(state, &token, state.sessions.ttl_secs());
+    let mut response = Json(json!({
+        "username": username,
+        "token": token,
+        "token_type": "Bearer",
+        "expires_at": Utc.timestamp_opt(claims.exp, 0).single(),
+    }))
+    .into_response();
+    if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+        response.headers_mut().insert(header::SET_COOKIE, cookie);
+    }
+    response
+}
+
+/// Cierra la sesión: revoca el token y borra la cookie
+pub async fn logout(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Some(claims) = session_token(&headers).and_then(|token| state.sessions.validate(token)) {
+        state.sessions.revoke(&claims);
+        info!(username = %claims.sub, "user logged out");
+    }
+
+    let mut response = StatusCode::NO_CONTENT.into_response();
+    if let Ok(cookie) = HeaderValue::from_str(&session_cookie(&state, "", 0)) {
+        response.headers_mut().insert(header::SET_COOKIE, cookie);
+    }
+    response
+}
+
+/// Usuario de la sesión actual
+pub async fn me(Extension(user): Extension<AuthUser>) -> impl IntoResponse {
+    Json(json!({ "username": user.username }))
+}
+
+fn session_cookie(state: &AppState, value: &str, max_age: i64) -> String {
+    let secure = if state.settings().config.auth_cookie_secure {
+        "; Secure"
+    } else {
+        ""
+    };
+    format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Strict; Max-Age={}{}",
+        SESSION_COOKIE, value, max_age, secure
+    )
+}
@@ -0,0 +1,163 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures_util::{stream, Stream, StreamExt};
+use ghost_shared::responses::{BatchAnalysisResponse, BatchItem, BatchOutcome};
+use ghost_shared::{timeframes, AnalysisRequest, BatchAnalysisRequest};
+use std::convert::Infallible;
+use tracing::info;
+
+use crate::errors::validation_error;
+use crate::inbestia::InbestiaClient;
+use crate::middleware::{charge, insert_rate_limit_headers};
+use crate::ratelimit::{ClientIdentity, ANALYSIS_CLASS};
+use crate::trace::TraceContext;
+use crate::validation::FieldError;
+use crate::AppState;
+
+/// Tipo MIME de la respuesta en streaming (un `BatchItem` JSON por línea)
+pub const NDJSON: &str = "application/x-ndjson";
+
+/// Analiza cada símbolo en cada timeframe con como mucho BATCH_CONCURRENCY llamadas
+/// simultáneas a inBestia. Los fallos se devuelven por elemento, con las sugerencias de
+/// inBestia si el símbolo no existe.
+///
+/// Cada elemento cuenta como una request de la clase `analysis`, y el lote se cobra
+/// entero antes de empezar.
+///
+/// Con `Accept: application/x-ndjson` cada resultado se envía en cuanto termina; si no,
+/// se devuelve un único documento con los elementos en el orden pedido.
+pub async fn analyze(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    ClientIdentity(identity): ClientIdentity,
+    headers: HeaderMap,
+    Json(request): Json<BatchAnalysisRequest>,
+) -> Response {
+    let config = &state.settings().config;
+    let total = request.symbols.len() * request.timeframes.len();
+    if total > config.batch_max_items {
+        let field = FieldError {
+            field: "body".to_string(),
+            message: format!(
+                "El lote tiene {} elementos (símbolos × timeframes); máximo {}",
+                total, config.batch_max_items
+            ),
+        };
+        return validation_error(&trace.trace_id, &[field]);
+    }
+
+    let decision = match charge(&state, &identity, ANALYSIS_CLASS, u32::try_from(total).unwrap_or(u32::MAX), &trace.trace_id) {
+        Ok(decision) => decision,
+        Err(response) => return *response,
+    };
+
+    info!(trace_id = %trace.trace_id, items = total, "batch analysis started");
+    let client = state.inbestia().with_trace(&trace);
+    let results = run(client, request, config.batch_concurrency);
+
+    let wants_ndjson = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains(NDJSON));
+    let mut response = if wants_ndjson {
+        let lines = results.map(|(_, item)| {
+            let mut line = serde_json::to_vec(&item).unwrap_or_default();
+            line.push(b'\n');
+            Ok::<_, Infallible>(Bytes::from(line))
+        });
+        let mut response = Response::new(Body::from_stream(lines));
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(NDJSON));
+        response
+    } else {
+        collect(results, total).await
+    };
+    if let Some(decision) = &decision {
+        insert_rate_limit_headers(&mut response, decision, None);
+    }
+    response
+}
+
+/// Respuesta única con los elementos en el orden pedido
+async fn collect(results: impl Stream<Item = (usize, BatchItem)>, total: usize) -> Response {
+    let mut items: Vec<(usize, BatchItem)> = results.collect().await;
+    items.sort_by_key(|(index, _)| *index);
+    let items: Vec<BatchItem> = items.into_iter().map(|(_, item)| item).collect();
+    let succeeded = items
+        .iter()
+        .filter(|item| matches!(item.outcome, BatchOutcome::Ok { .. }))
+        .count();
+
+    Json(BatchAnalysisResponse {
+        total,
+        succeeded,
+        failed: total - succeeded,
+        items,
+    })
+    .into_response()
+}
+
+/// Resultados del lote a medida que terminan, con su posición en el orden pedido
+pub(crate) fn run(
+    client: InbestiaClient,
+    request: BatchAnalysisRequest,
+    concurrency: usize,
+) -> impl Stream<Item = (usize, BatchItem)> {
+    let pairs: Vec<(String, String)> = request
+        .symbols
+        .iter()
+        .flat_map(|symbol| {
+            request
+                .timeframes
+                .iter()
+                .map(move |timeframe| (symbol.clone(), timeframe.clone()))
+        })
+        .collect();
+    let include_fundamental = request.include_fundamental;
+
+    stream::iter(pairs.into_iter().enumerate())
+        .map(move |(index, (symbol, timeframe))| {
+            let client = client.clone();
+            async move {
+                let outcome = analyze_one(&client, &symbol, &timeframe, include_fundamental).await;
+                (
+                    index,
+                    BatchItem {
+                        symbol,
+                        timeframe,
+                        outcome,
+                    },
+                )
+            }
+        })
+        .buffer_unordered(concurrency)
+}
+
+async fn analyze_one(
+    client: &InbestiaClient,
+    symbol: &str,
+    timeframe: &str,
+    include_fundamental: bool,
+) -> BatchOutcome {
+    // inBestia espera el nombre de análisis del timeframe (daily, hour4...)
+    let request = AnalysisRequest {
+        symbol: symbol.to_string(),
+        timeframe: timeframes::resolve(timeframe)
+            .map(|tf| tf.analysis.to_string())
+            .unwrap_or_else(|| timeframe.to_string()),
+        include_fundamental,
+    };
+
+    match client.analyze(&request).await {
+        Ok(result) => BatchOutcome::Ok { result },
+        Err(e) => BatchOutcome::Error {
+            error: e.item_error(),
+        },
+    }
+}
@@ -0,0 +1,77 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use ghost_shared::requests::is_valid_symbol;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::confluence;
+use crate::errors::validation_error;
+use crate::middleware::{charge, insert_rate_limit_headers};
+use crate::ratelimit::{ClientIdentity, ANALYSIS_CLASS};
+use crate::trace::TraceContext;
+use crate::validation::FieldError;
+use crate::AppState;
+
+/// Confluencia multi-temporal de `symbol` (15m, 1h, 4h, 1d, 1w) de -100 a +100,
+/// con el desglose por timeframe y su interpretación. Cuenta como una request de la
+/// clase `analysis` por timeframe.
+pub async fn confluence(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    ClientIdentity(identity): ClientIdentity,
+    Path(symbol): Path<String>,
+) -> Response {
+    if !is_valid_symbol(&symbol) {
+        let field = FieldError {
+            field: "symbol".to_string(),
+            message: format!("Formato inválido: \"{}\"", symbol),
+        };
+        return validation_error(&trace.trace_id, &[field]);
+    }
+
+    let cost = confluence::RECOMMENDED.len() as u32;
+    let decision = match charge(&state, &identity, ANALYSIS_CLASS, cost, &trace.trace_id) {
+        Ok(decision) => decision,
+        Err(response) => return *response,
+    };
+
+    let client = state.inbestia().with_trace(&trace);
+    let mut response = match confluence::analyze(&client, &symbol).await {
+        Ok(result) => {
+            info!(
+                trace_id = %trace.trace_id,
+                symbol = %symbol,
+                score = result.score,
+                label = %result.interpretation.label,
+                "confluence computed"
+            );
+            Json(result).into_response()
+        }
+        Err(e) => {
+            warn!(trace_id = %trace.trace_id, symbol = %symbol, error = %e, "confluence failed");
+            match e.symbol_not_found() {
+                Some(not_found) => (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({
+                        "error": {
+                            "code": "SYMBOL_NOT_FOUND",
+                            "message": not_found.message,
+                            "trace_id": trace.trace_id,
+                            "suggestions": not_found.suggestions
+                        }
+                    })),
+                )
+                    .into_response(),
+                None => e.to_response(&trace.trace_id),
+            }
+        }
+    };
+    if let Some(decision) = &decision {
+        insert_rate_limit_headers(&mut response, decision, None);
+    }
+    response
+}
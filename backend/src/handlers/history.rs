@@ -0,0 +1,139 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use ghost_shared::requests::is_valid_symbol;
+use ghost_shared::timeframes;
+use serde::Deserialize;
+use tracing::error;
+
+use crate::errors::{error_response, validation_error};
+use crate::handlers::watchlists::database_not_configured;
+use crate::history::{self, HistoryFilter};
+use crate::trace::TraceContext;
+use crate::validation::FieldError;
+use crate::AppState;
+
+/// Análisis por página si no se indica `limit`
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    /// Timeframe (admite alias: `daily`, `4H`...); por defecto todos
+    pub timeframe: Option<String>,
+    /// Inicio del rango en RFC 3339, incluido
+    pub from: Option<String>,
+    /// Fin del rango en RFC 3339, excluido
+    pub to: Option<String>,
+    pub limit: Option<String>,
+    pub offset: Option<String>,
+}
+
+/// Evolución de las puntuaciones de `symbol`: una serie en columnas por timeframe y los
+/// cambios de `suggested_operation`, en orden cronológico y paginado con `limit`/`offset`
+pub async fn history(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    Path(symbol): Path<String>,
+    Query(query): Query<HistoryQuery>,
+) -> Response {
+    let Some(db) = state.db.as_ref() else {
+        return database_not_configured(&trace);
+    };
+    let filter = match parse_filter(&symbol, &query) {
+        Ok(filter) => filter,
+        Err(fields) => return validation_error(&trace.trace_id, &fields),
+    };
+
+    match history::query(db, &symbol.to_uppercase(), &filter).await {
+        Ok(history) => Json(history).into_response(),
+        Err(e) => {
+            error!(trace_id = %trace.trace_id, error = %e, "analysis history query failed");
+            error_response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "DATABASE_UNAVAILABLE",
+                "La base de datos no está disponible",
+                &trace.trace_id,
+            )
+        }
+    }
+}
+
+/// Valida símbolo y parámetros; devuelve todos los errores a la vez
+fn parse_filter(symbol: &str, query: &HistoryQuery) -> Result<HistoryFilter, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    let mut invalid = |field: &str, message: String| {
+        errors.push(FieldError {
+            field: field.to_string(),
+            message,
+        })
+    };
+
+    if !is_valid_symbol(symbol) {
+        invalid("symbol", format!("Formato inválido: \"{}\"", symbol));
+    }
+
+    let timeframe = match query.timeframe.as_deref() {
+        None => None,
+        Some(name) => match timeframes::resolve(name) {
+            Some(tf) => Some(tf.canonical.to_string()),
+            None => {
+                invalid("timeframe", format!("Timeframe '{}' desconocido", name));
+                None
+            }
+        },
+    };
+
+    let mut timestamp = |field: &str, value: Option<&str>| {
+        value.and_then(|value| match DateTime::parse_from_rfc3339(value) {
+            Ok(at) => Some(at.with_timezone(&Utc)),
+            Err(_) => {
+                invalid(
+                    field,
+                    format!("Fecha inválida: \"{}\" (se espera RFC 3339)", value),
+                );
+                None
+            }
+        })
+    };
+    let from = timestamp("from", query.from.as_deref());
+    let to = timestamp("to", query.to.as_deref());
+    if let (Some(from), Some(to)) = (from, to) {
+        if from >= to {
+            invalid("to", "Debe ser posterior a 'from'".to_string());
+        }
+    }
+
+    let limit = match query.limit.as_deref().map(str::parse::<u32>) {
+        None => DEFAULT_LIMIT,
+        Some(Ok(limit)) if (1..=MAX_LIMIT).contains(&limit) => limit,
+        Some(_) => {
+            invalid("limit", format!("Debe estar entre 1 y {}", MAX_LIMIT));
+            DEFAULT_LIMIT
+        }
+    };
+    let offset = match query.offset.as_deref().map(str::parse::<u32>) {
+        None => 0,
+        Some(Ok(offset)) => offset,
+        Some(Err(_)) => {
+            invalid("offset", "Debe ser un entero no negativo".to_string());
+            0
+        }
+    };
+
+    if errors.is_empty() {
+        Ok(HistoryFilter {
+            timeframe,
+            from,
+            to,
+            limit,
+            offset,
+        })
+    } else {
+        Err(errors)
+    }
+}
@@ -0,0 +1,50 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use tracing::info;
+
+use crate::errors::error_response;
+use crate::jobs::{self, Trigger};
+use crate::trace::TraceContext;
+use crate::AppState;
+
+/// Jobs programados con su próxima ejecución y sus últimas ejecuciones
+pub async fn list(State(state): State<AppState>) -> impl IntoResponse {
+    Json(json!({ "jobs": state.jobs.statuses() }))
+}
+
+/// Ejecuta un job ahora, en segundo plano: el resultado aparece en `GET /api/jobs`
+pub async fn run_now(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    Path(name): Path<String>,
+) -> Response {
+    let Some(job) = state.jobs.get(&name) else {
+        return error_response(
+            StatusCode::NOT_FOUND,
+            "JOB_NOT_FOUND",
+            &format!("No existe el job '{}'", name),
+            &trace.trace_id,
+        );
+    };
+    if !job.try_begin() {
+        return error_response(
+            StatusCode::CONFLICT,
+            "JOB_ALREADY_RUNNING",
+            &format!("El job '{}' ya se está ejecutando", name),
+            &trace.trace_id,
+        );
+    }
+
+    info!(trace_id = %trace.trace_id, job = %name, "manual job run requested");
+    jobs::spawn_run(state, job, Trigger::Manual);
+    (
+        StatusCode::ACCEPTED,
+        Json(json!({ "job": name, "status": "started" })),
+    )
+        .into_response()
+}
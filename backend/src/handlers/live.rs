@@ -0,0 +1,137 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures_util::{stream, StreamExt};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::info;
+
+use crate::errors::validation_error;
+use crate::live::{LiveEvent, LiveHub, Topic};
+use crate::trace::TraceContext;
+use crate::validation::FieldError;
+use crate::AppState;
+
+/// Espera que se sugiere al navegador antes de reconectar (`retry:`)
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Deserialize)]
+pub struct LiveQuery {
+    /// Temas separados por comas (`health,metrics`); por defecto todos
+    pub topics: Option<String>,
+}
+
+/// Canal Server-Sent Events con los cambios de health, métricas y proveedores.
+///
+/// Cada evento lleva el tema como `event:` y un id creciente; al reconectar con
+/// `Last-Event-ID` se reenvía lo publicado desde entonces si sigue en el buffer, y si
+/// no el último estado de cada tema. Un comentario `heartbeat` mantiene viva la conexión.
+pub async fn live(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    headers: HeaderMap,
+    Query(query): Query<LiveQuery>,
+) -> Response {
+    let topics = match parse_topics(query.topics.as_deref()) {
+        Ok(topics) => topics,
+        Err(message) => {
+            let field = FieldError {
+                field: "topics".to_string(),
+                message,
+            };
+            return validation_error(&trace.trace_id, &[field]);
+        }
+    };
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+
+    let (backlog, receiver) = state.live.subscribe(&topics, last_event_id);
+    info!(
+        trace_id = %trace.trace_id,
+        topics = ?topics,
+        last_event_id,
+        subscribers = state.live.subscribers(),
+        "live subscriber connected"
+    );
+
+    let events = updates(state.live.clone(), topics, backlog, receiver).map(|event| {
+        Ok::<_, Infallible>(
+            Event::default()
+                .id(event.id.to_string())
+                .event(event.topic.as_str())
+                .data(event.data.to_string()),
+        )
+    });
+    let hello = stream::once(async { Ok(Event::default().retry(RECONNECT_DELAY)) });
+
+    Sse::new(hello.chain(events))
+        .keep_alive(
+            KeepAlive::new()
+                .interval(Duration::from_secs(state.settings().config.live_heartbeat_secs))
+                .text("heartbeat"),
+        )
+        .into_response()
+}
+
+fn parse_topics(topics: Option<&str>) -> Result<Vec<Topic>, String> {
+    let Some(topics) = topics.filter(|t| !t.trim().is_empty()) else {
+        return Ok(Topic::ALL.to_vec());
+    };
+    let mut parsed = Vec::new();
+    for name in topics.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let topic: Topic = name.parse()?;
+        if !parsed.contains(&topic) {
+            parsed.push(topic);
+        }
+    }
+    Ok(parsed)
+}
+
+/// El backlog y después los eventos nuevos de `topics`. Si el suscriptor se queda atrás
+/// y el canal descarta eventos, recibe el último estado de cada tema.
+fn updates(
+    hub: Arc<LiveHub>,
+    topics: Vec<Topic>,
+    backlog: Vec<LiveEvent>,
+    receiver: tokio::sync::broadcast::Receiver<LiveEvent>,
+) -> impl futures_util::Stream<Item = LiveEvent> {
+    let pending: VecDeque<LiveEvent> = backlog.into();
+    stream::unfold(
+        (receiver, pending, 0u64),
+        move |(mut receiver, mut pending, mut last_sent)| {
+            let hub = hub.clone();
+            let topics = topics.clone();
+            async move {
+                loop {
+                    if let Some(event) = pending.pop_front() {
+                        last_sent = last_sent.max(event.id);
+                        return Some((event, (receiver, pending, last_sent)));
+                    }
+                    match receiver.recv().await {
+                        Ok(event) if event.id > last_sent && topics.contains(&event.topic) => {
+                            pending.push_back(event)
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(_)) => pending.extend(
+                            hub.snapshot(&topics)
+                                .into_iter()
+                                .filter(|event| event.id > last_sent),
+                        ),
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }
+        },
+    )
+}
@@ -1,188 +1,299 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{Query, State},
+    http::{header, StatusCode},
     response::IntoResponse,
     Json,
 };
+use serde::Deserialize;
 use serde_json::json;
 use chrono::Utc;
-use uuid::Uuid;
+use std::time::{Duration, Instant};
+use crate::health::{self, HealthSample};
+use crate::inbestia::InbestiaClient;
+use crate::resilience::CircuitState;
+use crate::system;
+use crate::trace::TraceContext;
 use crate::AppState;
 
+pub mod admin;
+pub mod alerts;
+pub mod auth;
+pub mod batch;
+pub mod confluence;
+pub mod history;
+pub mod jobs;
+pub mod live;
+pub mod usage;
+pub mod watchlists;
+pub mod webhooks;
+
 /// Health check endpoint específico del gateway
-pub async fn health_check() -> impl IntoResponse {
+pub async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
     Json(json!({
         "status": "healthy",
         "service": "ghost-dashboard-gateway",
         "version": env!("CARGO_PKG_VERSION"),
         "timestamp": Utc::now(),
-        "uptime_secs": get_uptime_seconds()
+        "uptime_secs": state.system.uptime_secs()
     }))
 }
 
-/// Health check que verifica conectividad con la API externa
-pub async fn api_health_check(State(state): State<AppState>) -> impl IntoResponse {
-    let trace_id = Uuid::new_v4().to_string();
-    let timestamp = Utc::now();
+/// Estado de la conexión con inBestia según el último health check en segundo plano.
+/// No contacta con inBestia: responde al momento con la muestra más reciente.
+pub async fn api_health_check(State(state): State<AppState>, trace: TraceContext) -> impl IntoResponse {
+    let (status, mut body) = health_report(&state);
+    body["gateway"] = json!({
+        "status": "healthy",
+        "version": env!("CARGO_PKG_VERSION"),
+        "uptime_secs": state.system.uptime_secs()
+    });
+    body["timestamp"] = json!(Utc::now());
+    body["trace_id"] = json!(trace.trace_id);
+    (status, Json(body))
+}
 
-    match state.client
-        .get(&format!("{}/health", state.api_base))
-        .header("Authorization", format!("Bearer {}", state.api_key))
-        .timeout(std::time::Duration::from_secs(10))
-        .send()
-        .await
-    {
-        Ok(response) if response.status().is_success() => {
-            (StatusCode::OK, Json(json!({
-                "status": "healthy",
-                "gateway": {
-                    "status": "healthy",
-                    "version": env!("CARGO_PKG_VERSION"),
-                    "uptime_secs": get_uptime_seconds()
-                },
-                "external_api": {
-                    "status": "available",
-                    "response_time_ms": 0 // TODO: measure actual response time
-                },
-                "timestamp": timestamp,
-                "trace_id": trace_id
-            })))
-        }
-        Ok(response) => {
-            (StatusCode::SERVICE_UNAVAILABLE, Json(json!({
-                "status": "degraded",
-                "gateway": {
-                    "status": "healthy",
-                    "version": env!("CARGO_PKG_VERSION")
-                },
+/// Cuerpo de `/api/health` sin los campos propios de cada respuesta (gateway, timestamp,
+/// trace_id); lo publica también el canal en vivo cuando cambia
+pub fn health_report(state: &AppState) -> (StatusCode, serde_json::Value) {
+    let (circuit_state, upstream_circuit) = upstream_circuit(state);
+    let upstreams = state.upstreams.snapshot();
+
+    // La réplica correcta más rápida o, si ninguna lo está, el último fallo
+    let latest: Vec<HealthSample> = state
+        .upstreams
+        .nodes()
+        .iter()
+        .filter_map(|node| state.health.latest(&node.url))
+        .collect();
+    let sample = latest
+        .iter()
+        .filter(|sample| sample.ok)
+        .min_by(|a, b| a.latency_ms.total_cmp(&b.latency_ms))
+        .or_else(|| latest.iter().max_by_key(|sample| sample.at));
+
+    match sample {
+        None => {
+            (StatusCode::SERVICE_UNAVAILABLE, json!({
+                "status": "unknown",
                 "external_api": {
-                    "status": "error",
-                    "http_status": response.status().as_u16()
+                    "status": "unknown"
                 },
-                "error": format!("External API returned status: {}", response.status()),
-                "timestamp": timestamp,
-                "trace_id": trace_id
-            })))
+                "upstream_circuit": upstream_circuit,
+                "upstreams": upstreams,
+                "error": "No upstream health check has completed yet"
+            }))
         }
-        Err(e) => {
-            (StatusCode::SERVICE_UNAVAILABLE, Json(json!({
-                "status": "unhealthy",
-                "gateway": {
-                    "status": "healthy",
-                    "version": env!("CARGO_PKG_VERSION")
-                },
+        Some(sample) if sample.ok => {
+            // inBestia responde a /health pero alguna ruta puede tener el circuito abierto
+            let (status, message) = if circuit_state == CircuitState::Open {
+                ("degraded", Some("upstream circuit open"))
+            } else {
+                ("healthy", None)
+            };
+            (StatusCode::OK, json!({
+                "status": status,
+                "message": message,
                 "external_api": {
-                    "status": "unavailable",
-                    "error": e.to_string()
+                    "status": "available",
+                    "response_time_ms": sample.latency_ms.round() as u64,
+                    "checked_at": sample.at
                 },
-                "error": format!("Failed to connect to external API: {}", e),
-                "timestamp": timestamp,
-                "trace_id": trace_id
-            })))
+                "upstream_circuit": upstream_circuit,
+                "upstreams": upstreams
+            }))
         }
+        Some(sample) => match sample.http_status {
+            Some(http_status) => {
+                (StatusCode::SERVICE_UNAVAILABLE, json!({
+                    "status": "degraded",
+                    "external_api": {
+                        "status": "error",
+                        "http_status": http_status,
+                        "checked_at": sample.at
+                    },
+                    "upstream_circuit": upstream_circuit,
+                    "upstreams": upstreams,
+                    "error": format!("External API returned status: {}", http_status)
+                }))
+            }
+            None => {
+                let error = sample.error.clone().unwrap_or_default();
+                (StatusCode::SERVICE_UNAVAILABLE, json!({
+                    "status": "unhealthy",
+                    "external_api": {
+                        "status": "unavailable",
+                        "error": error,
+                        "checked_at": sample.at
+                    },
+                    "upstream_circuit": upstream_circuit,
+                    "upstreams": upstreams,
+                    "error": format!("Failed to connect to external API: {}", error)
+                }))
+            }
+        },
     }
 }
 
-/// Endpoint para métricas básicas del sistema
-pub async fn system_metrics(State(state): State<AppState>) -> impl IntoResponse {
-    let trace_id = Uuid::new_v4().to_string();
-    let timestamp = Utc::now();
+#[derive(Debug, Deserialize)]
+pub struct HealthHistoryQuery {
+    /// Muestras recientes a incluir por réplica
+    pub limit: Option<usize>,
+}
 
-    // Obtener métricas del sistema operativo
-    let system_metrics = get_system_metrics();
+/// Historial de health checks por réplica con disponibilidad y latencia p50/p95 por ventana
+pub async fn health_history(
+    State(state): State<AppState>,
+    Query(query): Query<HealthHistoryQuery>,
+) -> impl IntoResponse {
+    let now = Utc::now();
+    let limit = query.limit.unwrap_or(60);
+    let upstreams: Vec<serde_json::Value> = state
+        .upstreams
+        .nodes()
+        .iter()
+        .map(|node| {
+            let samples = state.health.samples(&node.url);
+            let windows: serde_json::Map<String, serde_json::Value> = health::WINDOWS
+                .iter()
+                .map(|(name, window)| {
+                    let stats = health::window_stats(&samples, now, *window);
+                    (name.to_string(), json!(stats))
+                })
+                .collect();
+            let recent = &samples[samples.len().saturating_sub(limit)..];
+            json!({
+                "url": node.url,
+                "healthy": node.is_healthy(),
+                "windows": windows,
+                "samples": recent
+            })
+        })
+        .collect();
 
-    // Intentar obtener métricas de la API externa
-    match state.client
-        .get(&format!("{}/api/v1/metrics/system", state.api_base))
-        .header("Authorization", format!("Bearer {}", state.api_key))
-        .timeout(std::time::Duration::from_secs(5))
+    Json(json!({
+        "interval_secs": state.settings().config.upstream_health_interval_secs,
+        "capacity": state.health.capacity(),
+        "upstreams": upstreams,
+        "timestamp": now
+    }))
+}
+
+/// JSON Schemas con los que el gateway valida los bodies, por nombre y con la ruta que los usa
+pub async fn request_schemas(State(state): State<AppState>) -> impl IntoResponse {
+    let settings = state.settings();
+    let schemas: serde_json::Map<String, serde_json::Value> = state
+        .validators
+        .schemas()
+        .map(|(name, schema)| {
+            let routes: Vec<&str> = settings
+                .config
+                .routes
+                .iter()
+                .filter(|route| route.schema.as_deref() == Some(name))
+                .map(|route| route.path.as_str())
+                .collect();
+            (name.to_string(), json!({ "routes": routes, "schema": schema }))
+        })
+        .collect();
+
+    Json(json!({ "schemas": schemas }))
+}
+
+/// Resultado de consultar `/health` de una réplica de inBestia
+pub struct UpstreamProbe {
+    pub outcome: Result<StatusCode, reqwest::Error>,
+    pub elapsed: Duration,
+}
+
+/// Consulta `/health` de la réplica `base`; lo usan `/api/health` y los health checks del pool
+pub async fn probe_upstream(state: &AppState, base: &str) -> UpstreamProbe {
+    let started = Instant::now();
+    let outcome = state
+        .client
+        .get(format!("{}/health", base))
+        .bearer_auth(state.settings().api_keys.current().expose())
+        .timeout(Duration::from_secs(10))
         .send()
         .await
-    {
-        Ok(response) if response.status().is_success() => {
-            match response.json::<serde_json::Value>().await {
-                Ok(external_metrics) => {
-                    Json(json!({
-                        "status": "success",
-                        "gateway_metrics": system_metrics,
-                        "external_api_metrics": external_metrics,
-                        "timestamp": timestamp,
-                        "trace_id": trace_id
-                    }))
-                }
-                Err(e) => {
-                    Json(json!({
-                        "status": "partial",
-                        "gateway_metrics": system_metrics,
-                        "external_api_metrics": null,
-                        "error": format!("Failed to parse external metrics: {}", e),
-                        "timestamp": timestamp,
-                        "trace_id": trace_id
-                    }))
-                }
-            }
-        }
-        Ok(response) => {
-            Json(json!({
-                "status": "partial",
-                "gateway_metrics": system_metrics,
-                "external_api_metrics": null,
-                "error": format!("External API returned status: {}", response.status()),
-                "timestamp": timestamp,
-                "trace_id": trace_id
-            }))
-        }
-        Err(e) => {
-            Json(json!({
-                "status": "partial",
-                "gateway_metrics": system_metrics,
-                "external_api_metrics": null,
-                "error": format!("Failed to connect to external API: {}", e),
-                "timestamp": timestamp,
-                "trace_id": trace_id
-            }))
-        }
+        .map(|response| response.status());
+    UpstreamProbe {
+        outcome,
+        elapsed: started.elapsed(),
     }
 }
 
-/// Obtener métricas básicas del sistema
-fn get_system_metrics() -> serde_json::Value {
-    json!({
-        "memory": {
-            "used_mb": get_memory_usage_mb(),
-            "available_mb": get_available_memory_mb()
-        },
-        "cpu": {
-            "usage_percent": get_cpu_usage_percent()
-        },
-        "uptime_secs": get_uptime_seconds(),
-        "active_connections": get_active_connections_count()
-    })
+/// Resumen de los circuit breakers hacia inBestia: el peor estado y el detalle por ruta
+fn upstream_circuit(state: &AppState) -> (CircuitState, serde_json::Value) {
+    let routes = state.breakers.snapshot();
+    let worst = if routes.values().any(|b| b.state == CircuitState::Open) {
+        CircuitState::Open
+    } else if routes.values().any(|b| b.state == CircuitState::HalfOpen) {
+        CircuitState::HalfOpen
+    } else {
+        CircuitState::Closed
+    };
+    (worst, json!({ "state": worst, "routes": routes }))
 }
 
-// Implementaciones básicas - en producción usarías librerías como `sysinfo`
-fn get_uptime_seconds() -> u64 {
-    static START_TIME: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
-    let start = START_TIME.get_or_init(|| std::time::Instant::now());
-    start.elapsed().as_secs()
+/// Métricas del gateway en formato texto de Prometheus
+pub async fn prometheus_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, crate::metrics::CONTENT_TYPE)],
+        state.metrics.render(&state),
+    )
 }
 
-fn get_memory_usage_mb() -> u64 {
-    // Placeholder - implementar con sysinfo en producción
-    0
+/// Endpoint para métricas básicas del sistema
+pub async fn system_metrics(State(state): State<AppState>, trace: TraceContext) -> impl IntoResponse {
+    let client = state
+        .inbestia()
+        .with_trace(&trace)
+        .with_timeout(Duration::from_secs(5));
+    let mut body = system_report(&state, &client).await;
+    body["timestamp"] = json!(Utc::now());
+    body["trace_id"] = json!(trace.trace_id);
+    Json(body)
 }
 
-fn get_available_memory_mb() -> u64 {
-    // Placeholder - implementar con sysinfo en producción
-    0
-}
+/// Métricas del gateway y de inBestia (sin timestamp ni trace_id), como las publica
+/// también el canal en vivo
+pub async fn system_report(state: &AppState, client: &InbestiaClient) -> serde_json::Value {
+    // Obtener métricas del proceso y del host
+    let system_metrics = get_system_metrics(state);
 
-fn get_cpu_usage_percent() -> f64 {
-    // Placeholder - implementar con sysinfo en producción
-    0.0
+    // Intentar obtener métricas de la API externa
+    match client.system_metrics().await {
+        Ok(external_metrics) => json!({
+            "status": "success",
+            "gateway_metrics": system_metrics,
+            "external_api_metrics": external_metrics
+        }),
+        Err(e) => json!({
+            "status": "partial",
+            "gateway_metrics": system_metrics,
+            "external_api_metrics": null,
+            "error": e.to_string()
+        }),
+    }
 }
 
-fn get_active_connections_count() -> u32 {
-    // Placeholder - implementar contador real
-    0
-}
\ No newline at end of file
+/// Métricas del proceso del gateway y del host (`null` donde /proc no está disponible)
+fn get_system_metrics(state: &AppState) -> serde_json::Value {
+    let host = system::host_memory();
+    json!({
+        "memory": {
+            "used_mb": system::process_rss_mb(),
+            "available_mb": host.map(|m| m.available_mb),
+            "host_total_mb": host.map(|m| m.total_mb),
+            "host_used_mb": host.map(|m| m.total_mb.saturating_sub(m.available_mb))
+        },
+        "cpu": {
+            "usage_percent": state.system.process_cpu_percent(),
+            "host_usage_percent": state.system.host_cpu_percent()
+        },
+        "uptime_secs": state.system.uptime_secs(),
+        "active_connections": state.system.active_connections(),
+        "open_fds": system::open_fds(),
+        "tokio": system::tokio_stats()
+    })
+}
@@ -0,0 +1,39 @@
+use axum::{
+    extract::{Request, State},
+    response::IntoResponse,
+    Json,
+};
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+
+use crate::ratelimit::{identify, secs_until_quota_reset};
+use crate::AppState;
+
+/// Uso actual del cliente que llama: rate limit y cuota diaria de cada clase
+pub async fn me_usage(State(state): State<AppState>, req: Request) -> impl IntoResponse {
+    let identity = identify(&state, &req);
+    let used_today = state.quotas.usage(&identity);
+
+    let classes: Map<String, Value> = state
+        .limiter
+        .classes()
+        .map(|class| {
+            let used = used_today.get(class).copied().unwrap_or(0);
+            let quota = state.quotas.limit(class);
+            let usage = json!({
+                "rate_limit": state.limiter.peek(&identity, class),
+                "daily_quota": quota,
+                "used_today": used,
+                "remaining_today": quota.map(|limit| limit.saturating_sub(used)),
+            });
+            (class.to_string(), usage)
+        })
+        .collect();
+
+    Json(json!({
+        "identity": identity,
+        "day": Utc::now().date_naive(),
+        "quota_resets_in_secs": secs_until_quota_reset(),
+        "classes": classes,
+    }))
+}
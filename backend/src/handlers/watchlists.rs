@@ -0,0 +1,162 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use ghost_shared::{timeframes, WatchlistRequest};
+use tracing::info;
+
+use crate::auth::AuthUser;
+use crate::errors::{error_response, validation_error};
+use crate::trace::TraceContext;
+use crate::validation::FieldError;
+use crate::watchlists::{self, WatchlistError};
+use crate::AppState;
+
+/// Watchlists del usuario de la sesión
+pub async fn list(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    Extension(user): Extension<AuthUser>,
+) -> Response {
+    let Some(db) = state.db.as_ref() else {
+        return database_not_configured(&trace);
+    };
+    respond(
+        watchlists::list(db, &user.username).await,
+        StatusCode::OK,
+        &trace,
+    )
+}
+
+pub async fn create(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    Extension(user): Extension<AuthUser>,
+    Json(request): Json<WatchlistRequest>,
+) -> Response {
+    let Some(db) = state.db.as_ref() else {
+        return database_not_configured(&trace);
+    };
+    let request = match normalize(request) {
+        Ok(request) => request,
+        Err(fields) => return validation_error(&trace.trace_id, &fields),
+    };
+    let result = watchlists::create(db, &user.username, &request).await;
+    if let Ok(watchlist) = &result {
+        info!(trace_id = %trace.trace_id, user = %user.username, id = watchlist.id, "watchlist created");
+    }
+    respond(result, StatusCode::CREATED, &trace)
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<i64>,
+) -> Response {
+    let Some(db) = state.db.as_ref() else {
+        return database_not_configured(&trace);
+    };
+    respond(
+        watchlists::get(db, &user.username, id).await,
+        StatusCode::OK,
+        &trace,
+    )
+}
+
+/// Renombra la watchlist y reemplaza sus símbolos (orden, timeframe y notas incluidos)
+pub async fn update(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<i64>,
+    Json(request): Json<WatchlistRequest>,
+) -> Response {
+    let Some(db) = state.db.as_ref() else {
+        return database_not_configured(&trace);
+    };
+    let request = match normalize(request) {
+        Ok(request) => request,
+        Err(fields) => return validation_error(&trace.trace_id, &fields),
+    };
+    respond(
+        watchlists::update(db, &user.username, id, &request).await,
+        StatusCode::OK,
+        &trace,
+    )
+}
+
+pub async fn delete(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<i64>,
+) -> Response {
+    let Some(db) = state.db.as_ref() else {
+        return database_not_configured(&trace);
+    };
+    match watchlists::delete(db, &user.username, id).await {
+        Ok(()) => {
+            info!(trace_id = %trace.trace_id, user = %user.username, id, "watchlist deleted");
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => e.to_response(&trace.trace_id),
+    }
+}
+
+/// 503 cuando el gateway arrancó sin DATABASE_URL (watchlists, historial y alertas)
+pub(crate) fn database_not_configured(trace: &TraceContext) -> Response {
+    error_response(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "DATABASE_NOT_CONFIGURED",
+        "La persistencia no está configurada (DATABASE_URL)",
+        &trace.trace_id,
+    )
+}
+
+fn respond<T: serde::Serialize>(
+    result: Result<T, WatchlistError>,
+    status: StatusCode,
+    trace: &TraceContext,
+) -> Response {
+    match result {
+        Ok(body) => (status, Json(body)).into_response(),
+        Err(e) => e.to_response(&trace.trace_id),
+    }
+}
+
+/// Símbolos en mayúsculas y timeframes por su nombre canónico; rechaza nombres en blanco
+/// y símbolos repetidos (el esquema ya validó formato y timeframes)
+fn normalize(mut request: WatchlistRequest) -> Result<WatchlistRequest, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    request.name = request.name.trim().to_string();
+    if request.name.is_empty() {
+        errors.push(FieldError {
+            field: "name".to_string(),
+            message: "El nombre no puede estar en blanco".to_string(),
+        });
+    }
+
+    let mut seen = Vec::new();
+    for (i, item) in request.items.iter_mut().enumerate() {
+        item.symbol = item.symbol.to_uppercase();
+        if let Some(tf) = timeframes::resolve(&item.timeframe) {
+            item.timeframe = tf.canonical.to_string();
+        }
+        if seen.contains(&item.symbol) {
+            errors.push(FieldError {
+                field: format!("items.{}.symbol", i),
+                message: format!("{} ya está en la lista", item.symbol),
+            });
+        }
+        seen.push(item.symbol.clone());
+    }
+
+    if errors.is_empty() {
+        Ok(request)
+    } else {
+        Err(errors)
+    }
+}
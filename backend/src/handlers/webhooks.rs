@@ -0,0 +1,147 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::errors::{error_response, validation_error};
+use crate::handlers::watchlists::database_not_configured;
+use crate::trace::TraceContext;
+use crate::validation::FieldError;
+use crate::webhooks::{self, DeliveryFilter, DeliveryStatus, Requeue};
+use crate::AppState;
+
+/// Entregas por página si no se indica `limit`
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct DeliveriesQuery {
+    /// `pending`, `delivered` o `dead` (la dead letter); por defecto todas
+    pub status: Option<String>,
+    pub webhook: Option<String>,
+    pub limit: Option<String>,
+    pub offset: Option<String>,
+}
+
+/// Entregas de webhooks, la más reciente primero
+pub async fn deliveries(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    Query(query): Query<DeliveriesQuery>,
+) -> Response {
+    let Some(db) = state.db.as_ref() else {
+        return database_not_configured(&trace);
+    };
+    let filter = match parse_filter(query) {
+        Ok(filter) => filter,
+        Err(fields) => return validation_error(&trace.trace_id, &fields),
+    };
+    match webhooks::deliveries(db, &filter).await {
+        Ok(deliveries) => Json(json!({ "deliveries": deliveries })).into_response(),
+        Err(e) => database_unavailable(&trace, e),
+    }
+}
+
+/// Vuelve a poner en cola una entrega de la dead letter
+pub async fn retry(
+    State(state): State<AppState>,
+    trace: TraceContext,
+    Path(id): Path<String>,
+) -> Response {
+    let Some(db) = state.db.as_ref() else {
+        return database_not_configured(&trace);
+    };
+    let not_found = || {
+        error_response(
+            StatusCode::NOT_FOUND,
+            "DELIVERY_NOT_FOUND",
+            &format!("No existe la entrega '{}'", id),
+            &trace.trace_id,
+        )
+    };
+    let Ok(delivery_id) = Uuid::parse_str(&id) else {
+        return not_found();
+    };
+
+    match webhooks::requeue(db, delivery_id).await {
+        Ok(Requeue::Requeued(delivery)) => {
+            info!(trace_id = %trace.trace_id, delivery = %delivery.id, webhook = %delivery.webhook, "webhook delivery requeued");
+            state.webhooks.wake();
+            (StatusCode::ACCEPTED, Json(delivery)).into_response()
+        }
+        Ok(Requeue::NotFound) => not_found(),
+        Ok(Requeue::NotDead(status)) => error_response(
+            StatusCode::CONFLICT,
+            "DELIVERY_NOT_DEAD",
+            &format!(
+                "Sólo se reintentan entregas muertas; esta está '{}'",
+                status.as_str()
+            ),
+            &trace.trace_id,
+        ),
+        Err(e) => database_unavailable(&trace, e),
+    }
+}
+
+fn database_unavailable(trace: &TraceContext, e: sqlx::Error) -> Response {
+    error!(trace_id = %trace.trace_id, error = %e, "webhook delivery query failed");
+    error_response(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "DATABASE_UNAVAILABLE",
+        "La base de datos no está disponible",
+        &trace.trace_id,
+    )
+}
+
+/// Valida los parámetros; devuelve todos los errores a la vez
+fn parse_filter(query: DeliveriesQuery) -> Result<DeliveryFilter, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    let mut invalid = |field: &str, message: String| {
+        errors.push(FieldError {
+            field: field.to_string(),
+            message,
+        })
+    };
+
+    let status = match query.status.as_deref().map(str::parse::<DeliveryStatus>) {
+        None => None,
+        Some(Ok(status)) => Some(status),
+        Some(Err(_)) => {
+            invalid("status", "Debe ser pending, delivered o dead".to_string());
+            None
+        }
+    };
+    let limit = match query.limit.as_deref().map(str::parse::<u32>) {
+        None => DEFAULT_LIMIT,
+        Some(Ok(limit)) if (1..=MAX_LIMIT).contains(&limit) => limit,
+        Some(_) => {
+            invalid("limit", format!("Debe estar entre 1 y {}", MAX_LIMIT));
+            DEFAULT_LIMIT
+        }
+    };
+    let offset = match query.offset.as_deref().map(str::parse::<u32>) {
+        None => 0,
+        Some(Ok(offset)) => offset,
+        Some(Err(_)) => {
+            invalid("offset", "Debe ser un entero no negativo".to_string());
+            0
+        }
+    };
+
+    if errors.is_empty() {
+        Ok(DeliveryFilter {
+            status,
+            webhook: query.webhook,
+            limit,
+            offset,
+        })
+    } else {
+        Err(errors)
+    }
+}
@@ -0,0 +1,113 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::config::Config;
+
+/// Ventanas resumidas en `/api/health/history`
+pub const WINDOWS: [(&str, Duration); 3] = [
+    ("15m", Duration::from_secs(15 * 60)),
+    ("1h", Duration::from_secs(60 * 60)),
+    ("24h", Duration::from_secs(24 * 60 * 60)),
+];
+
+/// Resultado de un health check activo contra una réplica
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthSample {
+    pub at: DateTime<Utc>,
+    pub ok: bool,
+    /// Estado HTTP de `/health`; `None` si no hubo respuesta
+    pub http_status: Option<u16>,
+    pub latency_ms: f64,
+    pub error: Option<String>,
+}
+
+/// Disponibilidad y latencia de una réplica en una ventana de tiempo
+#[derive(Debug, Clone, Serialize)]
+pub struct WindowStats {
+    pub samples: usize,
+    pub uptime_percent: Option<f64>,
+    /// Percentiles de latencia de los checks correctos
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+}
+
+/// Últimas muestras de health check de cada réplica, en un buffer circular por réplica
+pub struct HealthHistory {
+    capacity: usize,
+    samples: Mutex<HashMap<String, VecDeque<HealthSample>>>,
+}
+
+impl HealthHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            samples: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.upstream_health_history)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Añade una muestra descartando la más antigua si el buffer está lleno
+    pub fn record(&self, upstream: &str, sample: HealthSample) {
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        let history = samples.entry(upstream.to_string()).or_default();
+        if history.len() == self.capacity {
+            history.pop_front();
+        }
+        history.push_back(sample);
+    }
+
+    pub fn latest(&self, upstream: &str) -> Option<HealthSample> {
+        let samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        samples.get(upstream)?.back().cloned()
+    }
+
+    /// Muestras de la réplica, de la más antigua a la más reciente
+    pub fn samples(&self, upstream: &str) -> Vec<HealthSample> {
+        let samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        samples
+            .get(upstream)
+            .map(|history| history.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Resume las muestras posteriores a `now - window`
+pub fn window_stats(samples: &[HealthSample], now: DateTime<Utc>, window: Duration) -> WindowStats {
+    let since = chrono::Duration::from_std(window)
+        .map(|w| now - w)
+        .unwrap_or(DateTime::<Utc>::MIN_UTC);
+    let in_window: Vec<&HealthSample> = samples.iter().filter(|s| s.at >= since).collect();
+    let mut latencies: Vec<f64> = in_window
+        .iter()
+        .filter(|s| s.ok)
+        .map(|s| s.latency_ms)
+        .collect();
+    latencies.sort_by(f64::total_cmp);
+
+    WindowStats {
+        samples: in_window.len(),
+        uptime_percent: (!in_window.is_empty())
+            .then(|| latencies.len() as f64 / in_window.len() as f64 * 100.0),
+        p50_ms: percentile(&latencies, 50.0),
+        p95_ms: percentile(&latencies, 95.0),
+    }
+}
+
+/// Percentil por rango más cercano sobre valores ya ordenados
+fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
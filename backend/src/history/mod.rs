@@ -0,0 +1,289 @@
+use axum::{
+    body::{to_bytes, Body, BodyDataStream, Bytes},
+    http::{Request, StatusCode},
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use ghost_shared::responses::{AnalysisHistoryResponse, OperationChange, ScoreSeries};
+use ghost_shared::{timeframes, AnalysisRequest, AnalysisResponse};
+use sqlx::{types::Json, FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::alerts;
+use crate::cache::X_CACHE;
+use crate::proxy::{forward, payload_too_large};
+use crate::routes::RouteSpec;
+use crate::trace::TraceContext;
+use crate::webhooks::Webhooks;
+use crate::AppState;
+
+/// Filtros de `GET /api/history/{symbol}`
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    /// Nombre canónico del timeframe; todos si es `None`
+    pub timeframe: Option<String>,
+    /// Desde (incluido)
+    pub from: Option<DateTime<Utc>>,
+    /// Hasta (excluido)
+    pub to: Option<DateTime<Utc>>,
+    pub limit: u32,
+    pub offset: u32,
+}
+
+/// Símbolo en mayúsculas y timeframe canónico con los que se guarda y se evalúa un análisis
+fn key(request: &AnalysisRequest) -> (String, String) {
+    let timeframe = timeframes::resolve(&request.timeframe)
+        .map(|tf| tf.canonical.to_string())
+        .unwrap_or_else(|| request.timeframe.clone());
+    (request.symbol.to_uppercase(), timeframe)
+}
+
+/// Guarda un análisis correcto de inBestia, recibido en `analyzed_at`
+pub async fn record(
+    db: &PgPool,
+    request: &AnalysisRequest,
+    response: &AnalysisResponse,
+    analyzed_at: DateTime<Utc>,
+    trace_id: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let (symbol, timeframe) = key(request);
+    sqlx::query(
+        "INSERT INTO analysis_history (
+             symbol, timeframe, analyzed_at, params, score, technical_score, fundamental_score,
+             trend_score, momentum_score, volatility_score, volume_score, suggested_operation,
+             explanation, trace_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)",
+    )
+    .bind(symbol)
+    .bind(timeframe)
+    .bind(analyzed_at)
+    .bind(Json(request))
+    .bind(response.score)
+    .bind(response.technical_score)
+    .bind(response.fundamental_score)
+    .bind(response.trend_score)
+    .bind(response.momentum_score)
+    .bind(response.volatility_score)
+    .bind(response.volume_score)
+    .bind(&response.suggested_operation)
+    .bind(&response.explanation)
+    .bind(trace_id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Destino de cada análisis nuevo (de un usuario, un lote o un job): el historial, si
+/// HISTORY_ENABLED, y las reglas de alerta
+#[derive(Clone)]
+pub struct AnalysisSink {
+    db: PgPool,
+    history: bool,
+    webhooks: Arc<Webhooks>,
+}
+
+impl AnalysisSink {
+    /// `None` sin base de datos
+    pub fn from_state(state: &AppState) -> Option<Self> {
+        state.db.clone().map(|db| Self {
+            db,
+            history: state.settings().config.history_enabled,
+            webhooks: state.webhooks.clone(),
+        })
+    }
+
+    /// Procesa el análisis en segundo plano: un fallo de la base de datos no afecta a la
+    /// respuesta. La marca de tiempo se toma ahora, no al insertar, para conservar el orden
+    /// de los análisis.
+    pub fn spawn_record(
+        &self,
+        request: AnalysisRequest,
+        response: AnalysisResponse,
+        trace_id: Option<String>,
+    ) {
+        let analyzed_at = Utc::now();
+        let sink = self.clone();
+        tokio::spawn(async move {
+            if sink.history {
+                if let Err(e) =
+                    record(&sink.db, &request, &response, analyzed_at, trace_id.as_deref()).await
+                {
+                    warn!(trace_id = ?trace_id, symbol = %request.symbol, error = %e, "analysis history not recorded");
+                }
+            }
+            let (symbol, timeframe) = key(&request);
+            if let Err(e) =
+                alerts::evaluate(&sink.db, &sink.webhooks, &symbol, &timeframe, &response, analyzed_at)
+                    .await
+            {
+                warn!(trace_id = ?trace_id, symbol = %symbol, error = %e, "alert rules not evaluated");
+            }
+        });
+    }
+}
+
+/// Reenvía a inBestia una ruta con `history = true` y, si inBestia responde 200, pasa el
+/// análisis al `AnalysisSink`. Las respuestas servidas desde la caché no cuentan otra vez.
+pub async fn forward_recorded(s: AppState, req: Request<Body>, route: &RouteSpec) -> Response {
+    let Some(sink) = AnalysisSink::from_state(&s) else {
+        return forward(s, req, route).await;
+    };
+
+    let max_size = route.body_limit(&s.settings().config);
+    let trace_id = TraceContext::of(&req).trace_id;
+    let (parts, body) = req.into_parts();
+    let body = match to_bytes(body, max_size).await {
+        Ok(body) => body,
+        Err(_) => return payload_too_large(max_size, &trace_id),
+    };
+    let request: Option<AnalysisRequest> = serde_json::from_slice(&body).ok();
+    let response = forward(s, Request::from_parts(parts, Body::from(body)), route).await;
+
+    let cache_hit = response.headers().get(X_CACHE).is_some_and(|v| v == "HIT");
+    let Some(request) = request.filter(|_| response.status() == StatusCode::OK && !cache_hit)
+    else {
+        return response;
+    };
+
+    // El body se sigue transmitiendo al cliente; al terminar se guarda una copia
+    let (parts, body) = response.into_parts();
+    let recorder = Recorder {
+        stream: body.into_data_stream(),
+        buffer: Some(Vec::new()),
+        sink,
+        request,
+        trace_id,
+    };
+    Response::from_parts(
+        parts,
+        Body::from_stream(futures_util::stream::unfold(recorder, Recorder::next)),
+    )
+}
+
+/// Copia el body del análisis mientras se transmite; al terminar sin errores lo guarda
+struct Recorder {
+    stream: BodyDataStream,
+    /// `None` si la transmisión falló: el análisis no se guarda
+    buffer: Option<Vec<u8>>,
+    sink: AnalysisSink,
+    request: AnalysisRequest,
+    trace_id: String,
+}
+
+impl Recorder {
+    async fn next(mut self) -> Option<(Result<Bytes, axum::Error>, Self)> {
+        match self.stream.next().await {
+            Some(Ok(chunk)) => {
+                if let Some(buffer) = self.buffer.as_mut() {
+                    buffer.extend_from_slice(&chunk);
+                }
+                Some((Ok(chunk), self))
+            }
+            Some(Err(e)) => {
+                self.buffer = None;
+                Some((Err(e), self))
+            }
+            None => {
+                if let Some(buffer) = self.buffer.take() {
+                    match serde_json::from_slice::<AnalysisResponse>(&buffer) {
+                        Ok(analysis) => {
+                            self.sink
+                                .spawn_record(self.request, analysis, Some(self.trace_id))
+                        }
+                        Err(e) => {
+                            warn!(trace_id = %self.trace_id, error = %e, "analysis response not recorded")
+                        }
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Historial de `symbol` (ya en mayúsculas) según `filter`
+pub async fn query(
+    db: &PgPool,
+    symbol: &str,
+    filter: &HistoryFilter,
+) -> Result<AnalysisHistoryResponse, sqlx::Error> {
+    let rows: Vec<HistoryRow> = sqlx::query_as(
+        "SELECT analyzed_at, timeframe, score, technical_score, fundamental_score, trend_score,
+                momentum_score, volatility_score, volume_score, suggested_operation,
+                previous_operation
+         FROM (
+             SELECT *, LAG(suggested_operation)
+                 OVER (PARTITION BY timeframe ORDER BY analyzed_at, id) AS previous_operation
+             FROM analysis_history
+             WHERE symbol = $1
+               AND ($2::text IS NULL OR timeframe = $2)
+               AND ($3::timestamptz IS NULL OR analyzed_at >= $3)
+               AND ($4::timestamptz IS NULL OR analyzed_at < $4)
+         ) history
+         ORDER BY analyzed_at, id
+         LIMIT $5 OFFSET $6",
+    )
+    .bind(symbol)
+    .bind(filter.timeframe.as_deref())
+    .bind(filter.from)
+    .bind(filter.to)
+    // Una fila de más para saber si hay otra página
+    .bind(i64::from(filter.limit) + 1)
+    .bind(i64::from(filter.offset))
+    .fetch_all(db)
+    .await?;
+
+    let has_more = rows.len() > filter.limit as usize;
+    let mut series: HashMap<String, ScoreSeries> = HashMap::new();
+    let mut operation_changes = Vec::new();
+    for row in rows.into_iter().take(filter.limit as usize) {
+        if let Some(previous) = row
+            .previous_operation
+            .filter(|previous| *previous != row.suggested_operation)
+        {
+            operation_changes.push(OperationChange {
+                at: row.analyzed_at,
+                timeframe: row.timeframe.clone(),
+                from: previous,
+                to: row.suggested_operation.clone(),
+            });
+        }
+        let points = series.entry(row.timeframe).or_default();
+        points.timestamps.push(row.analyzed_at);
+        points.score.push(row.score);
+        points.technical_score.push(row.technical_score);
+        points.fundamental_score.push(row.fundamental_score);
+        points.trend_score.push(row.trend_score);
+        points.momentum_score.push(row.momentum_score);
+        points.volatility_score.push(row.volatility_score);
+        points.volume_score.push(row.volume_score);
+        points.suggested_operation.push(row.suggested_operation);
+    }
+
+    Ok(AnalysisHistoryResponse {
+        symbol: symbol.to_string(),
+        series,
+        operation_changes,
+        limit: filter.limit,
+        offset: filter.offset,
+        has_more,
+    })
+}
+
+#[derive(FromRow)]
+struct HistoryRow {
+    analyzed_at: DateTime<Utc>,
+    timeframe: String,
+    score: f64,
+    technical_score: f64,
+    fundamental_score: Option<f64>,
+    trend_score: f64,
+    momentum_score: f64,
+    volatility_score: f64,
+    volume_score: f64,
+    suggested_operation: String,
+    previous_operation: Option<String>,
+}
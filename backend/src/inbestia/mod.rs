@@ -0,0 +1,359 @@
+use axum::{
+    http::{HeaderMap, Method, StatusCode},
+    response::Response,
+};
+use ghost_shared::responses::{
+    AnalysisResponse, BatchItemError, CompareResponse, DataQualityMetrics, HistoricalResponse,
+    IndicatorsResponse, ProviderStatus, ReconciliationMetrics, SymbolErrorResponse, SystemMetrics,
+    TimeframesConfig,
+};
+use ghost_shared::{AnalysisRequest, CompareRequest, HistoricalRequest, IndicatorsRequest};
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::errors::error_response;
+use crate::history::AnalysisSink;
+use crate::metrics::GatewayMetrics;
+use crate::proxy::{circuit_open, error_kind};
+use crate::resilience::{is_upstream_failure, CircuitBreakers, RetryPolicy};
+use crate::trace::TraceContext;
+use crate::upstream::{ApiKeys, UpstreamPool};
+use crate::AppState;
+
+/// Fallo de una llamada a inBestia
+#[derive(Debug)]
+pub enum InbestiaError {
+    /// inBestia no respondió a tiempo
+    Timeout,
+    /// No se pudo conectar con la réplica
+    Connect(String),
+    /// Cualquier otro fallo de transporte
+    Transport(String),
+    /// El circuit breaker del endpoint está abierto
+    CircuitOpen { retry_after: Duration },
+    /// inBestia respondió con un estado de error
+    Status { status: StatusCode, body: String },
+    /// La respuesta no tiene el formato esperado
+    Decode(String),
+}
+
+impl fmt::Display for InbestiaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout => write!(f, "inBestia timed out"),
+            Self::Connect(e) => write!(f, "cannot connect to inBestia: {}", e),
+            Self::Transport(e) => write!(f, "inBestia request failed: {}", e),
+            Self::CircuitOpen { retry_after } => {
+                write!(f, "inBestia circuit open (retry in {:?})", retry_after)
+            }
+            Self::Status { status, body } => write!(f, "inBestia returned {}: {}", status, body),
+            Self::Decode(e) => write!(f, "invalid inBestia response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for InbestiaError {}
+
+impl From<reqwest::Error> for InbestiaError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Self::Timeout
+        } else if e.is_connect() {
+            Self::Connect(e.to_string())
+        } else if e.is_decode() {
+            Self::Decode(e.to_string())
+        } else {
+            Self::Transport(e.to_string())
+        }
+    }
+}
+
+impl InbestiaError {
+    /// Clase del fallo para la métrica `upstream_errors_total`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Timeout => "timeout",
+            Self::Connect(_) => "connect",
+            Self::Transport(_) => "transport",
+            Self::CircuitOpen { .. } => "circuit_open",
+            Self::Status { .. } => "bad_status",
+            Self::Decode(_) => "decode",
+        }
+    }
+
+    /// Sugerencias de inBestia cuando rechaza un símbolo desconocido (400)
+    pub fn symbol_not_found(&self) -> Option<SymbolErrorResponse> {
+        match self {
+            Self::Status { status, body } if *status == StatusCode::BAD_REQUEST => {
+                serde_json::from_str(body).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Código de error del gateway (`error.code`) para este fallo
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Timeout => "UPSTREAM_TIMEOUT",
+            Self::Connect(_) | Self::Transport(_) => "UPSTREAM_UNAVAILABLE",
+            Self::CircuitOpen { .. } => "UPSTREAM_CIRCUIT_OPEN",
+            Self::Status { status, .. } if status.is_client_error() => "UPSTREAM_REJECTED",
+            Self::Status { .. } => "UPSTREAM_ERROR",
+            Self::Decode(_) => "UPSTREAM_INVALID_RESPONSE",
+        }
+    }
+
+    /// Mensaje para el cliente (el detalle técnico queda en `Display`)
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::Timeout => "inBestia no respondió a tiempo",
+            Self::Connect(_) | Self::Transport(_) => "Servicio no disponible",
+            Self::CircuitOpen { .. } => {
+                "inBestia no está disponible (circuito abierto); reintentar más tarde"
+            }
+            Self::Status { status, .. } if status.is_client_error() => {
+                "inBestia rechazó la petición"
+            }
+            Self::Status { .. } => "inBestia respondió con un error",
+            Self::Decode(_) => "Respuesta de inBestia inesperada",
+        }
+    }
+
+    /// Error de un elemento de un análisis agregado (lotes, confluencia), con las
+    /// sugerencias de inBestia si el símbolo no existe
+    pub fn item_error(&self) -> BatchItemError {
+        match self.symbol_not_found() {
+            Some(not_found) => BatchItemError {
+                code: "SYMBOL_NOT_FOUND".to_string(),
+                message: not_found.message,
+                suggestions: not_found.suggestions,
+            },
+            None => BatchItemError {
+                code: self.code().to_string(),
+                message: self.message().to_string(),
+                suggestions: Vec::new(),
+            },
+        }
+    }
+
+    /// Respuesta de error estándar del gateway para este fallo
+    pub fn to_response(&self, trace_id: &str) -> Response {
+        let status = match self {
+            Self::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::CircuitOpen { retry_after } => return circuit_open(*retry_after, trace_id),
+            // Los 4xx son culpa de la request y se devuelven con su estado
+            Self::Status { status, .. } if status.is_client_error() => *status,
+            _ => StatusCode::BAD_GATEWAY,
+        };
+        error_response(status, self.code(), self.message(), trace_id)
+    }
+}
+
+/// Cliente tipado de inBestia: un método por endpoint.
+///
+/// Comparte con el proxy la selección de réplica, el circuit breaker de cada endpoint,
+/// los reintentos con failover de los GET y las métricas de upstream.
+#[derive(Clone)]
+pub struct InbestiaClient {
+    http: Client,
+    upstreams: Arc<UpstreamPool>,
+    breakers: Arc<CircuitBreakers>,
+    metrics: Arc<GatewayMetrics>,
+    retry: RetryPolicy,
+    api_keys: Arc<ApiKeys>,
+    timeout: Duration,
+    trace: Option<TraceContext>,
+    /// Historial y reglas de alerta (None si no hay base de datos)
+    sink: Option<AnalysisSink>,
+}
+
+impl InbestiaClient {
+    pub fn new(state: &AppState) -> Self {
+        let settings = state.settings();
+        Self {
+            http: state.client.clone(),
+            upstreams: state.upstreams.clone(),
+            breakers: state.breakers.clone(),
+            metrics: state.metrics.clone(),
+            retry: settings.retry.clone(),
+            api_keys: settings.api_keys.clone(),
+            timeout: Duration::from_secs(settings.config.request_timeout_secs),
+            trace: None,
+            sink: AnalysisSink::from_state(state),
+        }
+    }
+
+    /// Propaga `traceparent` y `X-Request-Id` de la request del cliente
+    pub fn with_trace(mut self, trace: &TraceContext) -> Self {
+        self.trace = Some(trace.clone());
+        self
+    }
+
+    /// Timeout por llamada (por defecto REQUEST_TIMEOUT)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub async fn analyze(
+        &self,
+        request: &AnalysisRequest,
+    ) -> Result<AnalysisResponse, InbestiaError> {
+        let response: AnalysisResponse = self.post("/api/v1/analyze", request).await?;
+        if let Some(sink) = &self.sink {
+            sink.spawn_record(
+                request.clone(),
+                response.clone(),
+                self.trace.as_ref().map(|trace| trace.trace_id.clone()),
+            );
+        }
+        Ok(response)
+    }
+
+    pub async fn historical(
+        &self,
+        request: &HistoricalRequest,
+    ) -> Result<HistoricalResponse, InbestiaError> {
+        self.post("/api/v1/historical", request).await
+    }
+
+    pub async fn indicators(
+        &self,
+        request: &IndicatorsRequest,
+    ) -> Result<IndicatorsResponse, InbestiaError> {
+        self.post("/api/v1/indicators", request).await
+    }
+
+    pub async fn compare(
+        &self,
+        request: &CompareRequest,
+    ) -> Result<CompareResponse, InbestiaError> {
+        self.post("/api/v1/compare", request).await
+    }
+
+    pub async fn providers_status(&self) -> Result<Vec<ProviderStatus>, InbestiaError> {
+        self.get("/api/v1/providers/status").await
+    }
+
+    pub async fn system_metrics(&self) -> Result<SystemMetrics, InbestiaError> {
+        self.get("/api/v1/metrics/system").await
+    }
+
+    pub async fn reconciliation(&self) -> Result<ReconciliationMetrics, InbestiaError> {
+        self.get("/api/v1/metrics/reconciliation").await
+    }
+
+    pub async fn data_quality(&self) -> Result<DataQualityMetrics, InbestiaError> {
+        self.get("/api/v1/metrics/data_quality").await
+    }
+
+    pub async fn timeframes_config(&self) -> Result<TimeframesConfig, InbestiaError> {
+        self.get("/api/v1/timeframes/config").await
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, InbestiaError> {
+        self.call(Method::GET, path, None::<&()>).await
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, InbestiaError> {
+        self.call(Method::POST, path, Some(body)).await
+    }
+
+    /// Llama a `path` en una réplica de inBestia. Los GET que fallan por culpa del
+    /// servicio se reintentan con backoff en otra réplica, como en `proxy()`, y un 401
+    /// se repite con la siguiente clave de reserva.
+    async fn call<B: Serialize, T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&B>,
+    ) -> Result<T, InbestiaError> {
+        let mut headers = HeaderMap::new();
+        if let Some(trace) = &self.trace {
+            trace.apply(&mut headers);
+        }
+        let retryable = method == Method::GET;
+        let mut attempt = 0;
+        let mut upstream = self.upstreams.select();
+        let mut keys = self.api_keys.attempts();
+        let mut key = keys.next();
+
+        let result = loop {
+            let permit = match self.breakers.acquire(path) {
+                Ok(permit) => permit,
+                Err(retry_after) => {
+                    self.metrics.upstream_error(path, "circuit_open");
+                    return Err(InbestiaError::CircuitOpen { retry_after });
+                }
+            };
+
+            let mut request = self
+                .http
+                .request(method.clone(), format!("{}{}", upstream.url, path))
+                .timeout(self.timeout)
+                .bearer_auth(key.map(|(_, key)| key.expose()).unwrap_or_default())
+                .headers(headers.clone());
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+
+            let started = Instant::now();
+            let result = request.send().await;
+            let failed = match &result {
+                Ok(response) => is_upstream_failure(response.status()),
+                Err(_) => true,
+            };
+            permit.record(!failed);
+            self.upstreams.report(&upstream, !failed, started.elapsed());
+            self.metrics
+                .observe_upstream(path, &upstream.url, started.elapsed());
+            if failed {
+                self.metrics.upstream_error(path, error_kind(&result));
+            }
+
+            if let (Ok(response), Some((index, _))) = (&result, key) {
+                if response.status() != StatusCode::UNAUTHORIZED {
+                    if !failed {
+                        self.api_keys.accepted(index);
+                    }
+                } else if let Some(next) = keys.next() {
+                    warn!(upstream = %upstream.url, path, key = index, "inBestia rejected the API key, trying a fallback key");
+                    key = Some(next);
+                    continue;
+                }
+            }
+
+            if failed && retryable && attempt < self.retry.max_retries {
+                let delay = self.retry.backoff(attempt);
+                attempt += 1;
+                warn!(upstream = %upstream.url, path, attempt, "inBestia call failed, retrying");
+                tokio::time::sleep(delay).await;
+                upstream = self.upstreams.select_excluding(Some(&upstream.url));
+                continue;
+            }
+            break result;
+        };
+
+        let response = result?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(InbestiaError::Status {
+                status,
+                body: response.text().await.unwrap_or_default(),
+            });
+        }
+        let bytes = response.bytes().await?;
+        serde_json::from_slice(&bytes).map_err(|e| {
+            self.metrics.upstream_error(path, "decode");
+            InbestiaError::Decode(e.to_string())
+        })
+    }
+}
@@ -0,0 +1,488 @@
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use futures_util::StreamExt;
+use ghost_shared::requests::is_valid_symbol;
+use ghost_shared::responses::BatchOutcome;
+use ghost_shared::{timeframes, BatchAnalysisRequest};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::types::Json;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::handlers::batch;
+use crate::AppState;
+
+/// Qué hace un job en cada ejecución
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Re-analiza `symbols` × `timeframes`; los resultados van al historial de análisis
+    Analyze,
+    /// Guarda un snapshot de `/api/v1/providers/status`
+    ProvidersStatus,
+    /// Guarda un snapshot de `/api/v1/metrics/data_quality`
+    DataQuality,
+}
+
+impl JobKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobKind::Analyze => "analyze",
+            JobKind::ProvidersStatus => "providers_status",
+            JobKind::DataQuality => "data_quality",
+        }
+    }
+}
+
+/// Un job programado del manifiesto (JOBS_FILE)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobSpec {
+    /// Identificador del job (`/api/jobs/{name}/run`)
+    pub name: String,
+    /// Expresión cron: 5 campos (minuto hora día mes día-semana), 6 con segundos o
+    /// `@hourly`, `@daily`, `@weekly`...; en UTC. Ojo: en número el domingo es 1, no 0.
+    pub schedule: String,
+    pub kind: JobKind,
+    /// Símbolos a re-analizar (sólo `analyze`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub symbols: Vec<String>,
+    /// Timeframes de cada símbolo (sólo `analyze`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timeframes: Vec<String>,
+    #[serde(default)]
+    pub include_fundamental: bool,
+    /// Retraso aleatorio de 0 a `jitter_secs` sobre cada ejecución programada
+    #[serde(default)]
+    pub jitter_secs: u64,
+    /// Llamadas simultáneas a inBestia (por defecto BATCH_CONCURRENCY)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+    /// Con `false` sólo se ejecuta a mano
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JobManifest {
+    #[serde(default)]
+    jobs: Vec<JobSpec>,
+}
+
+/// Parsea un manifiesto de jobs TOML (o JSON si `origin` termina en `.json`)
+pub fn parse_manifest(source: &str, origin: &str) -> anyhow::Result<Vec<JobSpec>> {
+    let manifest: JobManifest = if origin.ends_with(".json") {
+        serde_json::from_str(source)
+            .map_err(|e| anyhow::anyhow!("Invalid job manifest {}: {}", origin, e))?
+    } else {
+        toml::from_str(source)
+            .map_err(|e| anyhow::anyhow!("Invalid job manifest {}: {}", origin, e))?
+    };
+    Ok(manifest.jobs)
+}
+
+/// Lee y parsea el manifiesto de `path`
+pub fn load_manifest(path: &Path) -> anyhow::Result<Vec<JobSpec>> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Cannot read job manifest {}: {}", path.display(), e))?;
+    parse_manifest(&source, &path.display().to_string())
+}
+
+/// Parsea una expresión cron. Las de 5 campos siguen al crontab clásico: se ejecutan en
+/// el segundo 0 y el día de la semana va de 0 a 7 (0 y 7 son domingo, 1 es lunes).
+pub fn parse_schedule(expression: &str) -> Result<Schedule, String> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    let expression = if fields.len() == 5 {
+        format!(
+            "0 {} {}",
+            fields[..4].join(" "),
+            crontab_day_of_week(fields[4])
+        )
+    } else {
+        expression.trim().to_string()
+    };
+    Schedule::from_str(&expression).map_err(|e| e.to_string())
+}
+
+/// Pasa el día de la semana del crontab (0-7, 0 = domingo) al de la crate `cron`
+/// (1-7, 1 = domingo). Los nombres (`Mon-Fri`) y `*` no cambian; los elementos que no
+/// se entienden tampoco, para que `cron` informe del error.
+fn crontab_day_of_week(field: &str) -> String {
+    field
+        .split(',')
+        .map(|element| match crontab_days(element) {
+            Some(days) => days
+                .into_iter()
+                .map(|day| (day % 7 + 1).to_string())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>()
+                .join(","),
+            None => element.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Días (0-7) de un elemento numérico: `5`, `1-5`, `1-5/2` o `1/2` (de 1 a 7 cada 2)
+fn crontab_days(element: &str) -> Option<Vec<u32>> {
+    let (range, step) = match element.split_once('/') {
+        Some((range, step)) => (range, step.parse::<usize>().ok().filter(|step| *step > 0)?),
+        None => (element, 1),
+    };
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (start.parse::<u32>().ok()?, end.parse::<u32>().ok()?),
+        None => {
+            let start = range.parse::<u32>().ok()?;
+            (start, if element.contains('/') { 7 } else { start })
+        }
+    };
+    if start > end || end > 7 {
+        return None;
+    }
+    Some((start..=end).step_by(step).collect())
+}
+
+/// Comprueba el manifiesto completo y devuelve todos los errores encontrados
+pub fn validate_jobs(jobs: &[JobSpec]) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    let mut names = HashSet::new();
+
+    for (i, job) in jobs.iter().enumerate() {
+        let at = format!("job #{} ({})", i + 1, job.name);
+
+        if job.name.is_empty()
+            || !job
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            errors.push(format!(
+                "{}: name must be non-empty and use only letters, digits, '-' or '_'",
+                at
+            ));
+        } else if !names.insert(job.name.as_str()) {
+            errors.push(format!("{}: duplicate job name", at));
+        }
+        if let Err(e) = parse_schedule(&job.schedule) {
+            errors.push(format!(
+                "{}: invalid schedule '{}': {}",
+                at, job.schedule, e
+            ));
+        }
+        if job.max_concurrency == Some(0) {
+            errors.push(format!("{}: max_concurrency must be greater than zero", at));
+        }
+
+        match job.kind {
+            JobKind::Analyze => {
+                if job.symbols.is_empty() || job.timeframes.is_empty() {
+                    errors.push(format!(
+                        "{}: analyze jobs need at least one symbol and one timeframe",
+                        at
+                    ));
+                }
+                for symbol in job.symbols.iter().filter(|s| !is_valid_symbol(s)) {
+                    errors.push(format!("{}: invalid symbol '{}'", at, symbol));
+                }
+                for timeframe in job
+                    .timeframes
+                    .iter()
+                    .filter(|tf| timeframes::resolve(tf).is_none())
+                {
+                    errors.push(format!("{}: unknown timeframe '{}'", at, timeframe));
+                }
+            }
+            JobKind::ProvidersStatus | JobKind::DataQuality => {
+                if !job.symbols.is_empty() || !job.timeframes.is_empty() {
+                    errors.push(format!(
+                        "{}: symbols and timeframes only apply to analyze jobs",
+                        at
+                    ));
+                }
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Origen de una ejecución
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    Scheduled,
+    Manual,
+}
+
+/// Resultado de una ejecución
+#[derive(Debug, Clone, Serialize)]
+pub struct JobRun {
+    pub trigger: Trigger,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Análisis correctos y fallidos, o si se guardó el snapshot
+    pub summary: Value,
+}
+
+/// Estado de un job para `GET /api/jobs`
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    #[serde(flatten)]
+    pub spec: JobSpec,
+    pub running: bool,
+    /// Próxima ejecución programada, jitter incluido (ninguna si está deshabilitado)
+    pub next_run: Option<DateTime<Utc>>,
+    /// Últimas ejecuciones, la más reciente primero
+    pub runs: Vec<JobRun>,
+}
+
+struct JobState {
+    running: bool,
+    next_run: Option<DateTime<Utc>>,
+    runs: VecDeque<JobRun>,
+}
+
+/// Un job con su calendario y sus últimas ejecuciones
+pub struct Job {
+    pub spec: JobSpec,
+    schedule: Schedule,
+    history_size: usize,
+    state: Mutex<JobState>,
+}
+
+impl Job {
+    /// Marca el job como en marcha; `false` si ya lo estaba (no se solapan ejecuciones)
+    pub fn try_begin(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        !std::mem::replace(&mut state.running, true)
+    }
+
+    fn finish(&self, run: JobRun) {
+        let mut state = self.state.lock().unwrap();
+        state.running = false;
+        if state.runs.len() == self.history_size {
+            state.runs.pop_back();
+        }
+        state.runs.push_front(run);
+    }
+
+    pub fn status(&self) -> JobStatus {
+        let state = self.state.lock().unwrap();
+        JobStatus {
+            spec: self.spec.clone(),
+            running: state.running,
+            next_run: state.next_run,
+            runs: state.runs.iter().cloned().collect(),
+        }
+    }
+}
+
+/// Jobs del manifiesto
+pub struct JobRegistry {
+    jobs: Vec<Arc<Job>>,
+}
+
+impl JobRegistry {
+    /// Crea los jobs de `config.jobs` (manifiesto ya validado)
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let jobs = config
+            .jobs
+            .iter()
+            .map(|spec| {
+                let schedule = parse_schedule(&spec.schedule).map_err(|e| {
+                    anyhow::anyhow!(
+                        "job {}: invalid schedule '{}': {}",
+                        spec.name,
+                        spec.schedule,
+                        e
+                    )
+                })?;
+                Ok(Arc::new(Job {
+                    spec: spec.clone(),
+                    schedule,
+                    history_size: config.jobs_history_size,
+                    state: Mutex::new(JobState {
+                        running: false,
+                        next_run: None,
+                        runs: VecDeque::new(),
+                    }),
+                }))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { jobs })
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<Job>> {
+        self.jobs.iter().find(|job| job.spec.name == name).cloned()
+    }
+
+    pub fn statuses(&self) -> Vec<JobStatus> {
+        self.jobs.iter().map(|job| job.status()).collect()
+    }
+}
+
+/// Lanza un bucle por cada job habilitado que lo ejecuta según su calendario
+pub fn spawn_scheduler(state: AppState) -> Vec<tokio::task::JoinHandle<()>> {
+    state
+        .jobs
+        .jobs
+        .iter()
+        .filter(|job| job.spec.enabled)
+        .map(|job| {
+            let (state, job) = (state.clone(), job.clone());
+            tokio::spawn(async move { schedule_loop(state, job).await })
+        })
+        .collect()
+}
+
+async fn schedule_loop(state: AppState, job: Arc<Job>) {
+    // Se avanza desde la ocurrencia anterior, no desde el reloj: despertar un instante
+    // antes de tiempo no repite la ejecución, y las ocurrencias perdidas mientras el job
+    // estaba en marcha no se recuperan
+    let mut after = Utc::now();
+    loop {
+        let Some(next) = job.schedule.after(&after).next() else {
+            info!(job = %job.spec.name, "job schedule has no more occurrences");
+            job.state.lock().unwrap().next_run = None;
+            return;
+        };
+        let jitter = Duration::from_millis(
+            rand::rng().random_range(0..=job.spec.jitter_secs.saturating_mul(1000)),
+        );
+        let at = next + jitter;
+        job.state.lock().unwrap().next_run = Some(at);
+
+        tokio::time::sleep((at - Utc::now()).to_std().unwrap_or_default()).await;
+        if job.try_begin() {
+            execute(&state, &job, Trigger::Scheduled).await;
+        } else {
+            warn!(job = %job.spec.name, "job still running: scheduled run skipped");
+        }
+        after = next.max(Utc::now());
+    }
+}
+
+/// Ejecuta en segundo plano un job ya marcado con `try_begin`
+pub fn spawn_run(state: AppState, job: Arc<Job>, trigger: Trigger) {
+    tokio::spawn(async move { execute(&state, &job, trigger).await });
+}
+
+/// Ejecuta `job` (ya marcado con `try_begin`) y guarda el resultado en su historial
+pub async fn execute(state: &AppState, job: &Job, trigger: Trigger) -> JobRun {
+    let started_at = Utc::now();
+    let started = Instant::now();
+    info!(job = %job.spec.name, kind = job.spec.kind.as_str(), trigger = ?trigger, "job started");
+
+    let (error, summary) = match job.spec.kind {
+        JobKind::Analyze => analyze(state, &job.spec).await,
+        JobKind::ProvidersStatus | JobKind::DataQuality => snapshot(state, &job.spec).await,
+    };
+
+    let run = JobRun {
+        trigger,
+        started_at,
+        duration_ms: started.elapsed().as_millis() as u64,
+        success: error.is_none(),
+        error,
+        summary,
+    };
+    match &run.error {
+        None => info!(job = %job.spec.name, duration_ms = run.duration_ms, "job finished"),
+        Some(e) => {
+            warn!(job = %job.spec.name, duration_ms = run.duration_ms, error = %e, "job failed")
+        }
+    }
+    job.finish(run.clone());
+    run
+}
+
+/// Re-analiza los símbolos del job; `InbestiaClient::analyze` guarda cada resultado
+/// en el historial de análisis
+async fn analyze(state: &AppState, spec: &JobSpec) -> (Option<String>, Value) {
+    let request = BatchAnalysisRequest {
+        symbols: spec.symbols.clone(),
+        timeframes: spec.timeframes.clone(),
+        include_fundamental: spec.include_fundamental,
+    };
+    let concurrency = spec
+        .max_concurrency
+        .unwrap_or(state.settings().config.batch_concurrency);
+    let items: Vec<_> = batch::run(state.inbestia(), request, concurrency)
+        .collect()
+        .await;
+
+    let failures: Vec<Value> = items
+        .iter()
+        .filter_map(|(_, item)| match &item.outcome {
+            BatchOutcome::Ok { .. } => None,
+            BatchOutcome::Error { error } => Some(json!({
+                "symbol": item.symbol,
+                "timeframe": item.timeframe,
+                "code": error.code,
+            })),
+        })
+        .collect();
+    let error = (!failures.is_empty())
+        .then(|| format!("{} of {} analyses failed", failures.len(), items.len()));
+    let summary = json!({
+        "total": items.len(),
+        "succeeded": items.len() - failures.len(),
+        "failed": failures.len(),
+        "failures": failures,
+        "stored": state.db.is_some() && state.settings().config.history_enabled,
+    });
+    (error, summary)
+}
+
+/// Consulta inBestia y guarda la respuesta en `job_snapshots` si hay base de datos
+async fn snapshot(state: &AppState, spec: &JobSpec) -> (Option<String>, Value) {
+    let client = state.inbestia();
+    let data = match spec.kind {
+        JobKind::DataQuality => client.data_quality().await.map(|metrics| json!(metrics)),
+        _ => client
+            .providers_status()
+            .await
+            .map(|providers| json!(providers)),
+    };
+    let data = match data {
+        Ok(data) => data,
+        Err(e) => return (Some(e.to_string()), json!({ "stored": false })),
+    };
+
+    let Some(db) = state.db.as_ref() else {
+        return (None, json!({ "stored": false }));
+    };
+    let stored = sqlx::query("INSERT INTO job_snapshots (job, kind, data) VALUES ($1, $2, $3)")
+        .bind(&spec.name)
+        .bind(spec.kind.as_str())
+        .bind(Json(&data))
+        .execute(db)
+        .await;
+    match stored {
+        Ok(_) => (None, json!({ "stored": true })),
+        Err(e) => (
+            Some(format!("snapshot not stored: {}", e)),
+            json!({ "stored": false }),
+        ),
+    }
+}
@@ -0,0 +1,355 @@
+use arc_swap::ArcSwap;
+use axum::{
+    middleware::{from_fn, from_fn_with_state},
+    routing::{get, post, put},
+    Router,
+};
+use reqwest::Client;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tower_http::trace::TraceLayer;
+
+pub mod alerts;
+pub mod auth;
+pub mod bodylog;
+pub mod cache;
+pub mod config;
+pub mod confluence;
+pub mod db;
+pub mod errors;
+pub mod handlers;
+pub mod health;
+pub mod history;
+pub mod inbestia;
+pub mod jobs;
+pub mod live;
+pub mod metrics;
+pub mod middleware;
+pub mod proxy;
+pub mod ratelimit;
+pub mod reload;
+pub mod resilience;
+pub mod routes;
+pub mod system;
+pub mod trace;
+pub mod upstream;
+pub mod validation;
+pub mod watchlists;
+pub mod webhooks;
+
+use auth::{SessionManager, UserStore};
+use bodylog::BodyLogPolicy;
+use cache::ResponseCache;
+use config::Config;
+use health::HealthHistory;
+use inbestia::InbestiaClient;
+use jobs::JobRegistry;
+use live::LiveHub;
+use metrics::GatewayMetrics;
+use proxy::HeaderPolicy;
+use ratelimit::{QuotaStore, RateLimiter};
+use resilience::{CircuitBreakers, RetryPolicy};
+use system::SystemMonitor;
+use upstream::{ApiKeys, UpstreamPool};
+use validation::Validators;
+use webhooks::Webhooks;
+
+/// Configuración en vigor y las políticas que se derivan de ella; se sustituye entera al
+/// recargar la configuración (ver `reload`)
+pub struct Settings {
+    pub config: Config,
+    pub api_keys: Arc<ApiKeys>,
+    pub request_headers: HeaderPolicy,
+    pub response_headers: HeaderPolicy,
+    pub retry: RetryPolicy,
+    pub body_log: Arc<BodyLogPolicy>,
+}
+
+impl Settings {
+    pub fn new(config: Config) -> Self {
+        Self {
+            request_headers: HeaderPolicy::new(
+                &config.proxy_request_headers_allow,
+                &config.proxy_request_headers_deny,
+            ),
+            response_headers: HeaderPolicy::new(
+                &config.proxy_response_headers_allow,
+                &config.proxy_response_headers_deny,
+            ),
+            retry: RetryPolicy::from_config(&config),
+            api_keys: Arc::new(ApiKeys::from_config(&config)),
+            body_log: Arc::new(BodyLogPolicy::from_config(&config)),
+            config,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AppState {
+    pub upstreams: Arc<UpstreamPool>,
+    /// Sin timeout global: cada llamada usa el de su ruta o REQUEST_TIMEOUT en vigor
+    pub client: Client,
+    pub settings: Arc<ArcSwap<Settings>>,
+    pub cache: Arc<ResponseCache>,
+    pub breakers: Arc<CircuitBreakers>,
+    pub users: Arc<UserStore>,
+    pub sessions: Arc<SessionManager>,
+    pub limiter: Arc<RateLimiter>,
+    pub quotas: Arc<QuotaStore>,
+    pub metrics: Arc<GatewayMetrics>,
+    pub system: Arc<SystemMonitor>,
+    pub health: Arc<HealthHistory>,
+    pub validators: Arc<Validators>,
+    pub live: Arc<LiveHub>,
+    /// Pool de PostgreSQL; `None` sin DATABASE_URL (watchlists, historial y alertas responden 503)
+    pub db: Option<PgPool>,
+    pub jobs: Arc<JobRegistry>,
+    pub webhooks: Arc<Webhooks>,
+}
+
+impl AppState {
+    pub fn new(config: Config) -> anyhow::Result<Self> {
+        Ok(Self {
+            upstreams: Arc::new(UpstreamPool::from_config(&config)),
+            client: Client::builder().build()?,
+            cache: Arc::new(ResponseCache::from_config(&config)),
+            breakers: Arc::new(CircuitBreakers::from_config(&config)),
+            users: Arc::new(UserStore::load(config.users_file.as_deref())?),
+            sessions: Arc::new(SessionManager::from_config(&config)),
+            limiter: Arc::new(RateLimiter::from_config(&config)),
+            quotas: Arc::new(QuotaStore::from_config(&config)?),
+            metrics: Arc::new(GatewayMetrics::new()?),
+            system: Arc::new(SystemMonitor::default()),
+            health: Arc::new(HealthHistory::from_config(&config)),
+            validators: Arc::new(Validators::new()?),
+            live: Arc::new(LiveHub::from_config(&config)),
+            db: db::connect(&config)?,
+            jobs: Arc::new(JobRegistry::from_config(&config)?),
+            webhooks: Arc::new(Webhooks::from_config(&config)?),
+            settings: Arc::new(ArcSwap::from_pointee(Settings::new(config))),
+        })
+    }
+
+    /// Configuración en vigor; una recarga no afecta a quien ya tiene su copia
+    pub fn settings(&self) -> Arc<Settings> {
+        self.settings.load_full()
+    }
+
+    /// Cliente tipado de inBestia que comparte réplicas, breakers y métricas con el proxy
+    pub fn inbestia(&self) -> InbestiaClient {
+        InbestiaClient::new(self)
+    }
+}
+
+/// Construye el router del gateway con todas sus rutas
+pub fn app(state: AppState) -> Router {
+    let settings = state.settings();
+    let config = &settings.config;
+    let gateway = Router::new()
+        // Endpoints públicos
+        .route("/", get(|| async { "Ghost Dashboard API" }))
+        .route("/health", get(handlers::health_check))
+        .route("/api/health", get(handlers::api_health_check))
+        .route("/api/health/history", get(handlers::health_history))
+        .route("/api/metrics/system", get(handlers::system_metrics))
+        .route("/metrics", get(handlers::prometheus_metrics))
+        .route("/api/schemas", get(handlers::request_schemas))
+        // Sesiones de usuario del gateway
+        .route("/api/auth/login", post(handlers::auth::login))
+        .route("/api/auth/logout", post(handlers::auth::logout))
+        .route(
+            "/api/auth/me",
+            get(handlers::auth::me)
+                .route_layer(from_fn_with_state(state.clone(), middleware::require_session)),
+        )
+        .route("/api/me/usage", get(handlers::usage::me_usage))
+        // Canal en vivo (SSE) de health, métricas y proveedores, sólo con sesión
+        .route(
+            "/api/live",
+            routes::guard(
+                &state,
+                get(handlers::live::live),
+                true,
+                Some("default"),
+                None,
+                config.max_request_size,
+            ),
+        )
+        // Análisis agregados contra inBestia: el handler cobra a la clase `analysis` una request
+        // por cada análisis que pide
+        .route(
+            "/api/batch/analyze",
+            routes::guard(
+                &state,
+                post(handlers::batch::analyze),
+                true,
+                None,
+                Some("BatchAnalysisRequest"),
+                config.max_request_size,
+            ),
+        )
+        .route(
+            "/api/confluence/:symbol",
+            routes::guard(
+                &state,
+                get(handlers::confluence::confluence),
+                true,
+                None,
+                None,
+                config.max_request_size,
+            ),
+        )
+        // Watchlists del usuario de la sesión, guardadas en PostgreSQL
+        .route(
+            "/api/watchlists",
+            routes::guard(
+                &state,
+                get(handlers::watchlists::list),
+                true,
+                Some("default"),
+                None,
+                config.max_request_size,
+            )
+            .merge(routes::guard(
+                &state,
+                post(handlers::watchlists::create),
+                true,
+                Some("default"),
+                Some("WatchlistRequest"),
+                config.max_request_size,
+            )),
+        )
+        .route(
+            "/api/watchlists/:id",
+            routes::guard(
+                &state,
+                get(handlers::watchlists::get).delete(handlers::watchlists::delete),
+                true,
+                Some("default"),
+                None,
+                config.max_request_size,
+            )
+            .merge(routes::guard(
+                &state,
+                put(handlers::watchlists::update),
+                true,
+                Some("default"),
+                Some("WatchlistRequest"),
+                config.max_request_size,
+            )),
+        )
+        // Reglas de alerta del usuario de la sesión y sus alertas disparadas
+        .route(
+            "/api/alerts",
+            routes::guard(
+                &state,
+                get(handlers::alerts::fired),
+                true,
+                Some("default"),
+                None,
+                config.max_request_size,
+            ),
+        )
+        .route(
+            "/api/alerts/rules",
+            routes::guard(
+                &state,
+                get(handlers::alerts::list_rules),
+                true,
+                Some("default"),
+                None,
+                config.max_request_size,
+            )
+            .merge(routes::guard(
+                &state,
+                post(handlers::alerts::create_rule),
+                true,
+                Some("default"),
+                Some("AlertRuleRequest"),
+                config.max_request_size,
+            )),
+        )
+        .route(
+            "/api/alerts/rules/:id",
+            routes::guard(
+                &state,
+                get(handlers::alerts::get_rule).delete(handlers::alerts::delete_rule),
+                true,
+                Some("default"),
+                None,
+                config.max_request_size,
+            )
+            .merge(routes::guard(
+                &state,
+                put(handlers::alerts::update_rule),
+                true,
+                Some("default"),
+                Some("AlertRuleRequest"),
+                config.max_request_size,
+            )),
+        )
+        // Evolución de las puntuaciones de un símbolo
+        .route(
+            "/api/history/:symbol",
+            routes::guard(
+                &state,
+                get(handlers::history::history),
+                true,
+                Some("default"),
+                None,
+                config.max_request_size,
+            ),
+        )
+        // Jobs programados: estado con sesión, ejecución manual con ADMIN_TOKEN
+        .route(
+            "/api/jobs",
+            routes::guard(
+                &state,
+                get(handlers::jobs::list),
+                true,
+                Some("default"),
+                None,
+                config.max_request_size,
+            ),
+        )
+        .route(
+            "/api/jobs/:name/run",
+            post(handlers::jobs::run_now)
+                .route_layer(from_fn_with_state(state.clone(), middleware::require_admin_token)),
+        )
+        // Cola de webhooks salientes y su dead letter (requiere ADMIN_TOKEN)
+        .route(
+            "/api/webhooks/deliveries",
+            get(handlers::webhooks::deliveries)
+                .route_layer(from_fn_with_state(state.clone(), middleware::require_admin_token)),
+        )
+        .route(
+            "/api/webhooks/deliveries/:id/retry",
+            post(handlers::webhooks::retry)
+                .route_layer(from_fn_with_state(state.clone(), middleware::require_admin_token)),
+        )
+        // Administración del gateway (requiere ADMIN_TOKEN)
+        .nest(
+            "/api/admin",
+            Router::new()
+                .route(
+                    "/cache",
+                    get(handlers::admin::cache_stats).delete(handlers::admin::cache_purge),
+                )
+                .route_layer(from_fn_with_state(state.clone(), middleware::require_admin_token)),
+        );
+
+    // Rutas reenviadas a inBestia, declaradas en el manifiesto (ROUTES_FILE)
+    let proxied = config.routes.iter().fold(gateway, |router, spec| {
+        router.route(&spec.path, routes::method_router(&state, spec))
+    });
+
+    let cors = middleware::cors_layer(&config.cors_allowed_origins);
+    proxied
+        .route_layer(from_fn_with_state(state.clone(), middleware::track_metrics))
+        .layer(from_fn_with_state(state.clone(), middleware::track_connections))
+        .layer(from_fn(middleware::propagate_trace))
+        .with_state(state)
+        .layer(cors)
+        .layer(TraceLayer::new_for_http())
+}
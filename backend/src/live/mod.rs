@@ -0,0 +1,231 @@
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, Notify};
+use tracing::debug;
+
+use crate::config::Config;
+use crate::handlers::{health_report, system_report};
+use crate::AppState;
+
+/// Timeout de cada llamada a inBestia de una ronda de sondeo
+const POLL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Temas que publica `GET /api/live`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Topic {
+    /// El cuerpo de `/api/health`
+    Health,
+    /// El cuerpo de `/api/metrics/system`
+    Metrics,
+    /// El estado de los proveedores de inBestia
+    Providers,
+}
+
+impl Topic {
+    pub const ALL: [Topic; 3] = [Topic::Health, Topic::Metrics, Topic::Providers];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Topic::Health => "health",
+            Topic::Metrics => "metrics",
+            Topic::Providers => "providers",
+        }
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Topic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Topic::ALL
+            .into_iter()
+            .find(|topic| topic.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Topic::ALL.iter().map(|t| t.as_str()).collect();
+                format!(
+                    "Tema '{}' desconocido; temas válidos: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Un cambio publicado en un tema
+#[derive(Debug, Clone)]
+pub struct LiveEvent {
+    pub id: u64,
+    pub topic: Topic,
+    pub data: Arc<Value>,
+}
+
+struct Published {
+    last_id: u64,
+    /// Último evento de cada tema: lo que recibe primero quien se suscribe
+    latest: BTreeMap<Topic, LiveEvent>,
+    /// Eventos recientes para reenviar a quien reconecta con `Last-Event-ID`
+    recent: VecDeque<LiveEvent>,
+}
+
+/// Canal en vivo: una sola ronda de sondeo alimenta a todos los dashboards suscritos
+pub struct LiveHub {
+    sender: broadcast::Sender<LiveEvent>,
+    published: Mutex<Published>,
+    replay_capacity: usize,
+    wake: Notify,
+}
+
+impl LiveHub {
+    pub fn new(replay_capacity: usize) -> Self {
+        let replay_capacity = replay_capacity.max(1);
+        Self {
+            sender: broadcast::channel(replay_capacity).0,
+            published: Mutex::new(Published {
+                // Los ids empiezan en la hora de arranque (ms) para que un Last-Event-ID
+                // de un proceso anterior no se confunda con uno de éste
+                last_id: Utc::now().timestamp_millis().max(0) as u64,
+                latest: BTreeMap::new(),
+                recent: VecDeque::with_capacity(replay_capacity),
+            }),
+            replay_capacity,
+            wake: Notify::new(),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.live_replay_events)
+    }
+
+    /// Publica `data` en `topic` si cambió desde la última publicación.
+    /// Devuelve si se publicó.
+    pub fn publish(&self, topic: Topic, data: Value) -> bool {
+        let mut published = self.published.lock().unwrap_or_else(|e| e.into_inner());
+        if published
+            .latest
+            .get(&topic)
+            .is_some_and(|event| *event.data == data)
+        {
+            return false;
+        }
+        published.last_id += 1;
+        let event = LiveEvent {
+            id: published.last_id,
+            topic,
+            data: Arc::new(data),
+        };
+        published.latest.insert(topic, event.clone());
+        if published.recent.len() == self.replay_capacity {
+            published.recent.pop_front();
+        }
+        published.recent.push_back(event.clone());
+        // Sin suscriptores el envío falla y no importa: el evento queda en `latest`
+        let _ = self.sender.send(event);
+        true
+    }
+
+    /// Se suscribe a los cambios. Devuelve además los eventos que el cliente debe recibir
+    /// antes que los nuevos: los posteriores a `last_event_id` si siguen en el buffer de
+    /// reenvío o, si no, el último estado de cada tema.
+    pub fn subscribe(
+        &self,
+        topics: &[Topic],
+        last_event_id: Option<u64>,
+    ) -> (Vec<LiveEvent>, broadcast::Receiver<LiveEvent>) {
+        // Con el lock tomado ninguna publicación cae entre el backlog y el receptor
+        let published = self.published.lock().unwrap_or_else(|e| e.into_inner());
+        let receiver = self.sender.subscribe();
+        if self.sender.receiver_count() == 1 {
+            // Primer suscriptor: el sondeo estaba parado, que no espere al siguiente tick
+            self.wake.notify_one();
+        }
+
+        let replayable = last_event_id.filter(|id| {
+            *id <= published.last_id
+                && published
+                    .recent
+                    .front()
+                    .is_none_or(|first| first.id <= id + 1)
+        });
+        let backlog = match replayable {
+            Some(id) => published
+                .recent
+                .iter()
+                .filter(|event| event.id > id && topics.contains(&event.topic))
+                .cloned()
+                .collect(),
+            None => latest_of(&published, topics),
+        };
+        (backlog, receiver)
+    }
+
+    /// Último estado de cada tema de `topics`, en orden de publicación
+    pub fn snapshot(&self, topics: &[Topic]) -> Vec<LiveEvent> {
+        let published = self.published.lock().unwrap_or_else(|e| e.into_inner());
+        latest_of(&published, topics)
+    }
+
+    pub fn subscribers(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+fn latest_of(published: &Published, topics: &[Topic]) -> Vec<LiveEvent> {
+    let mut events: Vec<LiveEvent> = topics
+        .iter()
+        .filter_map(|topic| published.latest.get(topic).cloned())
+        .collect();
+    events.sort_by_key(|event| event.id);
+    events
+}
+
+/// Lanza en segundo plano el sondeo que alimenta el canal en vivo. Sólo consulta
+/// mientras haya algún suscriptor.
+pub fn spawn_poller(state: AppState) -> tokio::task::JoinHandle<()> {
+    let interval = Duration::from_secs(state.settings().config.live_poll_interval_secs);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = state.live.wake.notified() => {}
+            }
+            if state.live.subscribers() > 0 {
+                poll(&state).await;
+            }
+        }
+    })
+}
+
+/// Una ronda de sondeo: publica los temas que hayan cambiado
+pub async fn poll(state: &AppState) {
+    let client = state.inbestia().with_timeout(POLL_TIMEOUT);
+    let providers = async {
+        match client.providers_status().await {
+            Ok(providers) => json!({ "status": "success", "providers": providers }),
+            Err(e) => json!({ "status": "error", "providers": null, "error": e.to_string() }),
+        }
+    };
+    let (metrics, providers) = tokio::join!(system_report(state, &client), providers);
+
+    let (_, health) = health_report(state);
+    for (topic, data) in [
+        (Topic::Health, health),
+        (Topic::Metrics, metrics),
+        (Topic::Providers, providers),
+    ] {
+        if state.live.publish(topic, data) {
+            debug!(topic = %topic, subscribers = state.live.subscribers(), "live update published");
+        }
+    }
+}
@@ -1,164 +1,164 @@
-use axum::{
-    body::Body,
-    extract::State,
-    http::{Request, StatusCode},
-    response::IntoResponse,
-    routing::{get, post},
-    Router,
-};
-use reqwest::Client;
+use clap::Parser;
+use ghost_backend::config::{parse_override, Config, ConfigSources};
+use ghost_backend::reload::{self, Reloader};
+use ghost_backend::{db, jobs, live, ratelimit, upstream, webhooks, AppState};
 use std::net::SocketAddr;
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
-use tracing::{error, info};
-use uuid::Uuid;
-
-mod config;
-mod handlers;
-
-use config::Config;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use tracing::{info, warn};
+use tracing_subscriber::EnvFilter;
+
+/// Gateway de Ghost Dashboard delante de inBestia.
+///
+/// La configuración se lee por capas: fichero TOML, variables de entorno (y `.env`) y
+/// estos flags, que tienen la última palabra.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Fichero TOML de configuración (por defecto CONFIG_FILE)
+    #[arg(short, long, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// Dirección de escucha (BIND_ADDR)
+    #[arg(long, value_name = "ADDR")]
+    bind: Option<String>,
+    /// Filtro de logs (RUST_LOG)
+    #[arg(long, value_name = "FILTER")]
+    log_level: Option<String>,
+    /// Cualquier clave de configuración, con el nombre de su variable de entorno
+    /// (`-s REQUEST_TIMEOUT=60`); se puede repetir
+    #[arg(short = 's', long = "set", value_name = "KEY=VALUE", value_parser = parse_override)]
+    overrides: Vec<(String, String)>,
+    /// Muestra la configuración efectiva en JSON, con los secretos ocultos, y termina
+    #[arg(long, conflicts_with = "check_config")]
+    print_config: bool,
+    /// Valida la configuración y termina; sale con código 1 si hay problemas
+    #[arg(long)]
+    check_config: bool,
+}
 
-#[derive(Clone)]
-pub struct AppState {
-    pub api_base: String,
-    pub api_key: String,
-    pub client: Client,
-    pub config: Config,
+impl Cli {
+    fn sources(&self) -> ConfigSources {
+        let flags = [("BIND_ADDR", &self.bind), ("RUST_LOG", &self.log_level)];
+        flags
+            .into_iter()
+            .filter_map(|(key, value)| Some((key, value.as_deref()?)))
+            .chain(self.overrides.iter().map(|(key, value)| (key.as_str(), value.as_str())))
+            .fold(
+                ConfigSources::from_env(self.config.as_deref()),
+                |sources, (key, value)| sources.with_override(key, value),
+            )
+    }
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<ExitCode> {
+    let cli = Cli::parse();
     dotenvy::dotenv().ok();
 
-    // Cargar y validar configuración
-    let config = Config::from_env()?;
-    config.validate()?;
+    // Cargar y validar configuración: todos los problemas a la vez
+    let config = match Config::load(&cli.sources()) {
+        Ok(config) => config,
+        Err(e) if cli.check_config || cli.print_config => {
+            eprintln!("{}", e);
+            return Ok(ExitCode::FAILURE);
+        }
+        Err(e) => return Err(e),
+    };
+    if cli.check_config {
+        println!("configuration OK");
+        return Ok(ExitCode::SUCCESS);
+    }
+    if cli.print_config {
+        println!("{}", serde_json::to_string_pretty(&config.redacted())?);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(serve(config, cli))?;
+    Ok(ExitCode::SUCCESS)
+}
 
-    // Configurar logging con el nivel especificado
-    tracing_subscriber::fmt()
+async fn serve(config: Config, cli: Cli) -> anyhow::Result<()> {
+
+    // Configurar logging con el nivel especificado (RUST_LOG se puede recargar)
+    let logging = tracing_subscriber::fmt()
         .with_env_filter(&config.log_level)
         .compact()
-        .init();
+        .with_filter_reloading();
+    let log_filter = logging.reload_handle();
+    logging.init();
 
     let bind_addr: SocketAddr = config.bind_address.parse()?;
 
-    let state = AppState {
-        api_base: config.api_base_url.clone(),
-        api_key: config.api_key.clone(),
-        client: Client::builder()
-            .timeout(std::time::Duration::from_secs(config.request_timeout_secs))
-            .build()?,
-        config: config.clone(),
-    };
+    let state = AppState::new(config)?;
+    if state.users.is_empty() {
+        warn!("no gateway users configured (USERS_FILE): nobody can log in");
+    }
+    match &state.db {
+        Some(pool) => db::migrate(pool).await?,
+        None => warn!("DATABASE_URL not set: watchlists, analysis history, alerts and job snapshots are disabled"),
+    }
+    upstream::spawn_health_checks(state.clone());
+    ratelimit::spawn_quota_flush(state.clone());
+    state.system.spawn_sampler();
+    live::spawn_poller(state.clone());
+    if webhooks::spawn_dispatcher(state.clone()).is_some() {
+        info!(webhooks = state.webhooks.specs().len(), "webhook dispatcher started");
+    }
+    webhooks::spawn_provider_watch(state.clone());
+    let scheduled = jobs::spawn_scheduler(state.clone());
+    if !scheduled.is_empty() {
+        info!(jobs = scheduled.len(), "job scheduler started");
+    }
 
-    let app = Router::new()
-        // Endpoints públicos
-        .route("/", get(|| async { "Ghost Dashboard API" }))
-        .route("/health", get(handlers::health_check))
-        .route("/api/health", get(handlers::api_health_check))
-        .route("/api/metrics/system", get(handlers::system_metrics))
-        .route("/api/v1/info", get(forward_public))
-        // Endpoints que requieren autenticación
-        .route("/api/analyze", post(forward_auth))
-        .route("/api/timeframes/config", get(forward_auth))
-        .route("/api/v1/analyze", post(forward_auth))
-        .route("/api/v1/historical", post(forward_auth))
-        .route("/api/v1/indicators", post(forward_auth))
-        .route("/api/v1/compare", post(forward_auth))
-        .route("/api/v1/providers/status", get(forward_auth))
-        .route("/api/v1/metrics/system", get(forward_auth))
-        .route("/api/v1/metrics/reconciliation", get(forward_auth))
-        .route("/api/v1/metrics/data_quality", get(forward_auth))
-        .route("/api/v1/timeframes/config", get(forward_auth))
-        .with_state(state)
-        .layer(CorsLayer::permissive())
-        .layer(TraceLayer::new_for_http());
+    // Recarga en caliente: cambios en el fichero de configuración o el manifiesto, y SIGHUP
+    let files = cli.sources().files();
+    let reloader = Arc::new(
+        Reloader::new(state.clone(), move || Config::load(&cli.sources())).with_log_level(
+            move |level| Ok(log_filter.reload(EnvFilter::try_new(level)?)?),
+        ),
+    );
+    reload::spawn(reloader.clone(), files)?;
 
     info!("gateway on http://{bind_addr}");
-    axum::serve(tokio::net::TcpListener::bind(bind_addr).await?, app).await?;
+    axum::serve(
+        tokio::net::TcpListener::bind(bind_addr).await?,
+        reloader.router().into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
+
+    ratelimit::flush_on_shutdown(&state);
     Ok(())
 }
 
-async fn forward_public(State(s): State<AppState>, req: Request<Body>) -> impl IntoResponse {
-    proxy(s, req, false).await
-}
-
-async fn forward_auth(State(s): State<AppState>, req: Request<Body>) -> impl IntoResponse {
-    proxy(s, req, true).await
-}
-
-async fn proxy(s: AppState, req: Request<Body>, with_auth: bool) -> impl IntoResponse {
-    use axum::body::to_bytes;
-    let trace_id = Uuid::new_v4().to_string();
-
-    let path_q = req.uri().path_and_query().map(|x| x.as_str()).unwrap_or("/");
-    let url = format!("{}{}", s.api_base, path_q);
-    let method = req.method().clone();
-
-    // Log detallado de la request entrante
-    info!(
-        trace_id = %trace_id,
-        method = %method,
-        path = %path_q,
-        url = %url,
-        with_auth = %with_auth,
-        "proxy request started"
-    );
-
-    let body = to_bytes(req.into_body(), s.config.max_request_size).await.unwrap_or_default();
-
-    // Log del body para requests POST/PUT
-    if !body.is_empty() {
-        if let Ok(body_str) = std::str::from_utf8(&body) {
-            info!(
-                trace_id = %trace_id,
-                body = %body_str,
-                "request body"
-            );
-        }
-    }
-
-    let mut r = s.client.request(method.clone(), &url);
-    if with_auth {
-        r = r.header("Authorization", format!("Bearer {}", s.api_key));
-    }
-    // Agregar Content-Type header para peticiones POST
-    if method == axum::http::Method::POST {
-        r = r.header("Content-Type", "application/json");
-    }
-    let res = r.body(body.to_vec()).send().await;
-
-    match res {
-        Ok(up) => {
-            let status = up.status();
-            let bytes = up.bytes().await.unwrap_or_default();
+/// Ctrl+C o SIGTERM (el que envían Docker, Kubernetes y systemd al parar el servicio)
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
 
-            // Log de la respuesta
-            if let Ok(response_str) = std::str::from_utf8(&bytes) {
-                info!(
-                    trace_id = %trace_id,
-                    status = %status,
-                    response = %response_str,
-                    "proxy response received"
-                );
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                warn!(error = %e, "cannot listen for SIGTERM");
+                std::future::pending::<()>().await;
             }
-
-            (status, bytes).into_response()
-        }
-        Err(e) => {
-            error!(error=?e, trace_id=%trace_id, "upstream error");
-            let error_response = serde_json::json!({
-                "error": {
-                    "code": "UPSTREAM_TIMEOUT",
-                    "message": "Servicio no disponible",
-                    "trace_id": trace_id
-                }
-            });
-            (
-                StatusCode::BAD_GATEWAY,
-                axum::Json(error_response),
-            )
-                .into_response()
         }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => info!("SIGTERM received"),
     }
+    info!("shutting down gracefully");
 }
-
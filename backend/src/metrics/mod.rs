@@ -0,0 +1,227 @@
+use prometheus::{
+    exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::time::Duration;
+
+use crate::AppState;
+
+/// Tipo de contenido de la exposición en texto de Prometheus
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Métricas Prometheus del gateway, registradas en un `Registry` propio
+pub struct GatewayMetrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    upstream_duration: HistogramVec,
+    in_flight: IntGaugeVec,
+    request_body_bytes: HistogramVec,
+    response_body_bytes: HistogramVec,
+    upstream_errors: IntCounterVec,
+    cache_hits: IntCounter,
+    cache_misses: IntCounter,
+    cache_entries: IntGauge,
+    cache_bytes: IntGauge,
+    upstream_healthy: IntGaugeVec,
+}
+
+impl GatewayMetrics {
+    pub fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new_custom(Some("gateway".to_string()), None)?;
+        let latency_buckets = exponential_buckets(0.005, 2.0, 12)?; // 5ms .. ~10s
+        let size_buckets = exponential_buckets(64.0, 4.0, 10)?; // 64B .. 16MB
+
+        let metrics = Self {
+            requests: IntCounterVec::new(
+                Opts::new(
+                    "http_requests_total",
+                    "Requests atendidas por ruta, método y estado",
+                ),
+                &["route", "method", "status"],
+            )?,
+            request_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "http_request_duration_seconds",
+                    "Tiempo total en el gateway hasta las cabeceras de respuesta",
+                )
+                .buckets(latency_buckets.clone()),
+                &["route", "method"],
+            )?,
+            upstream_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "upstream_request_duration_seconds",
+                    "Tiempo de cada llamada a inBestia hasta las cabeceras de respuesta",
+                )
+                .buckets(latency_buckets),
+                &["route", "upstream"],
+            )?,
+            in_flight: IntGaugeVec::new(
+                Opts::new("http_requests_in_flight", "Requests en curso por ruta"),
+                &["route"],
+            )?,
+            request_body_bytes: HistogramVec::new(
+                HistogramOpts::new("http_request_body_bytes", "Tamaño de los bodies recibidos")
+                    .buckets(size_buckets.clone()),
+                &["route"],
+            )?,
+            response_body_bytes: HistogramVec::new(
+                HistogramOpts::new("http_response_body_bytes", "Tamaño de los bodies enviados")
+                    .buckets(size_buckets),
+                &["route"],
+            )?,
+            upstream_errors: IntCounterVec::new(
+                Opts::new(
+                    "upstream_errors_total",
+                    "Errores hablando con inBestia por tipo",
+                ),
+                &["route", "kind"],
+            )?,
+            cache_hits: IntCounter::new("cache_hits_total", "Respuestas servidas desde la caché")?,
+            cache_misses: IntCounter::new(
+                "cache_misses_total",
+                "Consultas a la caché sin entrada válida",
+            )?,
+            cache_entries: IntGauge::new("cache_entries", "Respuestas guardadas en la caché")?,
+            cache_bytes: IntGauge::new("cache_bytes", "Bytes ocupados por la caché")?,
+            upstream_healthy: IntGaugeVec::new(
+                Opts::new(
+                    "upstream_healthy",
+                    "1 si la réplica de inBestia está en el pool",
+                ),
+                &["upstream"],
+            )?,
+            registry,
+        };
+
+        metrics
+            .registry
+            .register(Box::new(metrics.requests.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.request_duration.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.upstream_duration.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.in_flight.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.request_body_bytes.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.response_body_bytes.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.upstream_errors.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.cache_hits.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.cache_misses.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.cache_entries.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.cache_bytes.clone()))?;
+        metrics
+            .registry
+            .register(Box::new(metrics.upstream_healthy.clone()))?;
+        Ok(metrics)
+    }
+
+    /// Marca el inicio de una request; el guard la descuenta de las requests en curso al soltarse
+    pub fn start(&self, route: &str) -> InFlightGuard {
+        let gauge = self.in_flight.with_label_values(&[route]);
+        gauge.inc();
+        InFlightGuard(gauge)
+    }
+
+    pub fn observe_request(&self, route: &str, method: &str, status: u16, elapsed: Duration) {
+        self.requests
+            .with_label_values(&[route, method, &status.to_string()])
+            .inc();
+        self.request_duration
+            .with_label_values(&[route, method])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_upstream(&self, route: &str, upstream: &str, elapsed: Duration) {
+        self.upstream_duration
+            .with_label_values(&[route, upstream])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Cuenta un error con inBestia: `timeout`, `connect`, `bad_status`, `circuit_open`...
+    pub fn upstream_error(&self, route: &str, kind: &str) {
+        self.upstream_errors.with_label_values(&[route, kind]).inc();
+    }
+
+    pub fn request_body_bytes(&self, route: &str) -> prometheus::Histogram {
+        self.request_body_bytes.with_label_values(&[route])
+    }
+
+    pub fn response_body_bytes(&self, route: &str) -> prometheus::Histogram {
+        self.response_body_bytes.with_label_values(&[route])
+    }
+
+    /// Exposición en texto para `/metrics`, con el estado de caché y réplicas al momento
+    pub fn render(&self, state: &AppState) -> String {
+        let cache = state.cache.stats();
+        // Los contadores de la caché viven en `ResponseCache`: aquí sólo se sincronizan
+        self.cache_hits
+            .inc_by(cache.hits.saturating_sub(self.cache_hits.get()));
+        self.cache_misses
+            .inc_by(cache.misses.saturating_sub(self.cache_misses.get()));
+        self.cache_entries.set(cache.entries as i64);
+        self.cache_bytes.set(cache.bytes as i64);
+        for upstream in state.upstreams.snapshot() {
+            self.upstream_healthy
+                .with_label_values(&[&upstream.url])
+                .set(i64::from(upstream.healthy));
+        }
+
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!(error = %e, "cannot encode prometheus metrics");
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+/// Descuenta la request de `http_requests_in_flight` al terminar (también si se cancela)
+pub struct InFlightGuard(IntGauge);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+/// Observa en un histograma los bytes que atraviesan un body cuando éste se suelta
+pub struct ByteCounter {
+    total: u64,
+    histogram: prometheus::Histogram,
+}
+
+impl ByteCounter {
+    pub fn new(histogram: prometheus::Histogram) -> Self {
+        Self {
+            total: 0,
+            histogram,
+        }
+    }
+
+    pub fn add(&mut self, bytes: usize) {
+        self.total += bytes as u64;
+    }
+}
+
+impl Drop for ByteCounter {
+    fn drop(&mut self) {
+        self.histogram.observe(self.total as f64);
+    }
+}
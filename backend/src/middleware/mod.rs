@@ -1,30 +1,53 @@
 use axum::{
-    extract::Request,
-    http::{HeaderValue, Method, StatusCode},
+    body::{Body, HttpBody},
+    extract::{MatchedPath, Request, State},
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
     middleware::Next,
     response::Response,
 };
+use futures_util::StreamExt;
 use std::time::Duration;
-use tower_http::cors::{Any, CorsLayer};
-use tracing::{info, warn};
+use tower_http::cors::{AllowHeaders, AllowOrigin, Any, CorsLayer};
+use tracing::{info, info_span, warn, Instrument};
 
-pub fn cors_layer(allowed_origins: Vec<String>) -> CorsLayer {
-    let mut cors = CorsLayer::new()
+use crate::auth::{session_token, AuthUser};
+use crate::cache::X_CACHE;
+use crate::errors::{error_response, validation_error};
+use crate::metrics::ByteCounter;
+use crate::proxy::payload_too_large;
+use crate::ratelimit::{identify, secs_until_quota_reset, RateDecision};
+use crate::trace::{TraceContext, REQUEST_ID, TRACEPARENT};
+use crate::validation::FieldError;
+use crate::AppState;
+
+/// CORS para CORS_ALLOWED_ORIGINS. Con orígenes concretos el navegador puede enviar la
+/// cookie de sesión; con `*` se admite cualquier origen, pero sin credenciales.
+pub fn cors_layer(allowed_origins: &[String]) -> CorsLayer {
+    let cors = CorsLayer::new()
         .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
-        .allow_headers(Any)
+        .expose_headers([
+            REQUEST_ID,
+            TRACEPARENT,
+            header::RETRY_AFTER,
+            HeaderName::from_static(X_CACHE),
+            HeaderName::from_static("ratelimit-limit"),
+            HeaderName::from_static("ratelimit-remaining"),
+            HeaderName::from_static("ratelimit-reset"),
+            HeaderName::from_static("ratelimit-policy"),
+        ])
         .max_age(Duration::from_secs(3600));
 
     if allowed_origins.iter().any(|origin| origin == "*") {
-        cors = cors.allow_origin(Any);
+        cors.allow_origin(Any).allow_headers(Any)
     } else {
-        for origin in allowed_origins {
-            if let Ok(header_value) = HeaderValue::from_str(&origin) {
-                cors = cors.allow_origin(header_value);
-            }
-        }
+        // Config::validate ya rechazó los orígenes que no son una cabecera válida
+        let origins = allowed_origins
+            .iter()
+            .filter_map(|origin| HeaderValue::from_str(origin).ok());
+        cors.allow_origin(AllowOrigin::list(origins))
+            .allow_headers(AllowHeaders::mirror_request())
+            .allow_credentials(true)
     }
-
-    cors
 }
 
 /// Middleware para logging detallado de requests
@@ -97,4 +120,288 @@ pub async fn request_size_limit(req: Request, next: Next) -> Response {
     }
 
     next.run(req).await
-}
\ No newline at end of file
+}
+
+/// Middleware de W3C Trace Context: respeta o genera `traceparent` y `X-Request-Id`,
+/// deja el `TraceContext` en las extensiones (lo reenvía `proxy()`) y lo devuelve al cliente.
+/// Los logs de la request van dentro de un span con ambos identificadores.
+pub async fn propagate_trace(mut req: Request, next: Next) -> Response {
+    let trace = TraceContext::from_headers(req.headers());
+    req.extensions_mut().insert(trace.clone());
+
+    let span = info_span!("request", trace_id = %trace.trace_id, request_id = %trace.request_id);
+    let mut response = next.run(req).instrument(span).await;
+    trace.apply(response.headers_mut());
+    response
+}
+
+/// Middleware que protege los endpoints de administración con `X-Admin-Token`.
+/// Si `ADMIN_TOKEN` no está configurado, la administración queda deshabilitada.
+pub async fn require_admin_token(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let trace_id = TraceContext::of(&req).trace_id;
+
+    let settings = state.settings();
+    let Some(expected) = settings.config.admin_token.as_deref() else {
+        return error_response(
+            StatusCode::FORBIDDEN,
+            "ADMIN_DISABLED",
+            "La administración del gateway está deshabilitada (ADMIN_TOKEN no configurado)",
+            &trace_id,
+        );
+    };
+
+    let provided = req
+        .headers()
+        .get("x-admin-token")
+        .map(|v| v.as_bytes())
+        .unwrap_or_default();
+    if !constant_time_eq(provided, expected.as_bytes()) {
+        warn!(uri = %req.uri(), trace_id = %trace_id, "admin request rejected");
+        return error_response(
+            StatusCode::UNAUTHORIZED,
+            "UNAUTHORIZED",
+            "Token de administración inválido",
+            &trace_id,
+        );
+    }
+
+    next.run(req).await
+}
+
+/// Comparación en tiempo constante para no filtrar secretos por timing
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Middleware que exige una sesión válida del gateway (cookie `ghost_session` o
+/// `Authorization: Bearer <jwt>`). Deja el usuario en las extensiones de la request.
+pub async fn require_session(State(state): State<AppState>, mut req: Request, next: Next) -> Response {
+    let claims = session_token(req.headers()).and_then(|token| state.sessions.validate(token));
+
+    let Some(claims) = claims else {
+        let trace_id = TraceContext::of(&req).trace_id;
+        warn!(uri = %req.uri(), trace_id = %trace_id, "request without a valid session rejected");
+        let mut response = error_response(
+            StatusCode::UNAUTHORIZED,
+            "UNAUTHORIZED",
+            "Se requiere iniciar sesión",
+            &trace_id,
+        );
+        response
+            .headers_mut()
+            .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        return response;
+    };
+
+    req.extensions_mut().insert(AuthUser { username: claims.sub });
+    next.run(req).await
+}
+
+/// Middleware de rate limiting (token bucket) y cuota diaria para una clase de ruta.
+/// Añade las cabeceras `RateLimit-*` y responde 429 con `Retry-After` al superarlas.
+pub async fn rate_limit(state: AppState, class: String, req: Request, next: Next) -> Response {
+    let identity = identify(&state, &req);
+    let trace_id = TraceContext::of(&req).trace_id;
+    let decision = match charge(&state, &identity, &class, 1, &trace_id) {
+        Ok(decision) => decision,
+        Err(response) => return *response,
+    };
+
+    let mut response = next.run(req).await;
+    if let Some(decision) = &decision {
+        insert_rate_limit_headers(&mut response, decision, None);
+    }
+    response
+}
+
+/// Cobra `cost` requests de `class` a `identity` en el token bucket y en la cuota diaria.
+/// Si no caben devuelve la respuesta 429 que hay que enviar; si caben, la decisión con la
+/// que poner las cabeceras `RateLimit-*` (`None` si la clase no tiene límite o si
+/// RATE_LIMIT_ENABLED=false).
+pub fn charge(
+    state: &AppState,
+    identity: &str,
+    class: &str,
+    cost: u32,
+    trace_id: &str,
+) -> Result<Option<RateDecision>, Box<Response>> {
+    if !state.settings().config.rate_limit_enabled {
+        return Ok(None);
+    }
+    enforce(state, identity, class, cost, trace_id)
+}
+
+/// `charge` aunque RATE_LIMIT_ENABLED=false, para los límites que protegen al gateway
+/// (intentos de login)
+pub fn enforce(
+    state: &AppState,
+    identity: &str,
+    class: &str,
+    cost: u32,
+    trace_id: &str,
+) -> Result<Option<RateDecision>, Box<Response>> {
+    let Some(decision) = state.limiter.check(identity, class, cost) else {
+        return Ok(None);
+    };
+
+    if !decision.allowed {
+        warn!(identity = %identity, class = %class, cost, trace_id = %trace_id, "rate limit exceeded");
+        // Una request que cuesta más que el bucket entero no pasará nunca: sin Retry-After
+        let (message, retry_after) = if cost > decision.limit {
+            (
+                format!(
+                    "La petición cuesta {} peticiones y el límite es {} cada {} s",
+                    cost, decision.limit, decision.period_secs
+                ),
+                None,
+            )
+        } else {
+            (
+                "Demasiadas peticiones; reintentar más tarde".to_string(),
+                Some(decision.retry_after_secs),
+            )
+        };
+        let mut response = error_response(StatusCode::TOO_MANY_REQUESTS, "RATE_LIMITED", &message, trace_id);
+        insert_rate_limit_headers(&mut response, &decision, retry_after);
+        return Err(Box::new(response));
+    }
+
+    if let Err(quota) = state.quotas.consume(identity, class, u64::from(cost)) {
+        warn!(identity = %identity, class = %class, cost, used = quota.used, trace_id = %trace_id, "daily quota exceeded");
+        let mut response = error_response(
+            StatusCode::TOO_MANY_REQUESTS,
+            "QUOTA_EXCEEDED",
+            &format!("Cuota diaria de {} peticiones agotada", quota.limit),
+            trace_id,
+        );
+        insert_rate_limit_headers(&mut response, &decision, Some(secs_until_quota_reset()));
+        return Err(Box::new(response));
+    }
+
+    Ok(Some(decision))
+}
+
+pub fn insert_rate_limit_headers(response: &mut Response, decision: &RateDecision, retry_after: Option<u64>) {
+    let headers = response.headers_mut();
+    headers.insert("ratelimit-limit", HeaderValue::from(decision.limit));
+    headers.insert("ratelimit-remaining", HeaderValue::from(decision.remaining));
+    headers.insert("ratelimit-reset", HeaderValue::from(decision.reset_secs));
+    if let Ok(policy) = HeaderValue::from_str(&format!("{};w={}", decision.limit, decision.period_secs)) {
+        headers.insert("ratelimit-policy", policy);
+    }
+    if let Some(secs) = retry_after {
+        headers.insert(header::RETRY_AFTER, HeaderValue::from(secs));
+    }
+}
+
+/// Middleware que valida el body JSON de una ruta contra su esquema (`schema` en el
+/// manifiesto). Responde 415 si no es JSON, 413 si supera `limit` y 422 con los errores
+/// por campo si no cumple el esquema; si es válido, la request sigue con el body intacto.
+pub async fn validate_body(state: AppState, schema: String, limit: usize, req: Request, next: Next) -> Response {
+    let trace_id = TraceContext::of(&req).trace_id;
+
+    let is_json = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(|mime| {
+            let mime = mime.trim().to_ascii_lowercase();
+            mime == "application/json" || mime.ends_with("+json")
+        })
+        .unwrap_or(false);
+    if !is_json {
+        return error_response(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "UNSUPPORTED_MEDIA_TYPE",
+            "El body debe ser JSON (Content-Type: application/json)",
+            &trace_id,
+        );
+    }
+
+    let (parts, body) = req.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, limit).await else {
+        warn!(trace_id = %trace_id, max_size = limit, "request body too large");
+        return payload_too_large(limit, &trace_id);
+    };
+
+    let instance = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(instance) => instance,
+        Err(e) => {
+            let field = FieldError {
+                field: "body".to_string(),
+                message: format!("JSON inválido: {}", e),
+            };
+            return validation_error(&trace_id, &[field]);
+        }
+    };
+    if let Err(fields) = state.validators.validate(&schema, &instance) {
+        warn!(trace_id = %trace_id, schema = %schema, errors = fields.len(), "request body rejected by schema");
+        return validation_error(&trace_id, &fields);
+    }
+
+    next.run(Request::from_parts(parts, Body::from(bytes))).await
+}
+
+/// Middleware que registra las métricas Prometheus de cada request: contador por
+/// estado, latencia, requests en curso y tamaño de los bodies en ambos sentidos
+pub async fn track_metrics(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = req.method().to_string();
+    let in_flight = state.metrics.start(&route);
+    let started = std::time::Instant::now();
+
+    let (parts, body) = req.into_parts();
+    let body = count_body(body, state.metrics.request_body_bytes(&route), ());
+    let response = next.run(Request::from_parts(parts, body)).await;
+
+    state
+        .metrics
+        .observe_request(&route, &method, response.status().as_u16(), started.elapsed());
+    // Con el body en streaming la request sigue "en curso" hasta enviarlo entero
+    let (parts, body) = response.into_parts();
+    let body = count_body(body, state.metrics.response_body_bytes(&route), in_flight);
+    Response::from_parts(parts, body)
+}
+
+/// Cuenta las conexiones activas del gateway (requests en curso, incluidas las no enrutadas)
+pub async fn track_connections(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let connection = state.system.connection();
+    let response = next.run(req).await;
+    let (parts, body) = response.into_parts();
+    Response::from_parts(parts, hold_until_end(body, connection))
+}
+
+/// Mantiene `guard` vivo hasta que termine un body en streaming. Los bodies de
+/// tamaño conocido ya están en memoria y se devuelven intactos.
+fn hold_until_end<G: Send + 'static>(body: Body, guard: G) -> Body {
+    if body.size_hint().exact().is_some() {
+        return body;
+    }
+    Body::from_stream(body.into_data_stream().map(move |chunk| {
+        let _ = &guard;
+        chunk
+    }))
+}
+
+/// Mide el tamaño de `body`. Si se conoce de antemano se observa ya y el body queda
+/// intacto (conserva su `Content-Length`); si no, se cuenta mientras se transmite.
+/// `guard` se suelta cuando termina el body.
+fn count_body<G: Send + 'static>(body: Body, histogram: prometheus::Histogram, guard: G) -> Body {
+    if let Some(len) = body.size_hint().exact() {
+        histogram.observe(len as f64);
+        return body;
+    }
+    let mut counter = ByteCounter::new(histogram);
+    Body::from_stream(body.into_data_stream().map(move |chunk| {
+        let _ = &guard;
+        if let Ok(chunk) = &chunk {
+            counter.add(chunk.len());
+        }
+        chunk
+    }))
+}
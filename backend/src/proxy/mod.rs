@@ -0,0 +1,367 @@
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Request, StatusCode},
+    response::Response,
+};
+use futures_util::StreamExt;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::bodylog::LogContext;
+use crate::cache::cached_proxy;
+use crate::config::Secret;
+use crate::errors::error_response;
+use crate::resilience::{is_idempotent, is_upstream_failure};
+use crate::routes::RouteSpec;
+use crate::trace::TraceContext;
+use crate::AppState;
+
+/// Cabeceras hop-by-hop (RFC 9110 §7.6.1): describen la conexión y no el mensaje,
+/// por lo que nunca atraviesan el proxy, independientemente de la política.
+const HOP_BY_HOP: [HeaderName; 8] = [
+    header::CONNECTION,
+    HeaderName::from_static("keep-alive"),
+    header::PROXY_AUTHENTICATE,
+    header::PROXY_AUTHORIZATION,
+    header::TE,
+    header::TRAILER,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+];
+
+/// Política explícita de cabeceras para un sentido del proxy.
+///
+/// Si `allow` no está vacío sólo pasan las cabeceras listadas; `deny` siempre
+/// tiene prioridad. Las cabeceras hop-by-hop se eliminan siempre.
+#[derive(Debug, Clone, Default)]
+pub struct HeaderPolicy {
+    allow: Vec<HeaderName>,
+    deny: Vec<HeaderName>,
+}
+
+impl HeaderPolicy {
+    /// Crea la política a partir de nombres de cabecera (ya validados por `Config::validate`)
+    pub fn new(allow: &[String], deny: &[String]) -> Self {
+        let parse = |names: &[String]| {
+            names
+                .iter()
+                .filter_map(|name| HeaderName::from_bytes(name.as_bytes()).ok())
+                .collect()
+        };
+        Self {
+            allow: parse(allow),
+            deny: parse(deny),
+        }
+    }
+
+    fn permits(&self, name: &HeaderName) -> bool {
+        (self.allow.is_empty() || self.allow.contains(name)) && !self.deny.contains(name)
+    }
+
+    /// Devuelve las cabeceras de `source` que la política deja pasar
+    pub fn filter(&self, source: &HeaderMap) -> HeaderMap {
+        // Las cabeceras nombradas en `Connection` también son hop-by-hop
+        let connection_tokens: Vec<HeaderName> = source
+            .get_all(header::CONNECTION)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|token| HeaderName::from_bytes(token.trim().as_bytes()).ok())
+            .collect();
+
+        let mut filtered = HeaderMap::with_capacity(source.len());
+        for (name, value) in source {
+            if HOP_BY_HOP.contains(name) || connection_tokens.contains(name) || !self.permits(name)
+            {
+                continue;
+            }
+            filtered.append(name.clone(), value.clone());
+        }
+        filtered
+    }
+}
+
+/// Pasa por la caché las rutas con TTL en el manifiesto; el resto va directo a `proxy()`
+pub async fn forward(s: AppState, req: Request<Body>, route: &RouteSpec) -> Response {
+    match route.cache_ttl(&s.settings().config) {
+        Some(ttl) => cached_proxy(s, req, route, ttl).await,
+        None => proxy(s, req, route).await,
+    }
+}
+
+/// Reenvía la request a inBestia en streaming, en ambos sentidos.
+///
+/// - Bodies mayores que el límite de la ruta se rechazan con 413.
+/// - Las cabeceras se filtran con `request_headers` / `response_headers`.
+/// - Si la ruta lo declara se inyecta `Authorization: Bearer $INBESTIA_API_KEY`.
+/// - Se reenvían `traceparent` y `X-Request-Id` del `TraceContext` de la request.
+/// - Los bodies sólo se registran según la política de logging (LOG_BODIES), redactados.
+/// - Cada ruta del manifiesto tiene su timeout y su circuit breaker; con el circuito abierto se responde 503
+///   sin contactar a inBestia.
+/// - Las requests idempotentes sin body se reintentan con backoff exponencial.
+/// - Si inBestia rechaza la clave con 401 se repite con la siguiente clave de reserva; con
+///   claves de reserva configuradas el body se lee entero para poder repetirlo.
+pub async fn proxy(s: AppState, req: Request<Body>, route: &RouteSpec) -> Response {
+    let trace = TraceContext::of(&req);
+    let trace_id = trace.trace_id.clone();
+
+    let with_auth = route.auth;
+    let path_q = req
+        .uri()
+        .path_and_query()
+        .map(|x| x.as_str())
+        .unwrap_or("/")
+        .to_string();
+    let method = req.method().clone();
+
+    info!(
+        trace_id = %trace_id,
+        method = %method,
+        path = %path_q,
+        with_auth = %with_auth,
+        "proxy request started"
+    );
+
+    let settings = s.settings();
+    let max_size = route.body_limit(&settings.config);
+    let declared_len = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if declared_len.is_some_and(|len| len > max_size) {
+        warn!(trace_id = %trace_id, content_length = ?declared_len, max_size, "request body too large");
+        return payload_too_large(max_size, &trace_id);
+    }
+
+    let (parts, body) = req.into_parts();
+    let log_mode = settings.body_log.mode_for(route);
+    let log_context = |direction, headers: &HeaderMap| LogContext {
+        trace_id: trace_id.clone(),
+        route: route.path.clone(),
+        direction,
+        content_type: headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string),
+    };
+    let body = settings
+        .body_log
+        .capture(body, log_mode, log_context("request", &parts.headers));
+    let mut headers = settings.request_headers.filter(&parts.headers);
+    // inBestia recibe el mismo trace_id, con el gateway como tramo padre
+    trace.apply(&mut headers);
+
+    // Un body en streaming no se puede repetir: sólo se reintenta lo idempotente sin body
+    let retryable = is_idempotent(&method) && body.is_end_stream();
+    let exceeded = Arc::new(AtomicBool::new(false));
+    let mut body = (!body.is_end_stream()).then_some(body);
+    let mut attempt = 0;
+    let mut upstream = s.upstreams.select();
+
+    let mut keys = settings.api_keys.attempts().filter(|_| with_auth);
+    let mut key = keys.next();
+    let replay = match body.take() {
+        Some(streamed) if key.is_some() && settings.api_keys.has_fallbacks() => {
+            match to_bytes(streamed, max_size).await {
+                Ok(bytes) => Some(bytes),
+                Err(_) => {
+                    warn!(trace_id = %trace_id, max_size, "request body too large");
+                    return payload_too_large(max_size, &trace_id);
+                }
+            }
+        }
+        streamed => {
+            body = streamed;
+            None
+        }
+    };
+
+    let result = loop {
+        let permit = match s.breakers.acquire(&route.path) {
+            Ok(permit) => permit,
+            Err(retry_after) => {
+                warn!(trace_id = %trace_id, route = %route.path, "upstream circuit open, request rejected");
+                s.metrics.upstream_error(&route.path, "circuit_open");
+                return circuit_open(retry_after, &trace_id);
+            }
+        };
+
+        if let Some((_, key)) = key {
+            authorize(&mut headers, key, &trace_id);
+        }
+        let url = format!("{}{}", upstream.url, path_q);
+        let mut r = s
+            .client
+            .request(method.clone(), &url)
+            .timeout(route.timeout(&settings.config))
+            .headers(headers.clone());
+        // Sin body (p.ej. GET) no se adjunta stream: evita un `Transfer-Encoding: chunked` vacío
+        if let Some(bytes) = &replay {
+            r = r.body(bytes.clone());
+        } else if let Some(body) = body.take() {
+            r = r.body(limited_stream(body, max_size, exceeded.clone()));
+        }
+
+        let started = Instant::now();
+        let result = r.send().await;
+        let failed = match &result {
+            Ok(up) => is_upstream_failure(up.status()),
+            // Un body demasiado grande es culpa del cliente, no de inBestia
+            Err(_) => !exceeded.load(Ordering::Relaxed),
+        };
+        permit.record(!failed);
+        s.upstreams.report(&upstream, !failed, started.elapsed());
+        s.metrics
+            .observe_upstream(&route.path, &upstream.url, started.elapsed());
+        if failed {
+            s.metrics.upstream_error(&route.path, error_kind(&result));
+        }
+
+        if let (Ok(up), Some((index, _))) = (&result, key) {
+            if up.status() != StatusCode::UNAUTHORIZED {
+                if !failed {
+                    settings.api_keys.accepted(index);
+                }
+            } else if let Some(next) = keys.next() {
+                warn!(trace_id = %trace_id, upstream = %upstream.url, key = index, "inBestia rejected the API key, trying a fallback key");
+                key = Some(next);
+                continue;
+            }
+        }
+
+        if failed && retryable && attempt < settings.retry.max_retries {
+            let delay = settings.retry.backoff(attempt);
+            attempt += 1;
+            warn!(
+                trace_id = %trace_id,
+                upstream = %upstream.url,
+                attempt,
+                delay_ms = delay.as_millis() as u64,
+                "upstream call failed, retrying"
+            );
+            tokio::time::sleep(delay).await;
+            // Failover: el reintento va a otra réplica si la hay
+            upstream = s.upstreams.select_excluding(Some(&upstream.url));
+            continue;
+        }
+        break result;
+    };
+
+    match result {
+        Ok(up) => {
+            let status = up.status();
+            let headers = settings.response_headers.filter(up.headers());
+
+            info!(
+                trace_id = %trace_id,
+                upstream = %upstream.url,
+                status = %status,
+                content_length = ?up.content_length(),
+                retries = attempt,
+                "proxy response received"
+            );
+
+            let context = log_context("response", up.headers());
+            let body = Body::from_stream(up.bytes_stream());
+            let mut response = Response::new(settings.body_log.capture(body, log_mode, context));
+            *response.status_mut() = status;
+            *response.headers_mut() = headers;
+            response
+        }
+        Err(_) if exceeded.load(Ordering::Relaxed) => {
+            warn!(trace_id = %trace_id, max_size, "request body too large while streaming");
+            payload_too_large(max_size, &trace_id)
+        }
+        Err(e) => {
+            error!(error=?e, trace_id=%trace_id, upstream = %upstream.url, retries = attempt, "upstream error");
+            upstream_error(&e, &trace_id)
+        }
+    }
+}
+
+/// `Authorization: Bearer <clave>` hacia inBestia, marcada como sensible
+fn authorize(headers: &mut HeaderMap, key: &Secret, trace_id: &str) {
+    match HeaderValue::from_str(&format!("Bearer {}", key.expose())) {
+        Ok(mut value) => {
+            value.set_sensitive(true);
+            headers.insert(header::AUTHORIZATION, value);
+        }
+        Err(_) => error!(trace_id = %trace_id, "INBESTIA_API_KEY is not a valid header value"),
+    }
+}
+
+/// Clasifica un fallo de inBestia para la métrica `upstream_errors_total`
+pub(crate) fn error_kind(result: &Result<reqwest::Response, reqwest::Error>) -> &'static str {
+    match result {
+        Ok(_) => "bad_status",
+        Err(e) if e.is_timeout() => "timeout",
+        Err(e) if e.is_connect() => "connect",
+        Err(_) => "transport",
+    }
+}
+
+/// Traduce un error de transporte con inBestia a la respuesta estándar del gateway
+pub(crate) fn upstream_error(e: &reqwest::Error, trace_id: &str) -> Response {
+    if e.is_timeout() {
+        error_response(
+            StatusCode::GATEWAY_TIMEOUT,
+            "UPSTREAM_TIMEOUT",
+            "inBestia no respondió a tiempo",
+            trace_id,
+        )
+    } else {
+        error_response(
+            StatusCode::BAD_GATEWAY,
+            "UPSTREAM_UNAVAILABLE",
+            "Servicio no disponible",
+            trace_id,
+        )
+    }
+}
+
+pub(crate) fn circuit_open(retry_after: Duration, trace_id: &str) -> Response {
+    let mut response = error_response(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "UPSTREAM_CIRCUIT_OPEN",
+        "inBestia no está disponible (circuito abierto); reintentar más tarde",
+        trace_id,
+    );
+    // Redondeo hacia arriba: nunca anunciar `Retry-After: 0` con el circuito abierto
+    let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(secs.max(1)));
+    response
+}
+
+/// Convierte el body entrante en un stream para reqwest que falla (y marca `exceeded`)
+/// en cuanto supera `max_size` bytes. Cubre bodies chunked sin `Content-Length`.
+fn limited_stream(body: Body, max_size: usize, exceeded: Arc<AtomicBool>) -> reqwest::Body {
+    let mut received = 0usize;
+    let stream = body.into_data_stream().map(move |chunk| {
+        let chunk = chunk.map_err(std::io::Error::other)?;
+        received += chunk.len();
+        if received > max_size {
+            exceeded.store(true, Ordering::Relaxed);
+            return Err(std::io::Error::other(
+                "request body exceeds max_request_size",
+            ));
+        }
+        Ok(chunk)
+    });
+    reqwest::Body::wrap_stream(stream)
+}
+
+pub(crate) fn payload_too_large(max_size: usize, trace_id: &str) -> Response {
+    error_response(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        "PAYLOAD_TOO_LARGE",
+        &format!("El body supera el máximo permitido de {} bytes", max_size),
+        trace_id,
+    )
+}
@@ -0,0 +1,380 @@
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, Request},
+    http::{request::Parts, Extensions, HeaderMap},
+};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+use crate::auth::{session_token, AuthUser};
+use crate::config::{Config, RateLimitRule};
+use crate::middleware::constant_time_eq;
+use crate::AppState;
+
+/// Al superar este número de buckets se descartan los llenos y, si no basta, los que
+/// llevan más tiempo sin usarse
+const MAX_BUCKETS: usize = 10_000;
+
+/// Buckets que quedan como mucho tras descartar: la limpieza es O(n), pero sólo vuelve a
+/// hacer falta tras otros `MAX_BUCKETS - BUCKETS_AFTER_EVICTION` clientes nuevos
+const BUCKETS_AFTER_EVICTION: usize = MAX_BUCKETS * 9 / 10;
+
+/// Clase de los análisis agregados (lotes, confluencia): cobran una request por cada
+/// análisis que piden a inBestia
+pub const ANALYSIS_CLASS: &str = "analysis";
+
+/// Clase de los intentos de login, por IP y usuario (`login_identity`)
+pub const LOGIN_CLASS: &str = "login";
+
+/// Clase de los intentos de login por IP, sea cual sea el usuario (`ip_identity`)
+pub const LOGIN_IP_CLASS: &str = "login_ip";
+
+/// Límites de login por defecto. Se aplican aunque RATE_LIMITS no los incluya y aunque
+/// RATE_LIMIT_ENABLED=false: sin ellos el login quedaría abierto a fuerza bruta
+pub const LOGIN_RULES: [(&str, RateLimitRule); 2] = [
+    (LOGIN_CLASS, RateLimitRule { requests: 5, period_secs: 60 }),
+    (LOGIN_IP_CLASS, RateLimitRule { requests: 20, period_secs: 60 }),
+];
+
+/// Cabecera con la API key de un cliente (CLIENT_API_KEYS)
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Identifica al cliente: usuario con sesión, API key conocida o IP, en ese orden
+pub fn identify(state: &AppState, req: &Request) -> String {
+    identify_parts(state, req.headers(), req.extensions())
+}
+
+fn identify_parts(state: &AppState, headers: &HeaderMap, extensions: &Extensions) -> String {
+    if let Some(user) = extensions.get::<AuthUser>() {
+        return format!("user:{}", user.username);
+    }
+    if let Some(claims) = session_token(headers).and_then(|t| state.sessions.validate(t)) {
+        return format!("user:{}", claims.sub);
+    }
+    if let Some(name) = client_key_name(&state.settings().config, headers) {
+        return format!("key:{}", name);
+    }
+    ip_identity(
+        extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip()),
+    )
+}
+
+/// Identidad de un cliente por su IP
+pub fn ip_identity(ip: Option<IpAddr>) -> String {
+    match ip {
+        Some(ip) => format!("ip:{}", ip),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+/// Identidad de un intento de login: la IP del cliente y el usuario con el que entra.
+/// Acota los intentos contra un usuario sin bloquearlo desde otras IPs; cuántos usuarios
+/// prueba una IP lo limita aparte la clase `login_ip`
+pub fn login_identity(ip: Option<IpAddr>, username: &str) -> String {
+    format!("{}|login:{}", ip_identity(ip), username.trim().to_lowercase())
+}
+
+/// Identidad del cliente (`identify`) para los handlers que cobran su propio coste
+pub struct ClientIdentity(pub String);
+
+#[async_trait]
+impl FromRequestParts<AppState> for ClientIdentity {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        Ok(Self(identify_parts(state, &parts.headers, &parts.extensions)))
+    }
+}
+
+/// Nombre de la API key de cliente enviada en `X-API-Key`, si es una de las configuradas
+fn client_key_name<'a>(config: &'a Config, headers: &HeaderMap) -> Option<&'a str> {
+    let provided = headers.get(API_KEY_HEADER)?.as_bytes();
+    config
+        .client_api_keys
+        .iter()
+        .find(|(_, key)| constant_time_eq(provided, key.as_bytes()))
+        .map(|(name, _)| name.as_str())
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Resultado de consultar un token bucket, con los datos de las cabeceras `RateLimit-*`
+#[derive(Debug, Clone, Serialize)]
+pub struct RateDecision {
+    #[serde(skip)]
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    pub period_secs: u64,
+    /// Segundos hasta que el bucket vuelva a estar lleno
+    pub reset_secs: u64,
+    /// Segundos hasta que haya un token disponible (0 si ya lo hay)
+    #[serde(skip)]
+    pub retry_after_secs: u64,
+}
+
+/// Token buckets por cliente y clase de ruta
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<(String, String), Bucket>>,
+    rules: BTreeMap<String, RateLimitRule>,
+}
+
+impl RateLimiter {
+    pub fn new(rules: BTreeMap<String, RateLimitRule>) -> Self {
+        Self {
+            buckets: Mutex::new(HashMap::new()),
+            rules,
+        }
+    }
+
+    /// Las clases de RATE_LIMITS más las de `LOGIN_RULES` que no estén configuradas
+    pub fn from_config(config: &Config) -> Self {
+        let mut rules = config.rate_limit_classes.clone();
+        for (class, rule) in LOGIN_RULES {
+            rules.entry(class.to_string()).or_insert(rule);
+        }
+        Self::new(rules)
+    }
+
+    /// Consume `cost` tokens del bucket de `identity` para `class`, o ninguno si no hay
+    /// tantos. Devuelve `None` si la clase no tiene límite configurado.
+    pub fn check(&self, identity: &str, class: &str, cost: u32) -> Option<RateDecision> {
+        self.take(identity, class, cost)
+    }
+
+    /// Estado del bucket sin consumir tokens
+    pub fn peek(&self, identity: &str, class: &str) -> Option<RateDecision> {
+        self.take(identity, class, 0)
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = &str> {
+        self.rules.keys().map(String::as_str)
+    }
+
+    fn take(&self, identity: &str, class: &str, cost: u32) -> Option<RateDecision> {
+        let rule = self.rules.get(class)?;
+        let capacity = f64::from(rule.requests);
+        let per_sec = capacity / rule.period_secs as f64;
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() > MAX_BUCKETS {
+            self.evict(&mut buckets, now);
+        }
+        let bucket = buckets
+            .entry((identity.to_string(), class.to_string()))
+            .or_insert(Bucket {
+                tokens: capacity,
+                updated: now,
+            });
+
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+        bucket.updated = now;
+
+        // `peek` (coste 0) dice si cabe una request más
+        let needed = f64::from(cost.max(1));
+        let allowed = bucket.tokens >= needed;
+        if allowed {
+            bucket.tokens -= f64::from(cost);
+        }
+
+        Some(RateDecision {
+            allowed,
+            limit: rule.requests,
+            remaining: bucket.tokens.floor() as u32,
+            period_secs: rule.period_secs,
+            reset_secs: ((capacity - bucket.tokens) / per_sec).ceil() as u64,
+            retry_after_secs: if allowed {
+                0
+            } else {
+                ((needed - bucket.tokens) / per_sec).ceil().max(1.0) as u64
+            },
+        })
+    }
+
+    /// Deja como mucho `BUCKETS_AFTER_EVICTION` buckets: primero se descartan los llenos
+    /// (equivalen a uno nuevo) y después los que llevan más tiempo sin usarse
+    fn evict(&self, buckets: &mut HashMap<(String, String), Bucket>, now: Instant) {
+        let rules = &self.rules;
+        buckets.retain(|(_, class), bucket| {
+            rules.get(class).is_some_and(|rule| {
+                let per_sec = f64::from(rule.requests) / rule.period_secs as f64;
+                bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * per_sec
+                    < f64::from(rule.requests)
+            })
+        });
+
+        let excess = buckets.len().saturating_sub(BUCKETS_AFTER_EVICTION);
+        if excess > 0 {
+            let mut updated: Vec<Instant> = buckets.values().map(|bucket| bucket.updated).collect();
+            let (_, &mut cutoff, _) = updated.select_nth_unstable(excess - 1);
+            buckets.retain(|_, bucket| bucket.updated > cutoff);
+        }
+    }
+}
+
+/// Uso de la cuota diaria de un cliente en una clase
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct QuotaStatus {
+    pub limit: u64,
+    pub used: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QuotaCounters {
+    /// Día UTC al que corresponden los contadores
+    day: Option<NaiveDate>,
+    /// identidad → clase → requests del día
+    usage: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+/// Cuotas diarias por cliente y clase, persistidas en QUOTA_FILE
+pub struct QuotaStore {
+    counters: Mutex<QuotaCounters>,
+    limits: BTreeMap<String, u64>,
+    path: Option<PathBuf>,
+    dirty: AtomicBool,
+}
+
+impl QuotaStore {
+    /// Carga los contadores guardados; los de un día anterior se descartan
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let path = config.quota_file.as_ref().map(PathBuf::from);
+        let counters = match &path {
+            Some(path) if path.exists() => {
+                let raw = std::fs::read_to_string(path).map_err(|e| {
+                    anyhow::anyhow!("Cannot read quota file {}: {}", path.display(), e)
+                })?;
+                serde_json::from_str(&raw)
+                    .map_err(|e| anyhow::anyhow!("Invalid quota file {}: {}", path.display(), e))?
+            }
+            _ => QuotaCounters::default(),
+        };
+        Ok(Self {
+            counters: Mutex::new(counters),
+            limits: config.daily_quotas.clone(),
+            path,
+            dirty: AtomicBool::new(false),
+        })
+    }
+
+    pub fn limit(&self, class: &str) -> Option<u64> {
+        self.limits.get(class).copied()
+    }
+
+    /// Cuenta `cost` requests de `identity` en `class`. Si no caben en la cuota devuelve
+    /// `Err` sin contarlas. Las clases sin cuota devuelven `Ok(None)`.
+    pub fn consume(
+        &self,
+        identity: &str,
+        class: &str,
+        cost: u64,
+    ) -> Result<Option<QuotaStatus>, QuotaStatus> {
+        let Some(limit) = self.limit(class) else {
+            return Ok(None);
+        };
+        let mut counters = self.today();
+        let used = counters
+            .usage
+            .entry(identity.to_string())
+            .or_default()
+            .entry(class.to_string())
+            .or_default();
+        if *used + cost.max(1) > limit {
+            return Err(QuotaStatus { limit, used: *used });
+        }
+        *used += cost;
+        self.dirty.store(true, Ordering::Relaxed);
+        Ok(Some(QuotaStatus { limit, used: *used }))
+    }
+
+    /// Requests de hoy de `identity` por clase
+    pub fn usage(&self, identity: &str) -> BTreeMap<String, u64> {
+        self.today()
+            .usage
+            .get(identity)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Bloquea los contadores, reiniciándolos si ha cambiado el día
+    fn today(&self) -> std::sync::MutexGuard<'_, QuotaCounters> {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        let today = Utc::now().date_naive();
+        if counters.day != Some(today) {
+            counters.day = Some(today);
+            counters.usage.clear();
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        counters
+    }
+
+    /// Guarda los contadores si han cambiado (escritura atómica: temporal + rename)
+    pub fn flush(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+        let json = {
+            let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+            serde_json::to_vec(&*counters)?
+        };
+        let tmp = path.with_extension("tmp");
+        let result = std::fs::write(&tmp, json).and_then(|_| std::fs::rename(&tmp, path));
+        if result.is_err() {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        result
+    }
+}
+
+/// Segundos hasta la medianoche UTC, cuando se reinician las cuotas
+pub fn secs_until_quota_reset() -> u64 {
+    let now = Utc::now();
+    let midnight = (now.date_naive() + chrono::Days::new(1))
+        .and_hms_opt(0, 0, 0)
+        .map(|t| t.and_utc());
+    midnight
+        .map(|t| (t - now).num_seconds().max(1) as u64)
+        .unwrap_or(1)
+}
+
+/// Guarda periódicamente los contadores de cuota en segundo plano
+pub fn spawn_quota_flush(state: AppState) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(5));
+        loop {
+            ticker.tick().await;
+            if let Err(e) = state.quotas.flush() {
+                error!(error = %e, "cannot persist quota counters");
+            }
+        }
+    })
+}
+
+/// Último guardado al apagar el gateway
+pub fn flush_on_shutdown(state: &AppState) {
+    match state.quotas.flush() {
+        Ok(()) => info!("quota counters saved"),
+        Err(e) => error!(error = %e, "cannot persist quota counters"),
+    }
+}
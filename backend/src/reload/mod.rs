@@ -0,0 +1,289 @@
+//! Recarga de la configuración sin reiniciar el gateway.
+//!
+//! Al cambiar el fichero de configuración o el manifiesto de rutas, o al recibir SIGHUP,
+//! se vuelve a cargar la configuración por capas y se valida. Si es correcta sustituye de
+//! una vez a los `Settings` de `AppState` y se reconstruye el router (CORS, políticas de
+//! las rutas, límites de body...); si no, sigue la configuración en vigor. Las conexiones
+//! abiertas no se cortan: cada request nueva usa el router vigente.
+
+use arc_swap::ArcSwap;
+use axum::{extract::Request, Router};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tower::ServiceExt;
+use tracing::{error, info, warn};
+
+use crate::config::Config;
+use crate::{app, AppState, Settings};
+
+/// Espera tras el primer aviso para agrupar las escrituras de un mismo guardado
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Claves que usan componentes construidos al arrancar (listener, pools, caché, breakers,
+/// rate limiter, jobs, webhooks...). Una recarga conserva su valor y avisa de que
+/// el cambio requiere reiniciar
+macro_rules! restart_only {
+    ($($field:ident),* $(,)?) => {
+        pub const RESTART_ONLY: &[&str] = &[$(stringify!($field)),*];
+
+        fn keep_restart_only(config: &mut Config, running: &Config) {
+            $(config.$field = running.$field.clone();)*
+        }
+    };
+}
+
+restart_only!(
+    bind_address,
+    api_base_urls,
+    upstream_strategy,
+    upstream_health_interval_secs,
+    upstream_unhealthy_threshold,
+    upstream_healthy_threshold,
+    upstream_health_history,
+    cache_max_entries,
+    cache_max_bytes,
+    cache_max_entry_bytes,
+    breaker_failure_threshold,
+    breaker_open_secs,
+    breaker_half_open_max_calls,
+    users_file,
+    auth_jwt_secret,
+    auth_session_ttl_secs,
+    rate_limit_classes,
+    daily_quotas,
+    quota_file,
+    live_poll_interval_secs,
+    live_replay_events,
+    database_url,
+    database_max_connections,
+    database_acquire_timeout_secs,
+    jobs,
+    jobs_history_size,
+    webhooks,
+    webhook_max_attempts,
+    webhook_retry_base_delay_ms,
+    webhook_retry_max_delay_ms,
+    webhook_timeout_secs,
+    webhook_providers_poll_secs,
+);
+
+type LoadConfig = dyn Fn() -> anyhow::Result<Config> + Send + Sync;
+type ApplyLogLevel = dyn Fn(&str) -> anyhow::Result<()> + Send + Sync;
+
+/// Una clave que cambia, con los secretos ocultos
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    pub key: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+/// Resultado de una recarga aplicada
+#[derive(Debug, Default)]
+pub struct Reloaded {
+    /// Claves que ya están en vigor
+    pub changes: Vec<Change>,
+    /// Claves cambiadas que no se aplican hasta reiniciar
+    pub restart_required: Vec<Change>,
+}
+
+pub struct Reloader {
+    state: AppState,
+    app: ArcSwap<Router>,
+    load: Box<LoadConfig>,
+    log_level: Option<Box<ApplyLogLevel>>,
+    /// Una recarga cada vez (el fichero y SIGHUP pueden coincidir)
+    lock: Mutex<()>,
+}
+
+impl Reloader {
+    /// `load` vuelve a leer todas las capas de configuración
+    pub fn new(
+        state: AppState,
+        load: impl Fn() -> anyhow::Result<Config> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            app: ArcSwap::from_pointee(app(state.clone())),
+            state,
+            load: Box::new(load),
+            log_level: None,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Aplica RUST_LOG al cambiar; un filtro inválido hace fallar la recarga
+    pub fn with_log_level(
+        mut self,
+        apply: impl Fn(&str) -> anyhow::Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.log_level = Some(Box::new(apply));
+        self
+    }
+
+    /// Router que pasa cada request al router en vigor
+    pub fn router(self: &Arc<Self>) -> Router {
+        let reloader = self.clone();
+        Router::new().fallback_service(tower::service_fn(move |req: Request| {
+            let app = reloader.app.load_full();
+            async move { app.as_ref().clone().oneshot(req).await }
+        }))
+    }
+
+    /// Carga, valida y aplica la configuración; con cualquier error sigue la actual
+    pub fn reload(&self) -> anyhow::Result<Reloaded> {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let running = self.state.settings();
+        let mut config = (self.load)()?;
+
+        let restart_required = diff(&running.config, &config)
+            .into_iter()
+            .filter(|change| RESTART_ONLY.contains(&change.key.as_str()))
+            .collect();
+        keep_restart_only(&mut config, &running.config);
+        config.validate()?;
+
+        let changes = diff(&running.config, &config);
+        if config.log_level != running.config.log_level {
+            if let Some(apply) = &self.log_level {
+                apply(&config.log_level)?;
+            }
+        }
+        self.state.settings.store(Arc::new(Settings::new(config)));
+        self.app.store(Arc::new(app(self.state.clone())));
+        Ok(Reloaded {
+            changes,
+            restart_required,
+        })
+    }
+
+    /// `reload` registrando el resultado; `trigger` dice qué la provocó
+    pub fn reload_and_log(&self, trigger: &str) {
+        let reloaded = match self.reload() {
+            Ok(reloaded) => reloaded,
+            Err(e) => {
+                error!(trigger, error = %e, "configuration reload failed, keeping the running configuration");
+                return;
+            }
+        };
+        for change in &reloaded.restart_required {
+            warn!(trigger, key = %change.key, from = %change.from, to = %change.to, "configuration key changed but only applies after a restart");
+        }
+        for change in &reloaded.changes {
+            info!(trigger, key = %change.key, from = %change.from, to = %change.to, "configuration key changed");
+        }
+        let keys: Vec<&str> = reloaded.changes.iter().map(|c| c.key.as_str()).collect();
+        info!(trigger, changed = keys.len(), keys = %keys.join(","), "configuration reloaded");
+    }
+}
+
+/// Claves que difieren entre `old` y `new`, por orden alfabético. Se comparan los valores
+/// reales, pero se muestran los de `Config::redacted`
+pub fn diff(old: &Config, new: &Config) -> Vec<Change> {
+    let as_map = |config: &Config| match serde_json::to_value(config) {
+        Ok(Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    let (old_raw, new_raw) = (as_map(old), as_map(new));
+    let (old_shown, new_shown) = (as_map(&old.redacted()), as_map(&new.redacted()));
+
+    // Las claves de inBestia son `Secret` y ya se serializan ocultas: se comparan aparte
+    let secrets = [
+        ("api_key", old.api_key != new.api_key),
+        (
+            "api_key_fallbacks",
+            old.api_key_fallbacks != new.api_key_fallbacks,
+        ),
+    ];
+    let mut changed: Vec<&String> = new_raw
+        .iter()
+        .filter(|(key, value)| old_raw.get(*key) != Some(value))
+        .map(|(key, _)| key)
+        .collect();
+    for (key, differs) in secrets {
+        if let Some((key, _)) = new_raw.get_key_value(key).filter(|_| differs) {
+            changed.push(key);
+        }
+    }
+    changed.sort();
+    changed.dedup();
+
+    changed
+        .into_iter()
+        .map(|key| Change {
+            key: key.clone(),
+            from: old_shown.get(key).cloned().unwrap_or(Value::Null),
+            to: new_shown.get(key).cloned().unwrap_or(Value::Null),
+        })
+        .collect()
+}
+
+/// Recarga con cada cambio de `files` y con SIGHUP
+pub fn spawn(reloader: Arc<Reloader>, files: Vec<PathBuf>) -> anyhow::Result<()> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<&'static str>();
+    let watcher = watch_files(&files, tx.clone())?;
+
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut hangup = signal(SignalKind::hangup())?;
+        tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                if tx.send("sighup").is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    tokio::spawn(async move {
+        // El watcher deja de avisar cuando se suelta
+        let _watcher = watcher;
+        while let Some(trigger) = rx.recv().await {
+            tokio::time::sleep(DEBOUNCE).await;
+            while rx.try_recv().is_ok() {}
+            // La carga lee ficheros con std::fs: fuera de los workers del runtime
+            let reloader = reloader.clone();
+            let reload = tokio::task::spawn_blocking(move || reloader.reload_and_log(trigger));
+            if let Err(e) = reload.await {
+                error!(trigger, error = %e, "configuration reload panicked");
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Vigila los directorios de `files`: los editores suelen guardar escribiendo otro
+/// fichero y renombrándolo, lo que rompe un watch sobre el propio fichero
+fn watch_files(
+    files: &[PathBuf],
+    tx: mpsc::UnboundedSender<&'static str>,
+) -> anyhow::Result<Option<RecommendedWatcher>> {
+    if files.is_empty() {
+        return Ok(None);
+    }
+    let files = files
+        .iter()
+        .map(std::path::absolute)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let watched = files.clone();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else { return };
+        if !matches!(event.kind, EventKind::Access(_))
+            && event.paths.iter().any(|path| watched.contains(path))
+        {
+            let _ = tx.send("file");
+        }
+    })?;
+    for file in &files {
+        let dir = file.parent().unwrap_or(file);
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        info!(file = %file.display(), "watching configuration file");
+    }
+    Ok(Some(watcher))
+}
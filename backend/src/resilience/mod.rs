@@ -0,0 +1,256 @@
+use axum::http::{Method, StatusCode};
+use rand::Rng;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::config::Config;
+
+/// Estado de un circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Las requests pasan con normalidad
+    Closed,
+    /// inBestia se considera caído: se responde 503 sin contactarlo
+    Open,
+    /// Pasado el tiempo de espera se deja pasar un número limitado de requests de prueba
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Breaker {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    half_open_in_flight: u32,
+    /// Se incrementa en cada paso a half-open: un permiso de una ronda anterior no libera
+    /// plazas de la actual
+    half_open_epoch: u64,
+}
+
+impl Breaker {
+    fn new() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            half_open_in_flight: 0,
+            half_open_epoch: 0,
+        }
+    }
+
+    /// Vuelve a closed conservando la ronda de half-open
+    fn reset(&mut self) {
+        *self = Self {
+            half_open_epoch: self.half_open_epoch,
+            ..Self::new()
+        };
+    }
+}
+
+/// Permiso de `CircuitBreakers::acquire` para una llamada a inBestia. Si se suelta sin
+/// `record` (cliente que se desconecta, timeout externo...) libera su plaza de half-open
+#[must_use = "el resultado de la llamada se registra con `record`"]
+pub struct BreakerPermit<'a> {
+    breakers: &'a CircuitBreakers,
+    route: String,
+    /// Ronda de half-open en la que ocupa plaza (None si el circuito estaba cerrado)
+    half_open_epoch: Option<u64>,
+}
+
+impl BreakerPermit<'_> {
+    /// Registra el resultado de la llamada
+    pub fn record(mut self, success: bool) {
+        self.half_open_epoch = None;
+        self.breakers.record(&self.route, success);
+    }
+}
+
+impl Drop for BreakerPermit<'_> {
+    fn drop(&mut self) {
+        let Some(epoch) = self.half_open_epoch else {
+            return;
+        };
+        let mut breakers = self.breakers.lock();
+        if let Some(breaker) = breakers.get_mut(&self.route) {
+            if breaker.state == CircuitState::HalfOpen && breaker.half_open_epoch == epoch {
+                breaker.half_open_in_flight = breaker.half_open_in_flight.saturating_sub(1);
+            }
+        }
+    }
+}
+
+/// Estado de un breaker para `/api/health`
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakerSnapshot {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// Segundos hasta que el breaker pase a half-open (sólo si está abierto)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+}
+
+/// Circuit breakers por ruta (closed → open → half-open → closed) hacia inBestia
+pub struct CircuitBreakers {
+    breakers: Mutex<HashMap<String, Breaker>>,
+    failure_threshold: u32,
+    open_duration: Duration,
+    half_open_max_calls: u32,
+}
+
+impl CircuitBreakers {
+    pub fn new(failure_threshold: u32, open_duration: Duration, half_open_max_calls: u32) -> Self {
+        Self {
+            breakers: Mutex::new(HashMap::new()),
+            failure_threshold,
+            open_duration,
+            half_open_max_calls,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            config.breaker_failure_threshold,
+            Duration::from_secs(config.breaker_open_secs),
+            config.breaker_half_open_max_calls,
+        )
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Breaker>> {
+        self.breakers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Pide permiso para llamar a inBestia por `route`; el resultado se registra con
+    /// `BreakerPermit::record`. Con el circuito abierto devuelve `Err` con el tiempo
+    /// restante hasta el half-open.
+    pub fn acquire(&self, route: &str) -> Result<BreakerPermit<'_>, Duration> {
+        let mut breakers = self.lock();
+        let breaker = breakers
+            .entry(route.to_string())
+            .or_insert_with(Breaker::new);
+
+        if breaker.state == CircuitState::Open {
+            let elapsed = breaker.opened_at.map(|t| t.elapsed()).unwrap_or_default();
+            if elapsed < self.open_duration {
+                return Err(self.open_duration - elapsed);
+            }
+            breaker.state = CircuitState::HalfOpen;
+            breaker.half_open_in_flight = 0;
+            breaker.half_open_epoch += 1;
+        }
+
+        let mut half_open_epoch = None;
+        if breaker.state == CircuitState::HalfOpen {
+            if breaker.half_open_in_flight >= self.half_open_max_calls {
+                return Err(Duration::from_secs(1));
+            }
+            breaker.half_open_in_flight += 1;
+            half_open_epoch = Some(breaker.half_open_epoch);
+        }
+
+        Ok(BreakerPermit {
+            breakers: self,
+            route: route.to_string(),
+            half_open_epoch,
+        })
+    }
+
+    fn record(&self, route: &str, success: bool) {
+        let mut breakers = self.lock();
+        let breaker = breakers
+            .entry(route.to_string())
+            .or_insert_with(Breaker::new);
+
+        if success {
+            breaker.reset();
+            return;
+        }
+
+        breaker.consecutive_failures += 1;
+        let trip = match breaker.state {
+            CircuitState::HalfOpen => true,
+            CircuitState::Closed => breaker.consecutive_failures >= self.failure_threshold,
+            CircuitState::Open => false,
+        };
+        if trip {
+            warn!(
+                route = %route,
+                consecutive_failures = breaker.consecutive_failures,
+                "upstream circuit opened"
+            );
+            breaker.state = CircuitState::Open;
+            breaker.opened_at = Some(Instant::now());
+            breaker.half_open_in_flight = 0;
+        }
+    }
+
+    /// Estado de todos los breakers conocidos, ordenado por ruta
+    pub fn snapshot(&self) -> BTreeMap<String, BreakerSnapshot> {
+        let breakers = self.lock();
+        breakers
+            .iter()
+            .map(|(route, breaker)| {
+                let retry_after_secs = breaker
+                    .opened_at
+                    .filter(|_| breaker.state == CircuitState::Open)
+                    .map(|t| self.open_duration.saturating_sub(t.elapsed()).as_secs());
+                (
+                    route.clone(),
+                    BreakerSnapshot {
+                        state: breaker.state,
+                        consecutive_failures: breaker.consecutive_failures,
+                        retry_after_secs,
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+/// Reintentos con backoff exponencial y jitter completo, sólo para requests idempotentes
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_retries: config.retry_max_retries,
+            base_delay: Duration::from_millis(config.retry_base_delay_ms),
+            max_delay: Duration::from_millis(config.retry_max_delay_ms),
+        }
+    }
+
+    /// Espera antes del reintento número `attempt` (0 = primer reintento):
+    /// un valor aleatorio en `[0, min(max_delay, base_delay * 2^attempt)]`
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let millis = ceiling.as_millis() as u64;
+        Duration::from_millis(rand::rng().random_range(0..=millis))
+    }
+}
+
+/// Métodos que se pueden repetir sin efectos secundarios (RFC 9110 §9.2.2)
+pub fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE
+    )
+}
+
+/// Estados de inBestia que indican que el servicio (no la request) está fallando
+pub fn is_upstream_failure(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
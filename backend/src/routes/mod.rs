@@ -0,0 +1,307 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Method, Request},
+    middleware::{from_fn, from_fn_with_state, Next},
+    routing::{MethodFilter, MethodRouter},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{BodyLogMode, Config};
+use crate::history::forward_recorded;
+use crate::middleware::{rate_limit, require_session, validate_body};
+use crate::proxy::forward;
+use crate::validation::{Validators, SCHEMA_NAMES};
+use crate::AppState;
+
+/// Manifiesto por defecto, embebido en el binario (se sustituye con ROUTES_FILE)
+pub const DEFAULT_MANIFEST: &str = include_str!("../../routes.toml");
+
+/// Rutas que sirve el propio gateway: el manifiesto no puede redefinirlas
+const INTERNAL_PATHS: [&str; 8] = [
+    "/",
+    "/health",
+    "/api/health",
+    "/api/health/history",
+    "/api/metrics/system",
+    "/metrics",
+    "/api/schemas",
+    "/api/live",
+];
+
+/// Prefijos reservados para endpoints propios del gateway
+const INTERNAL_PREFIXES: [&str; 10] = [
+    "/api/admin",
+    "/api/alerts",
+    "/api/auth",
+    "/api/batch",
+    "/api/confluence",
+    "/api/history",
+    "/api/jobs",
+    "/api/me",
+    "/api/watchlists",
+    "/api/webhooks",
+];
+
+/// Una ruta del manifiesto que el gateway reenvía a inBestia
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteSpec {
+    /// Ruta con la sintaxis de axum (`/api/v1/items/:id`)
+    pub path: String,
+    /// Métodos HTTP aceptados
+    pub methods: Vec<String>,
+    /// Inyecta `Authorization: Bearer $INBESTIA_API_KEY`
+    #[serde(default)]
+    pub auth: bool,
+    /// Timeout hacia inBestia (por defecto REQUEST_TIMEOUT)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// TTL en caché; sin él la ruta no se cachea
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_ttl_secs: Option<u64>,
+    /// Tamaño máximo del body (por defecto MAX_REQUEST_SIZE)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_body_bytes: Option<usize>,
+    /// Clase de rate limiting a la que pertenece la ruta
+    #[serde(default = "default_rate_limit_class")]
+    pub rate_limit_class: String,
+    /// Logging de bodies de la ruta (por defecto LOG_BODIES)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_bodies: Option<BodyLogMode>,
+    /// Esquema JSON contra el que se valida el body (`AnalysisRequest`...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// Guarda cada análisis en el historial y evalúa las reglas de alerta (sólo con
+    /// `schema = "AnalysisRequest"`)
+    #[serde(default)]
+    pub history: bool,
+}
+
+fn default_rate_limit_class() -> String {
+    "default".to_string()
+}
+
+impl RouteSpec {
+    /// Timeout efectivo de la ruta
+    pub fn timeout(&self, config: &Config) -> Duration {
+        Duration::from_secs(self.timeout_secs.unwrap_or(config.request_timeout_secs))
+    }
+
+    /// Tamaño máximo efectivo del body
+    pub fn body_limit(&self, config: &Config) -> usize {
+        self.max_body_bytes.unwrap_or(config.max_request_size)
+    }
+
+    /// TTL de caché efectivo (ninguno si la caché está deshabilitada)
+    pub fn cache_ttl(&self, config: &Config) -> Option<Duration> {
+        self.cache_ttl_secs
+            .filter(|_| config.cache_enabled)
+            .map(Duration::from_secs)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RouteManifest {
+    #[serde(default)]
+    routes: Vec<RouteSpec>,
+}
+
+/// Parsea un manifiesto TOML (o JSON si `origin` termina en `.json`)
+pub fn parse_manifest(source: &str, origin: &str) -> anyhow::Result<Vec<RouteSpec>> {
+    let manifest: RouteManifest = if origin.ends_with(".json") {
+        serde_json::from_str(source)
+            .map_err(|e| anyhow::anyhow!("Invalid route manifest {}: {}", origin, e))?
+    } else {
+        toml::from_str(source)
+            .map_err(|e| anyhow::anyhow!("Invalid route manifest {}: {}", origin, e))?
+    };
+    Ok(manifest.routes)
+}
+
+/// Lee y parsea el manifiesto de `path`
+pub fn load_manifest(path: &Path) -> anyhow::Result<Vec<RouteSpec>> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Cannot read route manifest {}: {}", path.display(), e))?;
+    parse_manifest(&source, &path.display().to_string())
+}
+
+/// Comprueba el manifiesto completo y devuelve todos los errores encontrados,
+/// no sólo el primero, para poder corregirlos de una vez
+pub fn validate_routes(routes: &[RouteSpec]) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    // Forma normalizada de la ruta (parámetros anonimizados) + método → índice de la entrada
+    let mut seen: HashMap<(String, String), usize> = HashMap::new();
+
+    for (i, route) in routes.iter().enumerate() {
+        let at = format!("route #{} ({})", i + 1, route.path);
+
+        if !route.path.starts_with('/') {
+            errors.push(format!("{}: path must start with '/'", at));
+        }
+        if INTERNAL_PATHS.contains(&route.path.as_str())
+            || INTERNAL_PREFIXES.iter().any(|prefix| {
+                route.path == *prefix || route.path.starts_with(&format!("{}/", prefix))
+            })
+        {
+            errors.push(format!("{}: path is reserved for the gateway", at));
+        }
+        if route.methods.is_empty() {
+            errors.push(format!("{}: at least one method is required", at));
+        }
+        for method in &route.methods {
+            if method_filter(method).is_none() {
+                errors.push(format!("{}: unsupported method '{}'", at, method));
+                continue;
+            }
+            let key = (route_shape(&route.path), method.to_ascii_uppercase());
+            if let Some(first) = seen.get(&key) {
+                errors.push(format!(
+                    "{}: {} conflicts with route #{} ({})",
+                    at,
+                    key.1,
+                    first + 1,
+                    routes[*first].path
+                ));
+            } else {
+                seen.insert(key, i);
+            }
+        }
+        if route.timeout_secs == Some(0) {
+            errors.push(format!("{}: timeout_secs must be greater than zero", at));
+        }
+        if route.cache_ttl_secs == Some(0) {
+            errors.push(format!(
+                "{}: cache_ttl_secs must be greater than zero (omit it to disable caching)",
+                at
+            ));
+        }
+        if route.max_body_bytes == Some(0) {
+            errors.push(format!("{}: max_body_bytes must be greater than zero", at));
+        }
+        if route.rate_limit_class.trim().is_empty() {
+            errors.push(format!("{}: rate_limit_class cannot be empty", at));
+        }
+        if let Some(schema) = route.schema.as_deref() {
+            if !Validators::is_known(schema) {
+                errors.push(format!(
+                    "{}: unknown schema '{}' (expected one of: {})",
+                    at,
+                    schema,
+                    SCHEMA_NAMES.join(", ")
+                ));
+            }
+        }
+        if route.history && route.schema.as_deref() != Some("AnalysisRequest") {
+            errors.push(format!(
+                "{}: history requires schema = \"AnalysisRequest\"",
+                at
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// `/items/:id` y `/items/:key` son la misma ruta para el router
+fn route_shape(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.chars().next() {
+            Some(':') => ":",
+            Some('*') => "*",
+            _ => segment,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn method_filter(method: &str) -> Option<MethodFilter> {
+    Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+        .ok()
+        .and_then(|method| MethodFilter::try_from(method).ok())
+}
+
+/// Construye el handler de una entrada del manifiesto (manifiesto ya validado).
+/// Las rutas con `auth` exigen una sesión del gateway antes de inyectar la API key.
+pub fn method_router(state: &AppState, spec: &RouteSpec) -> MethodRouter<AppState> {
+    let spec = Arc::new(spec.clone());
+    let handler = {
+        let spec = spec.clone();
+        move |State(s): State<AppState>, req: Request<Body>| {
+            let spec = spec.clone();
+            async move {
+                if spec.history {
+                    forward_recorded(s, req, &spec).await
+                } else {
+                    forward(s, req, &spec).await
+                }
+            }
+        }
+    };
+
+    let router = spec
+        .methods
+        .iter()
+        .filter_map(|method| method_filter(method))
+        .fold(MethodRouter::new(), |router, filter| {
+            router.on(filter, handler.clone())
+        });
+
+    guard(
+        state,
+        router,
+        spec.auth,
+        Some(&spec.rate_limit_class),
+        spec.schema.as_deref(),
+        spec.body_limit(&state.settings().config),
+    )
+}
+
+/// Envuelve `router` con las capas de una ruta protegida: validación del body contra
+/// `schema`, rate limiting de `class` y, con `auth`, sesión del gateway. Sin `class` es
+/// el handler quien cobra con `middleware::charge`, cuando conoce el coste de la request
+pub fn guard(
+    state: &AppState,
+    router: MethodRouter<AppState>,
+    auth: bool,
+    class: Option<&str>,
+    schema: Option<&str>,
+    body_limit: usize,
+) -> MethodRouter<AppState> {
+    // La validación es la capa más interna: sólo se valida lo que ya pasó sesión y rate limiting
+    let router = match schema {
+        Some(schema) => {
+            let (state, schema) = (state.clone(), schema.to_string());
+            router.route_layer(from_fn(move |req: Request<Body>, next: Next| {
+                validate_body(state.clone(), schema.clone(), body_limit, req, next)
+            }))
+        }
+        None => router,
+    };
+
+    // El rate limiting va dentro de la sesión para identificar al usuario autenticado
+    let router = match class.filter(|_| state.settings().config.rate_limit_enabled) {
+        Some(class) => {
+            let (state, class) = (state.clone(), class.to_string());
+            router.route_layer(from_fn(move |req: Request<Body>, next: Next| {
+                rate_limit(state.clone(), class.clone(), req, next)
+            }))
+        }
+        None => router,
+    };
+
+    if auth {
+        router.route_layer(from_fn_with_state(state.clone(), require_session))
+    } else {
+        router
+    }
+}
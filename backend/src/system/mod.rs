@@ -0,0 +1,212 @@
+use serde::Serialize;
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant};
+
+/// Ticks de reloj por segundo en /proc (USER_HZ, fijo en 100 en el ABI de Linux)
+const CLOCK_TICKS_PER_SEC: f64 = 100.0;
+
+/// Cada cuánto se muestrea el uso de CPU
+const CPU_SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Marca de "sin muestra" en los atómicos de CPU
+const NO_SAMPLE: u64 = u64::MAX;
+
+/// Estado del proceso y del host leído de /proc. Fuera de Linux los valores son `None`.
+pub struct SystemMonitor {
+    started: Instant,
+    active_connections: AtomicUsize,
+    /// Uso de CPU del proceso en centésimas de punto porcentual
+    process_cpu: AtomicU64,
+    /// Uso de CPU del host en centésimas de punto porcentual
+    host_cpu: AtomicU64,
+}
+
+/// Memoria del host según /proc/meminfo
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct HostMemory {
+    pub total_mb: u64,
+    pub available_mb: u64,
+}
+
+/// Tareas y workers del runtime de tokio
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TokioStats {
+    pub workers: usize,
+    pub alive_tasks: usize,
+    pub global_queue_depth: usize,
+}
+
+impl Default for SystemMonitor {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            active_connections: AtomicUsize::new(0),
+            process_cpu: AtomicU64::new(NO_SAMPLE),
+            host_cpu: AtomicU64::new(NO_SAMPLE),
+        }
+    }
+}
+
+impl SystemMonitor {
+    /// Cuenta una conexión activa mientras viva el guard
+    pub fn connection(self: &Arc<Self>) -> ConnectionGuard {
+        self.active_connections.fetch_add(1, Ordering::Relaxed);
+        ConnectionGuard(self.clone())
+    }
+
+    pub fn uptime_secs(&self) -> u64 {
+        self.started.elapsed().as_secs()
+    }
+
+    pub fn active_connections(&self) -> usize {
+        self.active_connections.load(Ordering::Relaxed)
+    }
+
+    /// Uso de CPU del proceso en el último intervalo de muestreo (100% = un núcleo)
+    pub fn process_cpu_percent(&self) -> Option<f64> {
+        load_percent(&self.process_cpu)
+    }
+
+    /// Uso de CPU del host en el último intervalo de muestreo (100% = todos los núcleos)
+    pub fn host_cpu_percent(&self) -> Option<f64> {
+        load_percent(&self.host_cpu)
+    }
+
+    /// Muestrea la CPU cada `CPU_SAMPLE_INTERVAL` en segundo plano
+    pub fn spawn_sampler(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let monitor = self.clone();
+        tokio::spawn(async move {
+            let mut previous = CpuSample::read();
+            let mut ticker = tokio::time::interval(CPU_SAMPLE_INTERVAL);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let current = CpuSample::read();
+                if let (Some(prev), Some(curr)) = (&previous, &current) {
+                    let wall = curr.at.duration_since(prev.at).as_secs_f64();
+                    if wall > 0.0 {
+                        let process_secs = curr.process_ticks.saturating_sub(prev.process_ticks)
+                            as f64
+                            / CLOCK_TICKS_PER_SEC;
+                        store_percent(&monitor.process_cpu, process_secs / wall * 100.0);
+                    }
+                    let total = curr.host_total.saturating_sub(prev.host_total);
+                    if total > 0 {
+                        let idle = curr.host_idle.saturating_sub(prev.host_idle);
+                        store_percent(
+                            &monitor.host_cpu,
+                            (1.0 - idle as f64 / total as f64) * 100.0,
+                        );
+                    }
+                }
+                previous = current;
+            }
+        })
+    }
+}
+
+/// Descuenta la conexión al soltarse (también si la request se cancela)
+pub struct ConnectionGuard(Arc<SystemMonitor>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.active_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+fn store_percent(cell: &AtomicU64, percent: f64) {
+    cell.store((percent.max(0.0) * 100.0).round() as u64, Ordering::Relaxed);
+}
+
+fn load_percent(cell: &AtomicU64) -> Option<f64> {
+    match cell.load(Ordering::Relaxed) {
+        NO_SAMPLE => None,
+        hundredths => Some(hundredths as f64 / 100.0),
+    }
+}
+
+struct CpuSample {
+    at: Instant,
+    process_ticks: u64,
+    host_total: u64,
+    host_idle: u64,
+}
+
+impl CpuSample {
+    fn read() -> Option<Self> {
+        let (host_total, host_idle) = host_cpu_ticks()?;
+        Some(Self {
+            at: Instant::now(),
+            process_ticks: process_cpu_ticks()?,
+            host_total,
+            host_idle,
+        })
+    }
+}
+
+/// utime + stime del proceso (campos 14 y 15 de /proc/self/stat)
+fn process_cpu_ticks() -> Option<u64> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    // El nombre del proceso va entre paréntesis y puede contener espacios
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+/// Ticks totales y ociosos (idle + iowait) del host, de la línea `cpu` de /proc/stat
+fn host_cpu_ticks() -> Option<(u64, u64)> {
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    let values: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    let idle = values.get(3)? + values.get(4).copied().unwrap_or(0);
+    Some((values.iter().sum(), idle))
+}
+
+/// Memoria residente (RSS) del proceso en MB
+pub fn process_rss_mb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    proc_kb(&status, "VmRSS:").map(|kb| kb / 1024)
+}
+
+/// Memoria total y disponible del host
+pub fn host_memory() -> Option<HostMemory> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    Some(HostMemory {
+        total_mb: proc_kb(&meminfo, "MemTotal:")? / 1024,
+        available_mb: proc_kb(&meminfo, "MemAvailable:")? / 1024,
+    })
+}
+
+/// Descriptores de fichero abiertos por el proceso
+pub fn open_fds() -> Option<usize> {
+    Some(std::fs::read_dir("/proc/self/fd").ok()?.count())
+}
+
+/// Estado del runtime de tokio actual
+pub fn tokio_stats() -> TokioStats {
+    let metrics = tokio::runtime::Handle::current().metrics();
+    TokioStats {
+        workers: metrics.num_workers(),
+        alive_tasks: metrics.num_alive_tasks(),
+        global_queue_depth: metrics.global_queue_depth(),
+    }
+}
+
+/// Valor en kB de una línea `Clave:   1234 kB` de un fichero de /proc
+fn proc_kb(content: &str, key: &str) -> Option<u64> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix(key))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
@@ -0,0 +1,134 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, HeaderMap, HeaderName, HeaderValue, Request},
+};
+use std::convert::Infallible;
+
+/// Cabecera de W3C Trace Context: `00-<trace-id>-<parent-id>-<flags>`
+pub const TRACEPARENT: HeaderName = HeaderName::from_static("traceparent");
+
+/// Identificador de la request elegido por el cliente (o generado por el gateway)
+pub const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longitud máxima aceptada para un `X-Request-Id` del cliente
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Contexto de trazas de una request.
+///
+/// `trace_id` se respeta si llega un `traceparent` válido y se genera si no; `span_id`
+/// identifica siempre el tramo del gateway y es el padre de la llamada a inBestia.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: String,
+    pub span_id: String,
+    pub sampled: bool,
+    pub request_id: String,
+}
+
+impl TraceContext {
+    /// Lee `traceparent` y `X-Request-Id`, generando lo que falte o no sea válido
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let parent = headers
+            .get(&TRACEPARENT)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_traceparent);
+        let request_id = headers
+            .get(&REQUEST_ID)
+            .and_then(|v| v.to_str().ok())
+            .filter(|id| valid_request_id(id))
+            .map(str::to_string)
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let (trace_id, sampled) = parent.unwrap_or_else(|| (random_hex::<16>(), true));
+
+        Self {
+            trace_id,
+            span_id: random_hex::<8>(),
+            sampled,
+            request_id,
+        }
+    }
+
+    /// Contexto de la request: el de `middleware::propagate_trace` o uno nuevo
+    pub fn of<B>(req: &Request<B>) -> Self {
+        req.extensions()
+            .get::<TraceContext>()
+            .cloned()
+            .unwrap_or_else(|| TraceContext::from_headers(req.headers()))
+    }
+
+    /// `traceparent` con el gateway como tramo padre
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{:02x}",
+            self.trace_id,
+            self.span_id,
+            u8::from(self.sampled)
+        )
+    }
+
+    /// Escribe `traceparent` y `X-Request-Id` en `headers`, sustituyendo los que hubiera
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        if let Ok(value) = HeaderValue::from_str(&self.traceparent()) {
+            headers.insert(TRACEPARENT, value);
+        }
+        if let Ok(value) = HeaderValue::from_str(&self.request_id) {
+            headers.insert(REQUEST_ID, value);
+        }
+    }
+}
+
+/// Extrae el contexto que dejó `middleware::propagate_trace`; sin él, lo crea de las cabeceras
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for TraceContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<TraceContext>()
+            .cloned()
+            .unwrap_or_else(|| TraceContext::from_headers(&parts.headers)))
+    }
+}
+
+/// `(trace_id, sampled)` de un `traceparent` válido de la versión 00 (o posterior)
+fn parse_traceparent(value: &str) -> Option<(String, bool)> {
+    let mut fields = value.trim().split('-');
+    let version = fields.next()?;
+    let trace_id = fields.next()?;
+    let parent_id = fields.next()?;
+    let flags = fields.next()?;
+    // La versión 00 tiene exactamente cuatro campos; las futuras pueden añadir más
+    if version == "00" && fields.next().is_some() {
+        return None;
+    }
+
+    let valid = is_lower_hex(version, 2)
+        && version != "ff"
+        && is_lower_hex(trace_id, 32)
+        && trace_id.bytes().any(|b| b != b'0')
+        && is_lower_hex(parent_id, 16)
+        && parent_id.bytes().any(|b| b != b'0')
+        && is_lower_hex(flags, 2);
+    if !valid {
+        return None;
+    }
+    let flags = u8::from_str_radix(flags, 16).ok()?;
+    Some((trace_id.to_string(), flags & 0x01 == 0x01))
+}
+
+fn is_lower_hex(value: &str, len: usize) -> bool {
+    value.len() == len
+        && value
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+fn random_hex<const N: usize>() -> String {
+    hex::encode(rand::random::<[u8; N]>())
+}
@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::{info, warn};
+
+use crate::config::{Config, Secret};
+
+/// Claves de inBestia para rotarlas sin cortes: la principal y las de reserva.
+///
+/// Cuando inBestia responde 401 se repite la llamada con la siguiente clave. La última
+/// clave aceptada es la primera que se prueba después, así que durante una rotación sólo
+/// se paga el 401 extra una vez.
+#[derive(Debug)]
+pub struct ApiKeys {
+    /// La principal primero
+    keys: Vec<Secret>,
+    /// Índice de la última clave aceptada
+    active: AtomicUsize,
+}
+
+impl ApiKeys {
+    pub fn new(primary: Secret, fallbacks: Vec<Secret>) -> Self {
+        Self {
+            keys: std::iter::once(primary).chain(fallbacks).collect(),
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.api_key.clone(), config.api_key_fallbacks.clone())
+    }
+
+    /// La clave que se usa ahora (la última aceptada)
+    pub fn current(&self) -> &Secret {
+        &self.keys[self.active_index()]
+    }
+
+    /// Hay claves de reserva que probar tras un 401
+    pub fn has_fallbacks(&self) -> bool {
+        self.keys.len() > 1
+    }
+
+    /// Claves en el orden en que se prueban, con su índice (0 = la principal): la
+    /// última aceptada y después las demás
+    pub fn attempts(&self) -> impl Iterator<Item = (usize, &Secret)> {
+        let start = self.active_index();
+        (0..self.keys.len())
+            .map(move |offset| (start + offset) % self.keys.len())
+            .map(|index| (index, &self.keys[index]))
+    }
+
+    /// inBestia aceptó la clave `index`
+    pub fn accepted(&self, index: usize) {
+        let previous = self.active.swap(index, Ordering::Relaxed);
+        if previous == index {
+            return;
+        }
+        if index == 0 {
+            info!("inBestia accepts the primary API key again");
+        } else {
+            warn!(
+                key = index,
+                "inBestia rejected the current API key, switched to a fallback key"
+            );
+        }
+    }
+
+    fn active_index(&self) -> usize {
+        self.active.load(Ordering::Relaxed).min(self.keys.len() - 1)
+    }
+}
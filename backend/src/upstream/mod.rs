@@ -0,0 +1,225 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::config::{Config, UpstreamStrategy};
+use crate::handlers::probe_upstream;
+use crate::health::HealthSample;
+use crate::AppState;
+
+mod keys;
+
+pub use keys::ApiKeys;
+
+/// Peso de la última muestra en la media móvil exponencial de latencia
+const LATENCY_EWMA_WEIGHT: f64 = 0.3;
+
+/// Una réplica de inBestia
+#[derive(Debug)]
+pub struct UpstreamNode {
+    pub url: String,
+    healthy: AtomicBool,
+    consecutive_failures: AtomicU32,
+    consecutive_successes: AtomicU32,
+    /// Media móvil de latencia en microsegundos (0 = sin muestras)
+    latency_us: AtomicU64,
+}
+
+impl UpstreamNode {
+    fn new(url: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            healthy: AtomicBool::new(true),
+            consecutive_failures: AtomicU32::new(0),
+            consecutive_successes: AtomicU32::new(0),
+            latency_us: AtomicU64::new(0),
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    fn latency_us(&self) -> u64 {
+        self.latency_us.load(Ordering::Relaxed)
+    }
+
+    fn observe_latency(&self, elapsed: Duration) {
+        let sample = elapsed.as_micros() as u64;
+        let _ = self
+            .latency_us
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+                Some(if current == 0 {
+                    sample
+                } else {
+                    (LATENCY_EWMA_WEIGHT * sample as f64
+                        + (1.0 - LATENCY_EWMA_WEIGHT) * current as f64) as u64
+                })
+            });
+    }
+}
+
+/// Estado de una réplica para `/api/health`
+#[derive(Debug, Clone, Serialize)]
+pub struct UpstreamSnapshot {
+    pub url: String,
+    pub healthy: bool,
+    pub consecutive_failures: u32,
+    pub latency_ms: Option<f64>,
+}
+
+/// Pool de réplicas de inBestia con selección round-robin o por menor latencia.
+///
+/// Las réplicas se expulsan tras `unhealthy_threshold` fallos consecutivos (de health
+/// checks activos o de requests reales) y se readmiten tras `healthy_threshold`
+/// health checks correctos seguidos.
+pub struct UpstreamPool {
+    nodes: Vec<Arc<UpstreamNode>>,
+    strategy: UpstreamStrategy,
+    next: AtomicUsize,
+    unhealthy_threshold: u32,
+    healthy_threshold: u32,
+}
+
+impl UpstreamPool {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            nodes: config
+                .api_base_urls
+                .iter()
+                .map(|url| Arc::new(UpstreamNode::new(url)))
+                .collect(),
+            strategy: config.upstream_strategy,
+            next: AtomicUsize::new(0),
+            unhealthy_threshold: config.upstream_unhealthy_threshold,
+            healthy_threshold: config.upstream_healthy_threshold,
+        }
+    }
+
+    pub fn nodes(&self) -> &[Arc<UpstreamNode>] {
+        &self.nodes
+    }
+
+    /// Elige la réplica para la próxima request
+    pub fn select(&self) -> Arc<UpstreamNode> {
+        self.select_excluding(None)
+    }
+
+    /// Elige una réplica evitando `exclude` (la que acaba de fallar) si hay alternativa.
+    /// Si todas están expulsadas se usa igualmente alguna: mejor intentarlo que fallar seguro.
+    pub fn select_excluding(&self, exclude: Option<&str>) -> Arc<UpstreamNode> {
+        let healthy: Vec<&Arc<UpstreamNode>> =
+            self.nodes.iter().filter(|node| node.is_healthy()).collect();
+        let pool = if healthy.is_empty() {
+            self.nodes.iter().collect()
+        } else {
+            healthy
+        };
+        let candidates: Vec<&Arc<UpstreamNode>> = match exclude {
+            Some(url) if pool.len() > 1 => pool.iter().copied().filter(|n| n.url != url).collect(),
+            _ => pool,
+        };
+
+        let chosen = match self.strategy {
+            UpstreamStrategy::RoundRobin => {
+                candidates[self.next.fetch_add(1, Ordering::Relaxed) % candidates.len()]
+            }
+            // Las réplicas sin muestras (latencia 0) se prueban primero
+            UpstreamStrategy::LeastLatency => candidates
+                .iter()
+                .copied()
+                .min_by_key(|node| node.latency_us())
+                .unwrap_or(candidates[0]),
+        };
+        chosen.clone()
+    }
+
+    /// Registra el resultado de una request real contra `node`
+    pub fn report(&self, node: &UpstreamNode, success: bool, elapsed: Duration) {
+        if success {
+            node.observe_latency(elapsed);
+            node.consecutive_failures.store(0, Ordering::Relaxed);
+        } else {
+            self.record_failure(node);
+        }
+    }
+
+    /// Registra el resultado de un health check activo contra `node`
+    fn report_probe(&self, node: &UpstreamNode, success: bool, elapsed: Duration) {
+        if !success {
+            node.consecutive_successes.store(0, Ordering::Relaxed);
+            self.record_failure(node);
+            return;
+        }
+
+        node.observe_latency(elapsed);
+        node.consecutive_failures.store(0, Ordering::Relaxed);
+        let successes = node.consecutive_successes.fetch_add(1, Ordering::Relaxed) + 1;
+        if !node.is_healthy() && successes >= self.healthy_threshold {
+            node.healthy.store(true, Ordering::Relaxed);
+            info!(upstream = %node.url, "upstream readmitted into pool");
+        }
+    }
+
+    fn record_failure(&self, node: &UpstreamNode) {
+        let failures = node.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if node.is_healthy() && failures >= self.unhealthy_threshold {
+            node.healthy.store(false, Ordering::Relaxed);
+            node.consecutive_successes.store(0, Ordering::Relaxed);
+            warn!(upstream = %node.url, consecutive_failures = failures, "upstream ejected from pool");
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<UpstreamSnapshot> {
+        self.nodes
+            .iter()
+            .map(|node| UpstreamSnapshot {
+                url: node.url.clone(),
+                healthy: node.is_healthy(),
+                consecutive_failures: node.consecutive_failures.load(Ordering::Relaxed),
+                latency_ms: match node.latency_us() {
+                    0 => None,
+                    us => Some(us as f64 / 1000.0),
+                },
+            })
+            .collect()
+    }
+}
+
+/// Lanza en segundo plano los health checks activos de todas las réplicas
+pub fn spawn_health_checks(state: AppState) -> tokio::task::JoinHandle<()> {
+    let interval = Duration::from_secs(state.settings().config.upstream_health_interval_secs);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            check_all(&state).await;
+        }
+    })
+}
+
+/// Ejecuta una ronda de health checks contra todas las réplicas en paralelo
+/// y guarda cada resultado en el historial
+pub async fn check_all(state: &AppState) {
+    let probes = state.upstreams.nodes().iter().map(|node| async move {
+        let probe = probe_upstream(state, &node.url).await;
+        let success = matches!(&probe.outcome, Ok(status) if status.is_success());
+        state.upstreams.report_probe(node, success, probe.elapsed);
+        state.health.record(
+            &node.url,
+            HealthSample {
+                at: Utc::now(),
+                ok: success,
+                http_status: probe.outcome.as_ref().ok().map(|status| status.as_u16()),
+                latency_ms: probe.elapsed.as_secs_f64() * 1000.0,
+                error: probe.outcome.as_ref().err().map(|e| e.to_string()),
+            },
+        );
+    });
+    futures_util::future::join_all(probes).await;
+}
@@ -0,0 +1,163 @@
+use ghost_shared::{
+    AlertRuleRequest, AnalysisRequest, BatchAnalysisRequest, CompareRequest, HistoricalRequest,
+    IndicatorsRequest, WatchlistRequest,
+};
+use jsonschema::{
+    error::{TypeKind, ValidationErrorKind},
+    ValidationError, Validator,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Esquemas que puede declarar una ruta del manifiesto (`schema = "..."`),
+/// generados a partir de los tipos de `ghost-shared`
+pub const SCHEMA_NAMES: [&str; 7] = [
+    "AnalysisRequest",
+    "HistoricalRequest",
+    "IndicatorsRequest",
+    "CompareRequest",
+    "BatchAnalysisRequest",
+    "WatchlistRequest",
+    "AlertRuleRequest",
+];
+
+/// Un error de validación de un campo del body
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    /// Ruta del campo (`symbol`, `symbols.1`); `body` si el error es del documento entero
+    pub field: String,
+    pub message: String,
+}
+
+struct Compiled {
+    schema: Value,
+    validator: Validator,
+}
+
+/// Validadores JSON Schema de los bodies de las rutas, compilados una sola vez
+pub struct Validators {
+    schemas: BTreeMap<&'static str, Compiled>,
+}
+
+impl Validators {
+    pub fn new() -> anyhow::Result<Self> {
+        let schemas = [
+            ("AnalysisRequest", schemars::schema_for!(AnalysisRequest)),
+            (
+                "HistoricalRequest",
+                schemars::schema_for!(HistoricalRequest),
+            ),
+            (
+                "IndicatorsRequest",
+                schemars::schema_for!(IndicatorsRequest),
+            ),
+            ("CompareRequest", schemars::schema_for!(CompareRequest)),
+            (
+                "BatchAnalysisRequest",
+                schemars::schema_for!(BatchAnalysisRequest),
+            ),
+            ("WatchlistRequest", schemars::schema_for!(WatchlistRequest)),
+            ("AlertRuleRequest", schemars::schema_for!(AlertRuleRequest)),
+        ]
+        .into_iter()
+        .map(|(name, schema)| {
+            let schema = schema.to_value();
+            let validator = jsonschema::validator_for(&schema)
+                .map_err(|e| anyhow::anyhow!("Invalid JSON schema {}: {}", name, e))?;
+            Ok((name, Compiled { schema, validator }))
+        })
+        .collect::<anyhow::Result<_>>()?;
+        Ok(Self { schemas })
+    }
+
+    /// ¿Existe un esquema con ese nombre?
+    pub fn is_known(name: &str) -> bool {
+        SCHEMA_NAMES.contains(&name)
+    }
+
+    /// JSON Schema publicado de `name`
+    pub fn schema(&self, name: &str) -> Option<&Value> {
+        self.schemas.get(name).map(|compiled| &compiled.schema)
+    }
+
+    /// Todos los esquemas, por nombre
+    pub fn schemas(&self) -> impl Iterator<Item = (&'static str, &Value)> {
+        self.schemas
+            .iter()
+            .map(|(name, compiled)| (*name, &compiled.schema))
+    }
+
+    /// Valida `instance` contra el esquema `name` y devuelve los errores por campo.
+    /// Un esquema desconocido no valida nada (el manifiesto ya se comprobó al arrancar).
+    pub fn validate(&self, name: &str, instance: &Value) -> Result<(), Vec<FieldError>> {
+        let Some(compiled) = self.schemas.get(name) else {
+            return Ok(());
+        };
+        let errors: Vec<FieldError> = compiled
+            .validator
+            .iter_errors(instance)
+            .map(|error| field_error(&error))
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn field_error(error: &ValidationError) -> FieldError {
+    let mut segments: Vec<String> = error
+        .instance_path()
+        .as_str()
+        .split('/')
+        .skip(1)
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    // Un campo obligatorio que falta se atribuye al propio campo, no al objeto que lo contiene
+    if let ValidationErrorKind::Required { property } = error.kind() {
+        segments.push(property.as_str().unwrap_or_default().to_string());
+    }
+    let field = if segments.is_empty() {
+        "body".to_string()
+    } else {
+        segments.join(".")
+    };
+
+    FieldError {
+        field,
+        message: message(error),
+    }
+}
+
+fn message(error: &ValidationError) -> String {
+    match error.kind() {
+        ValidationErrorKind::Required { .. } => "Campo obligatorio".to_string(),
+        ValidationErrorKind::Type {
+            kind: TypeKind::Single(kind),
+        } => format!("Debe ser de tipo {}", kind),
+        ValidationErrorKind::Enum { options } => format!(
+            "Valor {} no admitido; valores válidos: {}",
+            error.instance(),
+            options
+                .as_array()
+                .map(|options| options
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", "))
+                .unwrap_or_default()
+        ),
+        ValidationErrorKind::Pattern { .. } => format!("Formato inválido: {}", error.instance()),
+        ValidationErrorKind::Minimum { limit } => format!("Debe ser como mínimo {}", limit),
+        ValidationErrorKind::Maximum { limit } => format!("Debe ser como máximo {}", limit),
+        ValidationErrorKind::MinItems { limit } => {
+            format!("Debe tener al menos {} elementos", limit)
+        }
+        ValidationErrorKind::MaxItems { limit } => {
+            format!("Debe tener como máximo {} elementos", limit)
+        }
+        _ => error.to_string(),
+    }
+}
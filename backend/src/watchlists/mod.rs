@@ -0,0 +1,235 @@
+use axum::{http::StatusCode, response::Response};
+use chrono::{DateTime, Utc};
+use ghost_shared::responses::{Watchlist, WatchlistItem};
+use ghost_shared::WatchlistRequest;
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use std::fmt;
+use tracing::error;
+
+use crate::errors::error_response;
+
+/// Fallo de una operación sobre las watchlists
+#[derive(Debug)]
+pub enum WatchlistError {
+    /// No existe o es de otro usuario
+    NotFound,
+    /// El usuario ya tiene una watchlist con ese nombre
+    NameTaken,
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for WatchlistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "watchlist not found"),
+            Self::NameTaken => write!(f, "watchlist name already in use"),
+            Self::Database(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for WatchlistError {}
+
+impl From<sqlx::Error> for WatchlistError {
+    fn from(e: sqlx::Error) -> Self {
+        let name_taken = e
+            .as_database_error()
+            .and_then(|db| db.constraint())
+            .is_some_and(|constraint| constraint == "watchlists_owner_name_key");
+        if name_taken {
+            Self::NameTaken
+        } else {
+            Self::Database(e)
+        }
+    }
+}
+
+impl WatchlistError {
+    /// Respuesta de error estándar del gateway para este fallo
+    pub fn to_response(&self, trace_id: &str) -> Response {
+        match self {
+            Self::NotFound => error_response(
+                StatusCode::NOT_FOUND,
+                "WATCHLIST_NOT_FOUND",
+                "Watchlist no encontrada",
+                trace_id,
+            ),
+            Self::NameTaken => error_response(
+                StatusCode::CONFLICT,
+                "WATCHLIST_NAME_TAKEN",
+                "Ya existe una watchlist con ese nombre",
+                trace_id,
+            ),
+            Self::Database(e) => {
+                error!(trace_id, error = %e, "watchlist query failed");
+                error_response(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "DATABASE_UNAVAILABLE",
+                    "La base de datos no está disponible",
+                    trace_id,
+                )
+            }
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct ListRow {
+    id: i64,
+    name: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(FromRow)]
+struct ItemRow {
+    watchlist_id: i64,
+    symbol: String,
+    timeframe: String,
+    notes: String,
+}
+
+impl ListRow {
+    fn into_watchlist(self, items: &[ItemRow]) -> Watchlist {
+        Watchlist {
+            id: self.id,
+            name: self.name,
+            items: items
+                .iter()
+                .filter(|item| item.watchlist_id == self.id)
+                .map(|item| WatchlistItem {
+                    symbol: item.symbol.clone(),
+                    timeframe: item.timeframe.clone(),
+                    notes: item.notes.clone(),
+                })
+                .collect(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+/// Watchlists de `owner`, por nombre
+pub async fn list(db: &PgPool, owner: &str) -> Result<Vec<Watchlist>, WatchlistError> {
+    let lists: Vec<ListRow> = sqlx::query_as(
+        "SELECT id, name, created_at, updated_at FROM watchlists WHERE owner = $1 ORDER BY name",
+    )
+    .bind(owner)
+    .fetch_all(db)
+    .await?;
+    let items: Vec<ItemRow> = sqlx::query_as(
+        "SELECT i.watchlist_id, i.symbol, i.timeframe, i.notes
+         FROM watchlist_items i JOIN watchlists w ON w.id = i.watchlist_id
+         WHERE w.owner = $1
+         ORDER BY i.watchlist_id, i.position",
+    )
+    .bind(owner)
+    .fetch_all(db)
+    .await?;
+    Ok(lists
+        .into_iter()
+        .map(|list| list.into_watchlist(&items))
+        .collect())
+}
+
+pub async fn get(db: &PgPool, owner: &str, id: i64) -> Result<Watchlist, WatchlistError> {
+    let list: ListRow = sqlx::query_as(
+        "SELECT id, name, created_at, updated_at FROM watchlists WHERE id = $1 AND owner = $2",
+    )
+    .bind(id)
+    .bind(owner)
+    .fetch_optional(db)
+    .await?
+    .ok_or(WatchlistError::NotFound)?;
+    let items: Vec<ItemRow> = sqlx::query_as(
+        "SELECT watchlist_id, symbol, timeframe, notes FROM watchlist_items
+         WHERE watchlist_id = $1 ORDER BY position",
+    )
+    .bind(id)
+    .fetch_all(db)
+    .await?;
+    Ok(list.into_watchlist(&items))
+}
+
+pub async fn create(
+    db: &PgPool,
+    owner: &str,
+    request: &WatchlistRequest,
+) -> Result<Watchlist, WatchlistError> {
+    let mut tx = db.begin().await?;
+    let (id,): (i64,) =
+        sqlx::query_as("INSERT INTO watchlists (owner, name) VALUES ($1, $2) RETURNING id")
+            .bind(owner)
+            .bind(&request.name)
+            .fetch_one(&mut *tx)
+            .await?;
+    insert_items(&mut tx, id, request).await?;
+    tx.commit().await?;
+    get(db, owner, id).await
+}
+
+/// Renombra la watchlist y reemplaza sus símbolos por los de `request`
+pub async fn update(
+    db: &PgPool,
+    owner: &str,
+    id: i64,
+    request: &WatchlistRequest,
+) -> Result<Watchlist, WatchlistError> {
+    let mut tx = db.begin().await?;
+    let updated = sqlx::query(
+        "UPDATE watchlists SET name = $1, updated_at = now() WHERE id = $2 AND owner = $3",
+    )
+    .bind(&request.name)
+    .bind(id)
+    .bind(owner)
+    .execute(&mut *tx)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(WatchlistError::NotFound);
+    }
+    sqlx::query("DELETE FROM watchlist_items WHERE watchlist_id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    insert_items(&mut tx, id, request).await?;
+    tx.commit().await?;
+    get(db, owner, id).await
+}
+
+pub async fn delete(db: &PgPool, owner: &str, id: i64) -> Result<(), WatchlistError> {
+    let deleted = sqlx::query("DELETE FROM watchlists WHERE id = $1 AND owner = $2")
+        .bind(id)
+        .bind(owner)
+        .execute(db)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(WatchlistError::NotFound);
+    }
+    Ok(())
+}
+
+/// Inserta los símbolos de `request` en su orden con una sola consulta
+async fn insert_items(
+    tx: &mut Transaction<'_, Postgres>,
+    watchlist_id: i64,
+    request: &WatchlistRequest,
+) -> Result<(), sqlx::Error> {
+    let positions: Vec<i32> = (0..request.items.len() as i32).collect();
+    let symbols: Vec<&str> = request.items.iter().map(|i| i.symbol.as_str()).collect();
+    let timeframes: Vec<&str> = request.items.iter().map(|i| i.timeframe.as_str()).collect();
+    let notes: Vec<&str> = request.items.iter().map(|i| i.notes.as_str()).collect();
+    sqlx::query(
+        "INSERT INTO watchlist_items (watchlist_id, position, symbol, timeframe, notes)
+         SELECT $1, position, symbol, timeframe, notes
+         FROM UNNEST($2::int4[], $3::text[], $4::text[], $5::text[])
+             AS item (position, symbol, timeframe, notes)",
+    )
+    .bind(watchlist_id)
+    .bind(&positions)
+    .bind(&symbols)
+    .bind(&timeframes)
+    .bind(&notes)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,610 @@
+use chrono::{DateTime, Utc};
+use futures_util::future::join_all;
+use ghost_shared::responses::ProviderStatus;
+use hmac::{Hmac, Mac};
+use reqwest::{header::CONTENT_TYPE, Client, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::{types::Json, FromRow, PgConnection, PgPool};
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::{Config, Secret};
+use crate::AppState;
+
+/// Nombre del evento en cada entrega
+pub const X_GHOST_EVENT: &str = "x-ghost-event";
+/// Identificador de la entrega; se repite en sus reintentos para que el receptor deduplique
+pub const X_GHOST_DELIVERY: &str = "x-ghost-delivery";
+/// `sha256=<hex>`: HMAC-SHA256 del body con el secreto del webhook
+pub const X_GHOST_SIGNATURE: &str = "x-ghost-signature";
+
+/// Entregas que se reclaman y envían a la vez
+const CLAIM_BATCH: i64 = 20;
+/// Cada cuánto se buscan entregas vencidas aunque nadie avise (p. ej. las encoladas por
+/// otra réplica del gateway)
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Margen sobre WEBHOOK_TIMEOUT_SECS durante el que una entrega reclamada no se reclama otra
+/// vez; si el gateway cae a mitad de envío, se reintenta pasado ese tiempo
+const LEASE_MARGIN_SECS: u64 = 30;
+
+/// Eventos a los que se puede suscribir un webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// Se disparó una regla de alerta (`/api/alerts`)
+    AlertFired,
+    /// Algún proveedor de `/api/v1/providers/status` cambió de disponibilidad o de estado
+    ProviderStatusChanged,
+}
+
+impl WebhookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::AlertFired => "alert_fired",
+            WebhookEvent::ProviderStatusChanged => "provider_status_changed",
+        }
+    }
+}
+
+/// Un destino del manifiesto de webhooks (WEBHOOKS_FILE)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookSpec {
+    /// Identificador del webhook en `/api/webhooks/deliveries`
+    pub name: String,
+    /// URL http(s) a la que se hace POST de cada evento
+    pub url: String,
+    /// Clave de la firma HMAC-SHA256 (`X-Ghost-Signature`)
+    #[serde(skip_serializing)]
+    pub secret: Secret,
+    pub events: Vec<WebhookEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WebhookManifest {
+    #[serde(default)]
+    webhooks: Vec<WebhookSpec>,
+}
+
+/// Parsea un manifiesto de webhooks TOML (o JSON si `origin` termina en `.json`)
+pub fn parse_manifest(source: &str, origin: &str) -> anyhow::Result<Vec<WebhookSpec>> {
+    let manifest: WebhookManifest = if origin.ends_with(".json") {
+        serde_json::from_str(source)
+            .map_err(|e| anyhow::anyhow!("Invalid webhook manifest {}: {}", origin, e))?
+    } else {
+        toml::from_str(source)
+            .map_err(|e| anyhow::anyhow!("Invalid webhook manifest {}: {}", origin, e))?
+    };
+    Ok(manifest.webhooks)
+}
+
+/// Lee y parsea el manifiesto de `path`
+pub fn load_manifest(path: &Path) -> anyhow::Result<Vec<WebhookSpec>> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Cannot read webhook manifest {}: {}", path.display(), e))?;
+    parse_manifest(&source, &path.display().to_string())
+}
+
+/// Comprueba el manifiesto completo y devuelve todos los errores encontrados
+pub fn validate_webhooks(webhooks: &[WebhookSpec]) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    let mut names = HashSet::new();
+
+    for (i, webhook) in webhooks.iter().enumerate() {
+        let at = format!("webhook #{} ({})", i + 1, webhook.name);
+
+        if webhook.name.is_empty()
+            || !webhook
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            errors.push(format!(
+                "{}: name must be non-empty and use only letters, digits, '-' or '_'",
+                at
+            ));
+        } else if !names.insert(webhook.name.as_str()) {
+            errors.push(format!("{}: duplicate webhook name", at));
+        }
+        match Url::parse(&webhook.url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            _ => errors.push(format!(
+                "{}: url must be an absolute http(s) URL, not '{}'",
+                at, webhook.url
+            )),
+        }
+        if webhook.secret.expose().trim().is_empty() {
+            errors.push(format!("{}: secret must not be empty", at));
+        }
+        if webhook.events.is_empty() {
+            errors.push(format!("{}: subscribe to at least one event", at));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Firma de un body: `sha256=` seguido del HMAC-SHA256 en hexadecimal
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Estado de una entrega
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// En cola: primer intento o reintento pendiente
+    Pending,
+    Delivered,
+    /// Agotó WEBHOOK_MAX_ATTEMPTS (dead letter); se puede reencolar a mano
+    Dead,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Dead => "dead",
+        }
+    }
+}
+
+impl FromStr for DeliveryStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(DeliveryStatus::Pending),
+            "delivered" => Ok(DeliveryStatus::Delivered),
+            "dead" => Ok(DeliveryStatus::Dead),
+            other => Err(format!(
+                "unknown delivery status '{}' (expected pending, delivered or dead)",
+                other
+            )),
+        }
+    }
+}
+
+/// Una entrega para `GET /api/webhooks/deliveries`
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook: String,
+    pub event: String,
+    pub status: String,
+    pub attempts: i32,
+    /// Próximo intento (sólo las pendientes)
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    /// Código HTTP de la última respuesta del receptor
+    pub last_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub payload: Json<Value>,
+}
+
+const DELIVERY_COLUMNS: &str = "id, webhook, event, status, attempts,
+     CASE WHEN status = 'pending' THEN next_attempt_at END AS next_attempt_at,
+     last_status, last_error, created_at, delivered_at, payload";
+
+/// Filtro de `GET /api/webhooks/deliveries`
+#[derive(Debug, Clone, Default)]
+pub struct DeliveryFilter {
+    pub status: Option<DeliveryStatus>,
+    pub webhook: Option<String>,
+    pub limit: u32,
+    pub offset: u32,
+}
+
+/// Entregas según `filter`, la más reciente primero
+pub async fn deliveries(
+    db: &PgPool,
+    filter: &DeliveryFilter,
+) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM webhook_deliveries
+         WHERE ($1::text IS NULL OR status = $1)
+           AND ($2::text IS NULL OR webhook = $2)
+         ORDER BY created_at DESC, id
+         LIMIT $3 OFFSET $4",
+        DELIVERY_COLUMNS
+    ))
+    .bind(filter.status.map(DeliveryStatus::as_str))
+    .bind(filter.webhook.as_deref())
+    .bind(i64::from(filter.limit))
+    .bind(i64::from(filter.offset))
+    .fetch_all(db)
+    .await
+}
+
+/// Resultado de reencolar una entrega
+pub enum Requeue {
+    Requeued(WebhookDelivery),
+    NotFound,
+    /// Sólo se reencolan las entregas muertas
+    NotDead(DeliveryStatus),
+}
+
+/// Vuelve a poner en cola una entrega muerta, con los intentos a cero
+pub async fn requeue(db: &PgPool, id: Uuid) -> Result<Requeue, sqlx::Error> {
+    let requeued: Option<WebhookDelivery> = sqlx::query_as(&format!(
+        "UPDATE webhook_deliveries
+         SET status = 'pending', attempts = 0, next_attempt_at = now()
+         WHERE id = $1 AND status = 'dead'
+         RETURNING {}",
+        DELIVERY_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(db)
+    .await?;
+    if let Some(delivery) = requeued {
+        return Ok(Requeue::Requeued(delivery));
+    }
+    let status: Option<String> =
+        sqlx::query_scalar("SELECT status FROM webhook_deliveries WHERE id = $1")
+            .bind(id)
+            .fetch_optional(db)
+            .await?;
+    Ok(match status.and_then(|status| status.parse().ok()) {
+        Some(status) => Requeue::NotDead(status),
+        None => Requeue::NotFound,
+    })
+}
+
+/// Webhooks configurados y su cola de entregas en PostgreSQL
+pub struct Webhooks {
+    specs: Vec<WebhookSpec>,
+    client: Client,
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    lease: Duration,
+    /// Despierta al repartidor cuando se encolan entregas nuevas
+    wake: Notify,
+}
+
+impl Webhooks {
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let timeout = Duration::from_secs(config.webhook_timeout_secs);
+        Ok(Self {
+            specs: config.webhooks.clone(),
+            client: Client::builder()
+                .timeout(timeout)
+                .user_agent(concat!("ghost-gateway/", env!("CARGO_PKG_VERSION")))
+                .build()?,
+            max_attempts: config.webhook_max_attempts,
+            base_delay: Duration::from_millis(config.webhook_retry_base_delay_ms),
+            max_delay: Duration::from_millis(config.webhook_retry_max_delay_ms),
+            lease: timeout + Duration::from_secs(LEASE_MARGIN_SECS),
+            wake: Notify::new(),
+        })
+    }
+
+    pub fn specs(&self) -> &[WebhookSpec] {
+        &self.specs
+    }
+
+    fn subscribed(&self, event: WebhookEvent) -> impl Iterator<Item = &WebhookSpec> {
+        self.specs
+            .iter()
+            .filter(move |spec| spec.events.contains(&event))
+    }
+
+    /// Encola `event` para cada webhook suscrito, dentro de la transacción de quien lo
+    /// produce; tras confirmarla hay que llamar a `wake`. Devuelve las entregas encoladas.
+    pub async fn enqueue(
+        &self,
+        conn: &mut PgConnection,
+        event: WebhookEvent,
+        data: &Value,
+    ) -> Result<usize, sqlx::Error> {
+        let payload = json!({
+            "event": event.as_str(),
+            "created_at": Utc::now(),
+            "data": data,
+        });
+        let mut queued = 0;
+        for spec in self.subscribed(event) {
+            sqlx::query(
+                "INSERT INTO webhook_deliveries (id, webhook, event, payload)
+                 VALUES ($1, $2, $3, $4)",
+            )
+            .bind(Uuid::new_v4())
+            .bind(&spec.name)
+            .bind(event.as_str())
+            .bind(Json(&payload))
+            .execute(&mut *conn)
+            .await?;
+            queued += 1;
+        }
+        Ok(queued)
+    }
+
+    /// Encola `event` y avisa al repartidor
+    pub async fn publish(
+        &self,
+        db: &PgPool,
+        event: WebhookEvent,
+        data: &Value,
+    ) -> Result<usize, sqlx::Error> {
+        if self.subscribed(event).next().is_none() {
+            return Ok(0);
+        }
+        let mut conn = db.acquire().await?;
+        let queued = self.enqueue(&mut conn, event, data).await?;
+        self.wake();
+        Ok(queued)
+    }
+
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Espera antes del intento `attempts + 1`: `base * 2^(attempts - 1)`, con tope
+    fn backoff(&self, attempts: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+            .min(self.max_delay)
+    }
+}
+
+/// Lanza el repartidor de la cola; no hace nada sin base de datos o sin webhooks
+pub fn spawn_dispatcher(state: AppState) -> Option<tokio::task::JoinHandle<()>> {
+    let db = state.db.clone()?;
+    if state.webhooks.specs.is_empty() {
+        return None;
+    }
+    Some(tokio::spawn(async move {
+        let webhooks = &state.webhooks;
+        loop {
+            let next = match dispatch_due(webhooks, &db).await {
+                Ok(next) => next,
+                Err(e) => {
+                    warn!(error = %e, "webhook dispatch failed");
+                    None
+                }
+            };
+            let wait = next
+                .map(|at| (at - Utc::now()).to_std().unwrap_or_default())
+                .unwrap_or(POLL_INTERVAL)
+                .min(POLL_INTERVAL);
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = webhooks.wake.notified() => {}
+            }
+        }
+    }))
+}
+
+#[derive(FromRow)]
+struct Claimed {
+    id: Uuid,
+    webhook: String,
+    event: String,
+    payload: Json<Value>,
+    attempts: i32,
+}
+
+/// Envía todas las entregas vencidas y devuelve cuándo vence la siguiente
+async fn dispatch_due(
+    webhooks: &Webhooks,
+    db: &PgPool,
+) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    loop {
+        // Reclamar aplaza la entrega durante el envío: otra réplica no la repite y, si
+        // este proceso cae, se reintenta cuando venza el plazo
+        let claimed: Vec<Claimed> = sqlx::query_as(
+            "UPDATE webhook_deliveries
+             SET next_attempt_at = now() + make_interval(secs => $2)
+             WHERE id IN (
+                 SELECT id FROM webhook_deliveries
+                 WHERE status = 'pending' AND next_attempt_at <= now()
+                 ORDER BY next_attempt_at
+                 LIMIT $1
+                 FOR UPDATE SKIP LOCKED)
+             RETURNING id, webhook, event, payload, attempts",
+        )
+        .bind(CLAIM_BATCH)
+        .bind(webhooks.lease.as_secs_f64())
+        .fetch_all(db)
+        .await?;
+        let full = claimed.len() as i64 == CLAIM_BATCH;
+        for result in join_all(claimed.into_iter().map(|c| deliver(webhooks, db, c))).await {
+            result?;
+        }
+        if !full {
+            break;
+        }
+    }
+    sqlx::query_scalar(
+        "SELECT min(next_attempt_at) FROM webhook_deliveries WHERE status = 'pending'",
+    )
+    .fetch_one(db)
+    .await
+}
+
+/// Un intento de entrega, con su resultado guardado en la cola
+async fn deliver(webhooks: &Webhooks, db: &PgPool, delivery: Claimed) -> Result<(), sqlx::Error> {
+    let attempts = delivery.attempts.max(0) as u32 + 1;
+    let Some(spec) = webhooks
+        .specs
+        .iter()
+        .find(|spec| spec.name == delivery.webhook)
+    else {
+        // El webhook se quitó de WEBHOOKS_FILE: no hay adónde entregar
+        return record_failure(
+            db,
+            &delivery,
+            attempts,
+            None,
+            "webhook is no longer configured",
+            None,
+        )
+        .await;
+    };
+
+    let body = serde_json::to_vec(&delivery.payload.0).unwrap_or_default();
+    let result = webhooks
+        .client
+        .post(&spec.url)
+        .header(CONTENT_TYPE, "application/json")
+        .header(X_GHOST_EVENT, &delivery.event)
+        .header(X_GHOST_DELIVERY, delivery.id.to_string())
+        .header(X_GHOST_SIGNATURE, sign(spec.secret.expose(), &body))
+        .body(body)
+        .send()
+        .await;
+
+    let (status, error) = match result {
+        Ok(response) if response.status().is_success() => {
+            sqlx::query(
+                "UPDATE webhook_deliveries
+                 SET status = 'delivered', attempts = $2, last_status = $3, last_error = NULL,
+                     delivered_at = now()
+                 WHERE id = $1",
+            )
+            .bind(delivery.id)
+            .bind(attempts as i32)
+            .bind(i32::from(response.status().as_u16()))
+            .execute(db)
+            .await?;
+            debug!(webhook = %spec.name, delivery = %delivery.id, attempts, "webhook delivered");
+            return Ok(());
+        }
+        Ok(response) => (
+            Some(i32::from(response.status().as_u16())),
+            format!("receiver responded {}", response.status()),
+        ),
+        Err(e) => (None, e.to_string()),
+    };
+    let retry_in = (attempts < webhooks.max_attempts).then(|| webhooks.backoff(attempts));
+    record_failure(db, &delivery, attempts, status, &error, retry_in).await
+}
+
+/// Guarda un intento fallido: se reintenta tras `retry_in` o, sin él, pasa a dead letter
+async fn record_failure(
+    db: &PgPool,
+    delivery: &Claimed,
+    attempts: u32,
+    status: Option<i32>,
+    error: &str,
+    retry_in: Option<Duration>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE webhook_deliveries
+         SET status = CASE WHEN $5::float8 IS NULL THEN 'dead' ELSE 'pending' END,
+             attempts = $2, last_status = $3, last_error = $4,
+             next_attempt_at = now() + make_interval(secs => coalesce($5, 0))
+         WHERE id = $1",
+    )
+    .bind(delivery.id)
+    .bind(attempts as i32)
+    .bind(status)
+    .bind(error)
+    .bind(retry_in.map(|delay| delay.as_secs_f64()))
+    .execute(db)
+    .await?;
+    match retry_in {
+        Some(delay) => {
+            debug!(webhook = %delivery.webhook, delivery = %delivery.id, attempts, error, retry_in_ms = delay.as_millis() as u64, "webhook delivery failed, will retry")
+        }
+        None => {
+            warn!(webhook = %delivery.webhook, delivery = %delivery.id, attempts, error, "webhook delivery moved to dead letter")
+        }
+    }
+    Ok(())
+}
+
+/// Lanza el sondeo de `/api/v1/providers/status` que publica `provider_status_changed`;
+/// sólo si hay base de datos y algún webhook suscrito
+pub fn spawn_provider_watch(state: AppState) -> Option<tokio::task::JoinHandle<()>> {
+    let db = state.db.clone()?;
+    state
+        .webhooks
+        .subscribed(WebhookEvent::ProviderStatusChanged)
+        .next()?;
+    let interval = Duration::from_secs(state.settings().config.webhook_providers_poll_secs);
+    Some(tokio::spawn(async move {
+        let client = state.inbestia();
+        let mut ticker = tokio::time::interval(interval);
+        // La primera lectura sólo fija el estado de partida
+        let mut known: Option<Vec<ProviderStatus>> = None;
+        loop {
+            ticker.tick().await;
+            let providers = match client.providers_status().await {
+                Ok(providers) => providers,
+                Err(e) => {
+                    debug!(error = %e, "provider status unavailable for webhooks");
+                    continue;
+                }
+            };
+            if let Some(previous) = &known {
+                let changes = provider_changes(previous, &providers);
+                if !changes.is_empty() {
+                    info!(changes = changes.len(), "provider status changed");
+                    let data = json!({ "changes": changes, "providers": providers });
+                    if let Err(e) = state
+                        .webhooks
+                        .publish(&db, WebhookEvent::ProviderStatusChanged, &data)
+                        .await
+                    {
+                        warn!(error = %e, "provider status webhook not queued");
+                    }
+                }
+            }
+            known = Some(providers);
+        }
+    }))
+}
+
+/// Proveedores que aparecen, desaparecen o cambian `available`/`active`, por `type_code`
+pub fn provider_changes(previous: &[ProviderStatus], current: &[ProviderStatus]) -> Vec<Value> {
+    let state = |provider: &ProviderStatus| json!({ "available": provider.available, "active": provider.active });
+    let find = |providers: &[ProviderStatus], type_code: &str| {
+        providers
+            .iter()
+            .find(|provider| provider.type_code == type_code)
+            .map(state)
+    };
+
+    let mut changes = Vec::new();
+    for provider in current {
+        let from = find(previous, &provider.type_code);
+        let to = state(provider);
+        if from.as_ref() != Some(&to) {
+            changes.push(json!({
+                "type_code": provider.type_code,
+                "name": provider.name,
+                "from": from,
+                "to": to,
+            }));
+        }
+    }
+    for provider in previous {
+        if find(current, &provider.type_code).is_none() {
+            changes.push(json!({
+                "type_code": provider.type_code,
+                "name": provider.name,
+                "from": state(provider),
+                "to": null,
+            }));
+        }
+    }
+    changes
+}
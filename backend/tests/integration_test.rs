@@ -1,36 +1,181 @@
 use axum::{
-    body::Body,
-    http::{Request, StatusCode},
+    body::{to_bytes, Body, Bytes},
+    http::{HeaderMap, Request, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
     Router,
 };
-use reqwest::Client;
-use std::net::SocketAddr;
+use ghost_backend::{
+    alerts::expr,
+    app,
+    auth::{hash_password, SessionManager},
+    bodylog::{BodyLogPolicy, RedactPath},
+    config::{BodyLogMode, Config, ConfigSources, RateLimitRule, Secret},
+    db,
+    jobs::{self, JobKind, JobSpec, Trigger},
+    live::{self, LiveHub, Topic},
+    ratelimit::RateLimiter,
+    resilience::CircuitBreakers,
+    reload::{self, Reloader},
+    routes::{parse_manifest, validate_routes},
+    upstream,
+    webhooks::{self, WebhookEvent, WebhookSpec},
+    AppState,
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
 use tower::ServiceExt;
 
-async fn create_test_app() -> (Router, String) {
-    let api_base = "http://localhost:9999".to_string(); // Mock server
-    let api_key = "test-key".to_string();
-    let bind_addr = "127.0.0.1:0".parse::<SocketAddr>().unwrap();
-    
-    let state = crate::AppState {
-        api_base: api_base.clone(),
-        api_key,
-        client: Client::builder().build().unwrap(),
+/// Levanta un servidor HTTP local que hace de inBestia y devuelve su URL base
+async fn spawn_upstream(router: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+/// URL de un puerto local sin nadie escuchando: simula una réplica caída
+async fn dead_upstream() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    format!("http://{}", listener.local_addr().unwrap())
+}
+
+/// Upstream de prueba: `/api/v1/info` con cabeceras de caché, un eco de la request
+/// y endpoints que devuelven cuántas veces se les llamó
+fn mock_upstream() -> Router {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = move || {
+        let calls = calls.clone();
+        move || async move {
+            axum::Json(serde_json::json!({ "calls": calls.fetch_add(1, Ordering::SeqCst) + 1 }))
+        }
     };
 
-    let app = Router::new()
-        .route("/", axum::routing::get(|| async { "Ghost Dashboard API" }))
-        .route("/health", axum::routing::get(crate::forward_public))
-        .route("/api/v1/info", axum::routing::get(crate::forward_public))
-        .with_state(state);
+    let flaky_calls = Arc::new(AtomicUsize::new(0));
+
+    Router::new()
+        .route("/health", get(|| async { "API funcionando correctamente" }))
+        .route("/api/v1/providers/status", get(counter()))
+        .route(
+            "/api/v1/compare",
+            post(|| async { (StatusCode::SERVICE_UNAVAILABLE, "inBestia down") }),
+        )
+        .route(
+            // Falla las dos primeras llamadas y luego responde bien
+            "/api/v1/metrics/reconciliation",
+            get(move || async move {
+                match flaky_calls.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => StatusCode::BAD_GATEWAY.into_response(),
+                    _ => axum::Json(serde_json::json!({ "reconciled": true })).into_response(),
+                }
+            }),
+        )
+        .route("/api/v1/indicators", post(counter()))
+        .route(
+            "/api/v1/info",
+            get(|| async {
+                (
+                    [
+                        ("content-type", "application/json"),
+                        ("cache-control", "max-age=60"),
+                        ("retry-after", "5"),
+                        ("connection", "x-internal-hop"),
+                        ("x-internal-hop", "secret"),
+                    ],
+                    r#"{"name":"inBestia"}"#,
+                )
+            }),
+        )
+        .route(
+            "/api/v1/analyze",
+            post(|headers: HeaderMap, body: Bytes| async move {
+                let header = |name: &str| {
+                    headers
+                        .get(name)
+                        .and_then(|v| v.to_str().ok())
+                        .unwrap_or("")
+                        .to_string()
+                };
+                axum::Json(serde_json::json!({
+                    "content_type": header("content-type"),
+                    "authorization": header("authorization"),
+                    "cookie": header("cookie"),
+                    "traceparent": header("traceparent"),
+                    "request_id": header("x-request-id"),
+                    "body_len": body.len(),
+                }))
+                .into_response()
+            }),
+        )
+}
+
+const TEST_JWT_SECRET: &str = "test-secret-test-secret-test-secret";
+
+/// JWT de sesión válido para las apps de test (mismo secreto que `test_config`)
+fn test_session() -> String {
+    session_for("tester")
+}
+
+fn session_for(username: &str) -> String {
+    SessionManager::new(TEST_JWT_SECRET, 3600)
+        .issue(username)
+        .unwrap()
+        .0
+}
+
+fn test_config(api_base: &str) -> Config {
+    Config {
+        api_base_urls: vec![api_base.to_string()],
+        api_key: "test-key".into(),
+        max_request_size: 1024,
+        admin_token: Some("admin-secret".to_string()),
+        breaker_failure_threshold: 3,
+        retry_base_delay_ms: 1,
+        retry_max_delay_ms: 5,
+        auth_jwt_secret: Some(TEST_JWT_SECRET.to_string()),
+        quota_file: None,
+        ..Config::default()
+    }
+}
+
+async fn create_test_app() -> (Router, String) {
+    let api_base = spawn_upstream(mock_upstream()).await;
+    let config = test_config(&api_base);
+
+    (app(AppState::new(config).unwrap()), api_base)
+}
+
+fn get_request(uri: &str) -> Request<Body> {
+    Request::builder()
+        .uri(uri)
+        .header("authorization", format!("Bearer {}", test_session()))
+        .body(Body::empty())
+        .unwrap()
+}
+
+fn post_json(uri: &str, body: &str) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("authorization", format!("Bearer {}", test_session()))
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
 
-    (app, api_base)
+async fn json_body(response: axum::response::Response) -> serde_json::Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
 }
 
 #[tokio::test]
 async fn test_health_endpoint() {
     let (app, _) = create_test_app().await;
-    
+
     let response = app
         .oneshot(
             Request::builder()
@@ -47,7 +192,7 @@ async fn test_health_endpoint() {
 #[tokio::test]
 async fn test_info_endpoint() {
     let (app, _) = create_test_app().await;
-    
+
     let response = app
         .oneshot(
             Request::builder()
@@ -59,21 +204,3350 @@ async fn test_info_endpoint() {
         .unwrap();
 
     assert_eq!(response.status(), StatusCode::OK);
+    let headers = response.headers();
+    assert_eq!(headers["content-type"], "application/json");
+    assert_eq!(headers["cache-control"], "max-age=60");
+    assert_eq!(headers["retry-after"], "5");
+    // Hop-by-hop: ni `Connection` ni las cabeceras que nombra llegan al cliente
+    assert!(headers.get("connection").is_none());
+    assert!(headers.get("x-internal-hop").is_none());
 }
 
 #[tokio::test]
 async fn test_root_endpoint() {
     let (app, _) = create_test_app().await;
-    
+
+    let response = app
+        .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_proxy_forwards_client_content_type_and_injects_auth() {
+    let (app, _) = create_test_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/analyze")
+                .header("content-type", "application/json; charset=utf-8")
+                .header("cookie", format!("theme=dark; ghost_session={}", test_session()))
+                .body(Body::from(r#"{"symbol":"AAPL","timeframe":"1d"}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let echo = json_body(response).await;
+    assert_eq!(echo["content_type"], "application/json; charset=utf-8");
+    assert_eq!(echo["authorization"], "Bearer test-key");
+    assert_eq!(echo["cookie"], "");
+    assert_eq!(echo["body_len"], 34);
+}
+
+#[tokio::test]
+async fn test_proxy_rejects_oversized_body_with_content_length() {
+    let (app, _) = create_test_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/analyze")
+                .header("authorization", format!("Bearer {}", test_session()))
+                .header("content-type", "application/json")
+                .header("content-length", "2048")
+                .body(Body::from(vec![b'x'; 2048]))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body = json_body(response).await;
+    assert_eq!(body["error"]["code"], "PAYLOAD_TOO_LARGE");
+}
+
+#[tokio::test]
+async fn test_proxy_rejects_oversized_chunked_body() {
+    let (app, _) = create_test_app().await;
+
+    let chunks = futures_util::stream::iter(
+        (0..8).map(|_| Ok::<_, std::io::Error>(Bytes::from(vec![b'x'; 256]))),
+    );
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/analyze")
+                .header("authorization", format!("Bearer {}", test_session()))
+                .header("content-type", "application/json")
+                .body(Body::from_stream(chunks))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_cache_serves_repeated_get_from_cache() {
+    let (app, _) = create_test_app().await;
+
+    let first = app
+        .clone()
+        .oneshot(get_request("/api/v1/providers/status"))
+        .await
+        .unwrap();
+    assert_eq!(first.headers()["x-cache"], "MISS");
+    assert_eq!(json_body(first).await["calls"], 1);
+
+    let second = app
+        .oneshot(get_request("/api/v1/providers/status"))
+        .await
+        .unwrap();
+    assert_eq!(second.headers()["x-cache"], "HIT");
+    assert_eq!(json_body(second).await["calls"], 1);
+}
+
+#[tokio::test]
+async fn test_cache_key_normalizes_json_body() {
+    let (app, _) = create_test_app().await;
+
+    let first = app
+        .clone()
+        .oneshot(post_json(
+            "/api/v1/indicators",
+            r#"{"symbol":"AAPL","timeframe":"daily"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(first.headers()["x-cache"], "MISS");
+    json_body(first).await;
+
+    let reordered = app
+        .clone()
+        .oneshot(post_json(
+            "/api/v1/indicators",
+            r#"{ "timeframe": "daily", "symbol": "AAPL" }"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(reordered.headers()["x-cache"], "HIT");
+
+    let other_symbol = app
+        .oneshot(post_json(
+            "/api/v1/indicators",
+            r#"{"symbol":"MSFT","timeframe":"daily"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(other_symbol.headers()["x-cache"], "MISS");
+}
+
+#[tokio::test]
+async fn test_admin_cache_purge_requires_token() {
+    let (app, _) = create_test_app().await;
+
+    let cached = app
+        .clone()
+        .oneshot(get_request("/api/v1/providers/status"))
+        .await
+        .unwrap();
+    json_body(cached).await;
+
+    let anonymous = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri("/api/admin/cache")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+
+    let purge = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri("/api/admin/cache?prefix=GET%20/api/v1/providers")
+                .header("x-admin-token", "admin-secret")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(purge.status(), StatusCode::OK);
+    assert_eq!(json_body(purge).await["purged"], 1);
+
+    let refetched = app
+        .oneshot(get_request("/api/v1/providers/status"))
+        .await
+        .unwrap();
+    assert_eq!(refetched.headers()["x-cache"], "MISS");
+    assert_eq!(json_body(refetched).await["calls"], 2);
+}
+
+#[tokio::test]
+async fn test_idempotent_requests_are_retried() {
+    let (app, _) = create_test_app().await;
+
+    let response = app
+        .oneshot(get_request("/api/v1/metrics/reconciliation"))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(response).await["reconciled"], true);
+}
+
+#[tokio::test]
+async fn test_circuit_opens_after_consecutive_failures() {
+    let (app, _) = create_test_app().await;
+
+    // POST no es idempotente: cada request es un único intento y un fallo del breaker
+    for _ in 0..3 {
+        let response = app
+            .clone()
+            .oneshot(post_json(
+                "/api/v1/compare",
+                r#"{"symbols":["AAPL","MSFT"]}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    let rejected = app
+        .clone()
+        .oneshot(post_json(
+            "/api/v1/compare",
+            r#"{"symbols":["AAPL","MSFT"]}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(rejected.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(rejected.headers().contains_key("retry-after"));
+    assert_eq!(
+        json_body(rejected).await["error"]["code"],
+        "UPSTREAM_CIRCUIT_OPEN"
+    );
+
+    let health = json_body(app.oneshot(get_request("/api/health")).await.unwrap()).await;
+    assert_eq!(health["upstream_circuit"]["state"], "open");
+    assert_eq!(
+        health["upstream_circuit"]["routes"]["/api/v1/compare"]["state"],
+        "open"
+    );
+}
+
+#[tokio::test]
+async fn test_dropped_half_open_trial_releases_its_slot() {
+    let breakers = CircuitBreakers::new(1, std::time::Duration::from_millis(50), 1);
+    breakers.acquire("/r").unwrap().record(false);
+    assert!(breakers.acquire("/r").is_err());
+    tokio::time::sleep(std::time::Duration::from_millis(60)).await;
+
+    // La llamada de prueba se cancela (cliente desconectado, timeout externo...) sin
+    // registrar resultado
+    let trial = async {
+        let permit = breakers.acquire("/r").unwrap();
+        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+        permit.record(true);
+    };
+    assert!(
+        tokio::time::timeout(std::time::Duration::from_millis(20), trial)
+            .await
+            .is_err()
+    );
+
+    // Su plaza de half-open queda libre y el breaker se recupera
+    breakers.acquire("/r").unwrap().record(true);
+    assert_eq!(
+        breakers.snapshot()["/r"].state,
+        ghost_backend::resilience::CircuitState::Closed
+    );
+}
+
+#[tokio::test]
+async fn test_failover_and_ejection_of_dead_upstream() {
+    let live = spawn_upstream(mock_upstream()).await;
+    let dead = dead_upstream().await;
+    let config = Config {
+        api_base_urls: vec![dead.clone(), live.clone()],
+        upstream_unhealthy_threshold: 2,
+        ..test_config(&live)
+    };
+    let state = AppState::new(config).unwrap();
+    let app = app(state.clone());
+
+    // Round-robin empieza por la réplica caída: el reintento idempotente va a la viva
+    let response = app
+        .clone()
+        .oneshot(get_request("/api/v1/info"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    upstream::check_all(&state).await;
+
+    let health = json_body(app.oneshot(get_request("/api/health")).await.unwrap()).await;
+    assert_eq!(health["status"], "healthy");
+    let upstreams = health["upstreams"].as_array().unwrap();
+    assert_eq!(upstreams[0]["url"], dead.as_str());
+    assert_eq!(upstreams[0]["healthy"], false);
+    assert_eq!(upstreams[1]["healthy"], true);
+}
+
+// ===== Manifiesto de rutas =====
+
+#[test]
+fn test_embedded_route_manifest_is_valid() {
+    let routes = Config::default().routes;
+    assert!(routes.iter().any(|r| r.path == "/api/v1/analyze"));
+    assert!(validate_routes(&routes).is_ok());
+}
+
+#[test]
+fn test_route_manifest_reports_every_error() {
+    let routes = parse_manifest(
+        r#"
+        [[routes]]
+        path = "api/v1/info"
+        methods = ["GET"]
+
+        [[routes]]
+        path = "/api/health"
+        methods = ["FETCH"]
+        timeout_secs = 0
+
+        [[routes]]
+        path = "/api/v1/items/:id"
+        methods = ["GET"]
+
+        [[routes]]
+        path = "/api/v1/items/:key"
+        methods = ["get"]
+        "#,
+        "test.toml",
+    )
+    .unwrap();
+
+    let errors = validate_routes(&routes).unwrap_err();
+    assert_eq!(errors.len(), 5, "{:?}", errors);
+    assert!(errors[0].contains("must start with '/'"));
+    assert!(errors[1].contains("reserved"));
+    assert!(errors[2].contains("unsupported method 'FETCH'"));
+    assert!(errors[3].contains("timeout_secs"));
+    assert!(errors[4].contains("conflicts with route #3"));
+
+    let mut config = test_config("http://localhost:8080");
+    config.routes = routes;
+    let message = config.validate().unwrap_err().to_string();
+    assert!(message.contains("5 errors"), "{}", message);
+}
+
+#[test]
+fn test_route_manifest_rejects_unknown_fields() {
+    let error = parse_manifest(
+        r#"
+        [[routes]]
+        path = "/api/v1/info"
+        methods = ["GET"]
+        cache_ttl = 10
+        "#,
+        "test.toml",
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("cache_ttl"));
+}
+
+#[tokio::test]
+async fn test_gateway_serves_only_manifest_routes_with_their_settings() {
+    let api_base = spawn_upstream(mock_upstream()).await;
+    let mut config = test_config(&api_base);
+    config.routes = parse_manifest(
+        r#"{"routes": [{"path": "/api/v1/analyze", "methods": ["POST"], "max_body_bytes": 16}]}"#,
+        "routes.json",
+    )
+    .unwrap();
+    let app = app(AppState::new(config).unwrap());
+
+    // Sin `auth` no se inyecta la API key
+    let response = app
+        .clone()
+        .oneshot(post_json("/api/v1/analyze", r#"{"a":1}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let echo = json_body(response).await;
+    assert_eq!(echo["authorization"], "");
+
+    // Límite de body propio de la ruta, menor que MAX_REQUEST_SIZE
+    let response = app
+        .clone()
+        .oneshot(post_json("/api/v1/analyze", r#"{"symbol":"AAPL","x":1}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // Las rutas que no están en el manifiesto no existen
+    let response = app.oneshot(get_request("/api/v1/info")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+// ===== Sesiones del gateway =====
+
+/// Escribe un fichero de usuarios temporal con `user` / `password`
+fn users_file(user: &str, password: &str) -> String {
+    let path = std::env::temp_dir().join(format!("ghost-users-{}.toml", uuid::Uuid::new_v4()));
+    std::fs::write(
+        &path,
+        format!(
+            "[[users]]\nusername = \"{}\"\npassword_hash = \"{}\"\n",
+            user,
+            hash_password(password).unwrap()
+        ),
+    )
+    .unwrap();
+    path.display().to_string()
+}
+
+#[tokio::test]
+async fn test_auth_routes_require_a_session() {
+    let (app, _) = create_test_app().await;
+
     let response = app
+        .clone()
         .oneshot(
             Request::builder()
-                .uri("/")
+                .uri("/api/v1/providers/status")
+                .header("authorization", "Bearer not-a-jwt")
                 .body(Body::empty())
                 .unwrap(),
         )
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["www-authenticate"], "Bearer");
+    let body = json_body(response).await;
+    assert_eq!(body["error"]["code"], "UNAUTHORIZED");
+    assert!(body["error"]["trace_id"].is_string());
+
+    // Las rutas públicas del manifiesto no piden sesión
+    let response = app
+        .oneshot(Request::builder().uri("/api/v1/info").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_login_session_cookie_and_logout() {
+    let api_base = spawn_upstream(mock_upstream()).await;
+    let mut config = test_config(&api_base);
+    config.users_file = Some(users_file("ana", "correct horse"));
+    let app = app(AppState::new(config).unwrap());
+
+    let login = |password: &str| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/auth/login")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({"username": "ana", "password": password}).to_string(),
+            ))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(login("wrong")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(json_body(response).await["error"]["code"], "INVALID_CREDENTIALS");
+
+    let response = app.clone().oneshot(login("correct horse")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let set_cookie = response.headers()["set-cookie"].to_str().unwrap().to_string();
+    assert!(set_cookie.contains("HttpOnly"));
+    assert!(set_cookie.contains("SameSite=Strict"));
+    let cookie = set_cookie.split(';').next().unwrap().to_string();
+    assert_eq!(json_body(response).await["username"], "ana");
+
+    let with_cookie = |uri: &str, method: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("cookie", &cookie)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(with_cookie("/api/auth/me", "GET"))
+        .await
+        .unwrap();
+    assert_eq!(json_body(response).await["username"], "ana");
+
+    let response = app
+        .clone()
+        .oneshot(with_cookie("/api/v1/providers/status", "GET"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(with_cookie("/api/auth/logout", "POST"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(response.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .contains("Max-Age=0"));
+
+    // El token revocado deja de servir aunque no haya expirado
+    let response = app
+        .oneshot(with_cookie("/api/auth/me", "GET"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_login_attempts_are_limited_per_ip_and_username() {
+    let api_base = spawn_upstream(mock_upstream()).await;
+    let mut config = test_config(&api_base);
+    config.users_file = Some(users_file("ana", "correct horse"));
+    config.rate_limit_classes.insert(
+        "login".to_string(),
+        RateLimitRule {
+            requests: 2,
+            period_secs: 60,
+        },
+    );
+    config.rate_limit_classes.insert(
+        "login_ip".to_string(),
+        RateLimitRule {
+            requests: 4,
+            period_secs: 60,
+        },
+    );
+    let app = app(AppState::new(config).unwrap());
+
+    let login = |ip: [u8; 4], username: &str, password: &str| {
+        let mut request = Request::builder()
+            .method("POST")
+            .uri("/api/auth/login")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({"username": username, "password": password}).to_string(),
+            ))
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(axum::extract::ConnectInfo(std::net::SocketAddr::from((ip, 4000))));
+        request
+    };
+
+    for _ in 0..2 {
+        let response = app.clone().oneshot(login([10, 0, 0, 1], "ana", "wrong")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+    // Agotados los intentos, ni la contraseña correcta pasa (ni cambiando mayúsculas)
+    let response = app.clone().oneshot(login([10, 0, 0, 1], "Ana", "correct horse")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key("retry-after"));
+    assert_eq!(json_body(response).await["error"]["code"], "RATE_LIMITED");
+
+    // Otro usuario desde la misma IP y el mismo usuario desde otra IP siguen intentándolo
+    let response = app.clone().oneshot(login([10, 0, 0, 1], "luis", "wrong")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(login([10, 0, 0, 2], "ana", "correct horse")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Pero una IP no puede probar usuarios sin límite
+    let response = app.oneshot(login([10, 0, 0, 1], "marta", "wrong")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(json_body(response).await["error"]["code"], "RATE_LIMITED");
+}
+
+#[tokio::test]
+async fn test_login_limits_apply_without_rate_limit_configuration() {
+    let api_base = spawn_upstream(mock_upstream()).await;
+    let mut config = test_config(&api_base);
+    config.users_file = Some(users_file("ana", "correct horse"));
+    // RATE_LIMITS sin las clases de login y el rate limiting general apagado
+    config.rate_limit_enabled = false;
+    config.rate_limit_classes = [("default".to_string(), "120/60".parse().unwrap())].into();
+    let app = app(AppState::new(config).unwrap());
+
+    let login = || {
+        Request::builder()
+            .method("POST")
+            .uri("/api/auth/login")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"username":"ana","password":"wrong"}"#))
+            .unwrap()
+    };
+    for _ in 0..5 {
+        let response = app.clone().oneshot(login()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+    let response = app.oneshot(login()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["ratelimit-limit"], "5");
+}
+
+// ===== Rate limiting y cuotas =====
+
+fn post_as(uri: &str, username: &str) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("authorization", format!("Bearer {}", session_for(username)))
+        .header("content-type", "application/json")
+        .body(Body::from(r#"{"symbol":"AAPL","timeframe":"1d"}"#))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_rate_limit_per_user_and_class() {
+    let api_base = spawn_upstream(mock_upstream()).await;
+    let mut config = test_config(&api_base);
+    config.rate_limit_classes.insert(
+        "analysis".to_string(),
+        RateLimitRule {
+            requests: 2,
+            period_secs: 60,
+        },
+    );
+    let app = app(AppState::new(config).unwrap());
+
+    for remaining in ["1", "0"] {
+        let response = app
+            .clone()
+            .oneshot(post_as("/api/v1/indicators", "ana"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["ratelimit-limit"], "2");
+        assert_eq!(response.headers()["ratelimit-remaining"], remaining);
+        assert_eq!(response.headers()["ratelimit-policy"], "2;w=60");
+    }
+
+    let response = app
+        .clone()
+        .oneshot(post_as("/api/v1/indicators", "ana"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+    assert!((1..=30).contains(&retry_after));
+    assert_eq!(json_body(response).await["error"]["code"], "RATE_LIMITED");
 
+    // Otro usuario y otra clase de ruta tienen sus propios buckets
+    let response = app
+        .clone()
+        .oneshot(post_as("/api/v1/indicators", "luis"))
+        .await
+        .unwrap();
     assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .oneshot(get_request("/api/v1/providers/status"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[test]
+fn test_rate_limiter_evicts_least_recently_used_buckets() {
+    let limiter = RateLimiter::new(
+        [(
+            "analysis".to_string(),
+            RateLimitRule {
+                requests: 1,
+                period_secs: 3600,
+            },
+        )]
+        .into(),
+    );
+
+    // Más clientes activos que el máximo de buckets (10.000), todos con el bucket vacío
+    for i in 0..11_000 {
+        assert!(limiter.check(&format!("user:{}", i), "analysis", 1).unwrap().allowed);
+    }
+    // Se descartaron los más antiguos; los recientes conservan su límite
+    assert!(limiter.peek("user:0", "analysis").unwrap().allowed);
+    assert!(!limiter.peek("user:10999", "analysis").unwrap().allowed);
+}
+
+#[tokio::test]
+async fn test_daily_quota_survives_restart_and_is_reported() {
+    let api_base = spawn_upstream(mock_upstream()).await;
+    let quota_file = std::env::temp_dir().join(format!("ghost-quota-{}.json", uuid::Uuid::new_v4()));
+    let mut config = test_config(&api_base);
+    config.quota_file = Some(quota_file.display().to_string());
+    config.daily_quotas.insert("analysis".to_string(), 1);
+
+    let state = AppState::new(config.clone()).unwrap();
+    let response = app(state.clone())
+        .oneshot(post_as("/api/v1/indicators", "ana"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    state.quotas.flush().unwrap();
+
+    // Tras "reiniciar" el gateway la cuota sigue agotada
+    let app = app(AppState::new(config).unwrap());
+    let response = app
+        .clone()
+        .oneshot(post_as("/api/v1/indicators", "ana"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key("retry-after"));
+    assert_eq!(json_body(response).await["error"]["code"], "QUOTA_EXCEEDED");
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/me/usage")
+                .header("authorization", format!("Bearer {}", session_for("ana")))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let usage = json_body(response).await;
+    assert_eq!(usage["identity"], "user:ana");
+    assert_eq!(usage["classes"]["analysis"]["used_today"], 1);
+    assert_eq!(usage["classes"]["analysis"]["remaining_today"], 0);
+    assert_eq!(usage["classes"]["default"]["daily_quota"], serde_json::Value::Null);
+
+    std::fs::remove_file(quota_file).ok();
+}
+
+// ===== Métricas Prometheus =====
+
+#[tokio::test]
+async fn test_prometheus_metrics_endpoint() {
+    let (app, _) = create_test_app().await;
+
+    for _ in 0..2 {
+        let response = app
+            .clone()
+            .oneshot(get_request("/api/v1/providers/status"))
+            .await
+            .unwrap();
+        to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    }
+    let response = app
+        .clone()
+        .oneshot(post_json("/api/v1/compare", r#"{"symbols":["AAPL","MSFT"]}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let response = app.oneshot(get_request("/metrics")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain; version=0.0.4"));
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+
+    for expected in [
+        r#"gateway_http_requests_total{method="GET",route="/api/v1/providers/status",status="200"} 2"#,
+        r#"gateway_http_requests_total{method="POST",route="/api/v1/compare",status="503"} 1"#,
+        r#"gateway_upstream_errors_total{kind="bad_status",route="/api/v1/compare"} 1"#,
+        r#"gateway_http_requests_in_flight{route="/api/v1/providers/status"} 0"#,
+        "gateway_cache_hits_total 1",
+        "gateway_cache_misses_total 1",
+    ] {
+        assert!(text.contains(expected), "missing `{}` in:\n{}", expected, text);
+    }
+    assert!(text.contains("gateway_upstream_request_duration_seconds_bucket"));
+    assert!(text.contains("gateway_http_request_duration_seconds_bucket"));
+    assert!(text.contains("gateway_http_response_body_bytes_count"));
+    assert!(text.contains("gateway_upstream_healthy{upstream="));
+}
+
+// ===== Métricas del proceso =====
+
+#[tokio::test]
+async fn test_system_metrics_report_process_and_host() {
+    let (app, _) = create_test_app().await;
+
+    let response = app.oneshot(get_request("/api/metrics/system")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let gateway = &json["gateway_metrics"];
+
+    // La propia request cuenta como conexión activa
+    assert_eq!(gateway["active_connections"], 1);
+    assert!(gateway["tokio"]["workers"].as_u64().unwrap() >= 1);
+    assert!(gateway["tokio"]["alive_tasks"].is_u64());
+    // Sin muestreo en marcha no hay dato de CPU todavía
+    assert!(gateway["cpu"]["usage_percent"].is_null());
+
+    if cfg!(target_os = "linux") {
+        assert!(gateway["memory"]["used_mb"].as_u64().unwrap() > 0);
+        let total = gateway["memory"]["host_total_mb"].as_u64().unwrap();
+        assert!(gateway["memory"]["available_mb"].as_u64().unwrap() <= total);
+        assert!(gateway["open_fds"].as_u64().unwrap() > 0);
+    }
+}
+
+// ===== Historial de health checks =====
+
+#[tokio::test]
+async fn test_health_history_and_instant_health_from_latest_sample() {
+    let live = spawn_upstream(mock_upstream()).await;
+    let dead = dead_upstream().await;
+    let config = Config {
+        api_base_urls: vec![live.clone(), dead.clone()],
+        upstream_health_history: 3,
+        ..test_config(&live)
+    };
+    let state = AppState::new(config).unwrap();
+    let app = app(state.clone());
+
+    // Sin ningún health check todavía no hay nada que contar
+    let response = app.clone().oneshot(get_request("/api/health")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(json_body(response).await["status"], "unknown");
+
+    for _ in 0..4 {
+        upstream::check_all(&state).await;
+    }
+
+    let response = app.clone().oneshot(get_request("/api/health")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let health = json_body(response).await;
+    assert_eq!(health["status"], "healthy");
+    assert!(health["external_api"]["response_time_ms"].is_u64());
+    assert!(health["external_api"]["checked_at"].is_string());
+
+    let history = json_body(
+        app.oneshot(get_request("/api/health/history?limit=2"))
+            .await
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(history["capacity"], 3);
+    let upstreams = history["upstreams"].as_array().unwrap();
+
+    // El buffer circular guarda sólo las 3 últimas muestras
+    let live_stats = &upstreams[0]["windows"]["1h"];
+    assert_eq!(upstreams[0]["url"], live.as_str());
+    assert_eq!(live_stats["samples"], 3);
+    assert_eq!(live_stats["uptime_percent"], 100.0);
+    assert!(live_stats["p50_ms"].as_f64().unwrap() <= live_stats["p95_ms"].as_f64().unwrap());
+    assert_eq!(upstreams[0]["samples"].as_array().unwrap().len(), 2);
+    assert_eq!(upstreams[0]["samples"][1]["ok"], true);
+
+    let dead_stats = &upstreams[1]["windows"]["24h"];
+    assert_eq!(dead_stats["uptime_percent"], 0.0);
+    assert!(dead_stats["p95_ms"].is_null());
+    assert!(upstreams[1]["samples"][0]["error"].is_string());
+    assert!(upstreams[1]["samples"][0]["http_status"].is_null());
+}
+
+// ===== Trace context =====
+
+#[tokio::test]
+async fn test_trace_context_is_forwarded_and_echoed() {
+    let (app, _) = create_test_app().await;
+    let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+    let mut request = post_json("/api/v1/analyze", r#"{"symbol":"AAPL","timeframe":"1d"}"#);
+    request.headers_mut().insert(
+        "traceparent",
+        format!("00-{}-00f067aa0ba902b7-01", trace_id).parse().unwrap(),
+    );
+    request
+        .headers_mut()
+        .insert("x-request-id", "front-42".parse().unwrap());
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-request-id"], "front-42");
+    let echoed = response.headers()["traceparent"].to_str().unwrap().to_string();
+
+    // inBestia recibe el mismo trace_id con el tramo del gateway como padre
+    let upstream = json_body(response).await;
+    assert_eq!(upstream["request_id"], "front-42");
+    let forwarded = upstream["traceparent"].as_str().unwrap();
+    assert_eq!(forwarded, echoed);
+    let fields: Vec<&str> = forwarded.split('-').collect();
+    assert_eq!(fields[0], "00");
+    assert_eq!(fields[1], trace_id);
+    assert_ne!(fields[2], "00f067aa0ba902b7");
+    assert_eq!(fields[3], "01");
+}
+
+#[tokio::test]
+async fn test_trace_context_is_generated_when_missing_or_invalid() {
+    let (app, _) = create_test_app().await;
+
+    let mut request = get_request("/api/health");
+    request.headers_mut().insert(
+        "traceparent",
+        "00-00000000000000000000000000000000-00f067aa0ba902b7-01"
+            .parse()
+            .unwrap(),
+    );
+    let response = app.clone().oneshot(request).await.unwrap();
+    let traceparent = response.headers()["traceparent"].to_str().unwrap().to_string();
+    let request_id = response.headers()["x-request-id"].to_str().unwrap().to_string();
+    let body = json_body(response).await;
+
+    let trace_id = traceparent.split('-').nth(1).unwrap();
+    assert_eq!(trace_id.len(), 32);
+    assert_ne!(trace_id, "00000000000000000000000000000000");
+    assert!(!request_id.is_empty());
+    // El trace_id del body es el mismo que viaja en las cabeceras
+    assert_eq!(body["trace_id"], trace_id);
+
+    // Los errores del gateway también llevan el trace_id de la request
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/auth/me")
+                .header("x-request-id", "needs-login")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["x-request-id"], "needs-login");
+    let traceparent = response.headers()["traceparent"].to_str().unwrap().to_string();
+    let body = json_body(response).await;
+    assert_eq!(body["error"]["trace_id"], traceparent.split('-').nth(1).unwrap());
+}
+
+// ===== Logging de bodies =====
+
+#[test]
+fn test_redact_paths_hide_sensitive_fields() {
+    let mut value = serde_json::json!({
+        "user": { "password": "hunter2", "name": "ana" },
+        "accounts": [
+            { "iban": "ES91...", "balance": 10 },
+            { "iban": "ES12...", "balance": 20 }
+        ],
+        "items": ["a", "b"],
+        "nested": { "deep": { "token": "abc" } },
+        "token": "top"
+    });
+    for path in ["$.user.password", "$.accounts[*].iban", "$.items[1]", "$..token"] {
+        path.parse::<RedactPath>().unwrap().apply(&mut value);
+    }
+
+    assert_eq!(value["user"]["password"], "[REDACTED]");
+    assert_eq!(value["user"]["name"], "ana");
+    assert_eq!(value["accounts"][0]["iban"], "[REDACTED]");
+    assert_eq!(value["accounts"][1]["iban"], "[REDACTED]");
+    assert_eq!(value["accounts"][1]["balance"], 20);
+    assert_eq!(value["items"], serde_json::json!(["a", "[REDACTED]"]));
+    assert_eq!(value["nested"]["deep"]["token"], "[REDACTED]");
+    assert_eq!(value["token"], "[REDACTED]");
+
+    for invalid in ["password", "$", "$.", "$..", "$.items[x]", "$.items[0"] {
+        assert!(invalid.parse::<RedactPath>().is_err(), "{} should be rejected", invalid);
+    }
+}
+
+#[test]
+fn test_body_log_policy_redacts_truncates_and_caps() {
+    let config = Config {
+        log_body_truncate_bytes: 16,
+        log_body_max_bytes: 64,
+        ..Config::default()
+    };
+    let policy = BodyLogPolicy::from_config(&config);
+
+    let body = br#"{"symbol":"AAPL","api_key":"k-123"}"#;
+    let full = policy.render(body, body.len(), Some("application/json"), BodyLogMode::Full);
+    assert_eq!(full, r#"{"api_key":"[REDACTED]","symbol":"AAPL"}"#);
+
+    // En modo truncated se recorta el JSON ya redactado
+    let truncated = policy.render(body, body.len(), Some("application/json"), BodyLogMode::Truncated);
+    assert!(truncated.starts_with(r#"{"api_key":"[RED"#));
+    assert!(truncated.ends_with(&format!("… ({} bytes)", body.len())));
+    assert!(!truncated.contains("k-123"));
+
+    // Un JSON mayor que LOG_BODY_MAX_BYTES no se puede redactar: no se registra
+    let huge = format!(r#"{{"password":"{}"}}"#, "x".repeat(100));
+    let capped = policy.render(&huge.as_bytes()[..64], huge.len(), None, BodyLogMode::Full);
+    assert!(!capped.contains("xxx"));
+    assert!(capped.contains("exceed LOG_BODY_MAX_BYTES"));
+
+    assert!(policy
+        .render(b"password=x", 10, Some("application/x-www-form-urlencoded"), BodyLogMode::Full)
+        .starts_with("<omitted"));
+    assert_eq!(
+        policy.render(&[0xff, 0x00], 2, Some("application/octet-stream"), BodyLogMode::Full),
+        "<binary: 2 bytes>"
+    );
+    assert_eq!(policy.render(b"plain text", 10, Some("text/plain"), BodyLogMode::Full), "plain text");
+}
+
+#[test]
+fn test_body_log_config_is_validated() {
+    let base = test_config("http://localhost:8080");
+    base.validate().unwrap();
+
+    let bad_path = Config {
+        log_redact_paths: vec!["password".to_string()],
+        ..base.clone()
+    };
+    assert!(bad_path.validate().unwrap_err().to_string().contains("LOG_REDACT_PATHS"));
+
+    let bad_rate = Config {
+        log_body_sample_rate: 1.5,
+        ..base.clone()
+    };
+    assert!(bad_rate.validate().is_err());
+
+    // Una ruta del manifiesto puede desactivar el logging por su cuenta
+    let routes = parse_manifest(
+        r#"
+        [[routes]]
+        path = "/api/v1/historical"
+        methods = ["POST"]
+        log_bodies = "off"
+        "#,
+        "routes.toml",
+    )
+    .unwrap();
+    let policy = BodyLogPolicy::from_config(&Config {
+        log_bodies: BodyLogMode::Full,
+        ..base
+    });
+    assert_eq!(routes[0].log_bodies, Some(BodyLogMode::Off));
+    assert_eq!(policy.mode_for(&routes[0]), BodyLogMode::Off);
+}
+
+// ===== Validación de bodies =====
+
+#[tokio::test]
+async fn test_request_bodies_are_validated_against_shared_schemas() {
+    let (app, _) = create_test_app().await;
+
+    // Nombres canónicos, de análisis y alias de timeframe son válidos
+    for timeframe in ["1d", "daily", "minute1", "4hours", "1M"] {
+        let body = format!(r#"{{"symbol":"BRK.B","timeframe":"{}"}}"#, timeframe);
+        let response = app
+            .clone()
+            .oneshot(post_json("/api/v1/analyze", &body))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "timeframe {}", timeframe);
+    }
+
+    // Errores por campo: timeframe desconocido y símbolo ausente
+    let response = app
+        .clone()
+        .oneshot(post_json("/api/v1/analyze", r#"{"timeframe":"2d"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = json_body(response).await;
+    assert_eq!(body["error"]["code"], "VALIDATION_FAILED");
+    assert!(body["error"]["trace_id"].is_string());
+    let fields: Vec<&str> = body["error"]["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["field"].as_str().unwrap())
+        .collect();
+    assert!(fields.contains(&"symbol"));
+    assert!(fields.contains(&"timeframe"));
+
+    // Elementos de arrays y rangos numéricos
+    let response = app
+        .clone()
+        .oneshot(post_json(
+            "/api/v1/compare",
+            r#"{"symbols":["AAPL","NOT A TICKER"],"timeframe":"weekly"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = json_body(response).await;
+    assert_eq!(body["error"]["fields"][0]["field"], "symbols.1");
+
+    let response = app
+        .clone()
+        .oneshot(post_json(
+            "/api/v1/historical",
+            r#"{"symbol":"AAPL","timeframe":"1h","limit":0}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = json_body(response).await;
+    assert_eq!(body["error"]["fields"][0]["field"], "limit");
+
+    // JSON mal formado y bodies que no son JSON
+    let response = app
+        .clone()
+        .oneshot(post_json("/api/v1/indicators", "{"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = json_body(response).await;
+    assert_eq!(body["error"]["fields"][0]["field"], "body");
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/analyze")
+                .header("authorization", format!("Bearer {}", test_session()))
+                .header("content-type", "text/plain")
+                .body(Body::from("AAPL"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    // Los esquemas se publican con las rutas que los usan
+    let response = app.oneshot(get_request("/api/schemas")).await.unwrap();
+    let body = json_body(response).await;
+    assert_eq!(body["schemas"]["AnalysisRequest"]["routes"][0], "/api/v1/analyze");
+    assert!(body["schemas"]["CompareRequest"]["schema"]["properties"]["symbols"].is_object());
+}
+
+#[test]
+fn test_manifest_rejects_unknown_schema() {
+    let routes = parse_manifest(
+        r#"
+        [[routes]]
+        path = "/api/v1/analyze"
+        methods = ["POST"]
+        schema = "AnalyzeRequest"
+        "#,
+        "routes.toml",
+    )
+    .unwrap();
+    let errors = validate_routes(&routes).unwrap_err();
+    assert!(errors[0].contains("unknown schema 'AnalyzeRequest'"));
+}
+
+// ===== Cliente tipado de inBestia =====
+
+/// inBestia con respuestas con el formato real de cada endpoint
+fn typed_upstream() -> Router {
+    Router::new()
+        .route(
+            "/api/v1/analyze",
+            post(|headers: HeaderMap, axum::Json(request): axum::Json<serde_json::Value>| async move {
+                if request["symbol"] != "AAPL" {
+                    let body = serde_json::json!({ "message": "Symbol not found", "suggestions": ["AAPL"] });
+                    return (StatusCode::BAD_REQUEST, axum::Json(body)).into_response();
+                }
+                axum::Json(serde_json::json!({
+                    "symbol": "AAPL",
+                    "timeframe": request["timeframe"],
+                    "score": 72.5,
+                    "technical_score": 70.0,
+                    "fundamental_score": null,
+                    "trend_score": 80.0,
+                    "momentum_score": 65.0,
+                    "volatility_score": 40.0,
+                    "volume_score": 55.0,
+                    "suggested_operation": "BUY",
+                    // El cliente propaga el contexto de trazas
+                    "explanation": headers.get("traceparent").and_then(|v| v.to_str().ok()).unwrap_or(""),
+                }))
+                .into_response()
+            }),
+        )
+        .route(
+            "/api/v1/metrics/system",
+            get(|| async {
+                axum::Json(serde_json::json!({
+                    "cpu_usage": 25.5,
+                    "memory_usage": 45.2,
+                    "database_connections": 10,
+                    "cache_hit_ratio": 0.85,
+                    "active_requests": 5
+                }))
+            }),
+        )
+        .route(
+            "/api/v1/providers/status",
+            get(|| async { axum::Json(serde_json::json!({ "unexpected": true })) }),
+        )
+        .route(
+            "/api/v1/timeframes/config",
+            get(|| async {
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+                "late"
+            }),
+        )
+}
+
+#[tokio::test]
+async fn test_inbestia_client_returns_typed_results_and_errors() {
+    use ghost_backend::{inbestia::InbestiaError, trace::TraceContext};
+    use ghost_shared::AnalysisRequest;
+
+    let api_base = spawn_upstream(typed_upstream()).await;
+    let state = AppState::new(test_config(&api_base)).unwrap();
+    let trace = TraceContext::from_headers(&HeaderMap::new());
+    let client = state.inbestia().with_trace(&trace);
+
+    let request = AnalysisRequest {
+        symbol: "AAPL".to_string(),
+        timeframe: "daily".to_string(),
+        include_fundamental: false,
+    };
+    let analysis = client.analyze(&request).await.unwrap();
+    assert_eq!(analysis.timeframe, "daily");
+    assert_eq!(analysis.suggested_operation, "BUY");
+    assert!(analysis.explanation.contains(&trace.trace_id));
+
+    let metrics = client.system_metrics().await.unwrap();
+    assert_eq!(metrics.database_connections, 10);
+
+    // 400 de inBestia con sugerencias de símbolo
+    let unknown = AnalysisRequest {
+        symbol: "APPL".to_string(),
+        ..request
+    };
+    let error = client.analyze(&unknown).await.unwrap_err();
+    assert!(matches!(error, InbestiaError::Status { status, .. } if status == StatusCode::BAD_REQUEST));
+    assert_eq!(error.symbol_not_found().unwrap().suggestions, vec!["AAPL"]);
+    assert_eq!(error.to_response("t").status(), StatusCode::BAD_REQUEST);
+
+    // Formato inesperado, timeout y réplica caída
+    let error = client.providers_status().await.unwrap_err();
+    assert!(matches!(error, InbestiaError::Decode(_)));
+    assert_eq!(error.to_response("t").status(), StatusCode::BAD_GATEWAY);
+
+    let error = client
+        .clone()
+        .with_timeout(std::time::Duration::from_millis(50))
+        .timeframes_config()
+        .await
+        .unwrap_err();
+    assert!(matches!(error, InbestiaError::Timeout));
+    assert_eq!(error.to_response("t").status(), StatusCode::GATEWAY_TIMEOUT);
+
+    let dead = AppState::new(test_config(&dead_upstream().await)).unwrap();
+    let error = dead.inbestia().reconciliation().await.unwrap_err();
+    assert!(matches!(error, InbestiaError::Connect(_)));
+}
+
+// ===== Análisis por lotes =====
+
+#[tokio::test]
+async fn test_batch_analyze_aggregates_partial_failures() {
+    let api_base = spawn_upstream(typed_upstream()).await;
+    let app = app(AppState::new(test_config(&api_base)).unwrap());
+
+    let response = app
+        .clone()
+        .oneshot(post_json(
+            "/api/batch/analyze",
+            r#"{"symbols":["AAPL","APPL"],"timeframes":["1d","hourly"]}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_body(response).await;
+    assert_eq!(body["total"], 4);
+    assert_eq!(body["succeeded"], 2);
+    assert_eq!(body["failed"], 2);
+
+    // Orden pedido: símbolo × timeframe, con el timeframe traducido al de análisis
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items[0]["symbol"], "AAPL");
+    assert_eq!(items[0]["timeframe"], "1d");
+    assert_eq!(items[0]["status"], "ok");
+    assert_eq!(items[0]["result"]["timeframe"], "daily");
+    assert_eq!(items[1]["result"]["timeframe"], "hour1");
+    assert_eq!(items[2]["status"], "error");
+    assert_eq!(items[2]["error"]["code"], "SYMBOL_NOT_FOUND");
+    assert_eq!(items[2]["error"]["suggestions"][0], "AAPL");
+
+    // Lotes demasiado grandes y bodies inválidos: 422
+    let symbols: Vec<String> = (0..26).map(|i| format!("S{}", i)).collect();
+    let body = serde_json::json!({ "symbols": symbols, "timeframes": ["1m", "5m", "1h", "1d"] });
+    let response = app
+        .clone()
+        .oneshot(post_json("/api/batch/analyze", &body.to_string()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app
+        .oneshot(post_json("/api/batch/analyze", r#"{"symbols":[],"timeframes":["2d"]}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_batch_analyze_streams_ndjson() {
+    let api_base = spawn_upstream(typed_upstream()).await;
+    let app = app(AppState::new(test_config(&api_base)).unwrap());
+
+    let mut request = post_json(
+        "/api/batch/analyze",
+        r#"{"symbols":["AAPL","MSFT","APPL"],"timeframes":["1w"]}"#,
+    );
+    request
+        .headers_mut()
+        .insert("accept", "application/x-ndjson".parse().unwrap());
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let lines: Vec<serde_json::Value> = std::str::from_utf8(&bytes)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines.iter().filter(|item| item["status"] == "ok").count(), 1);
+    assert!(lines.iter().all(|item| item["timeframe"] == "1w"));
+}
+
+#[tokio::test]
+async fn test_batch_analyze_requires_session() {
+    let (app, _) = create_test_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/batch/analyze")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"symbols":["AAPL"],"timeframes":["1d"]}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_largest_batch_fits_the_default_rate_limits() {
+    let api_base = spawn_upstream(typed_upstream()).await;
+    let config = Config {
+        max_request_size: Config::default().max_request_size,
+        ..test_config(&api_base)
+    };
+    config.validate().unwrap();
+    let max_items = config.batch_max_items;
+    let app = app(AppState::new(config).unwrap());
+
+    let symbols: Vec<String> = (0..max_items).map(|i| format!("S{}", i)).collect();
+    let body = serde_json::json!({ "symbols": symbols, "timeframes": ["1d"] });
+    let response = app
+        .oneshot(post_json("/api/batch/analyze", &body.to_string()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["ratelimit-remaining"], "0");
+    assert_eq!(json_body(response).await["total"], max_items);
+}
+
+#[tokio::test]
+async fn test_batch_and_confluence_charge_each_analysis() {
+    let api_base = spawn_upstream(typed_upstream()).await;
+    let mut config = test_config(&api_base);
+    config.rate_limit_classes.insert(
+        "analysis".to_string(),
+        RateLimitRule {
+            requests: 8,
+            period_secs: 60,
+        },
+    );
+    // Un lote mayor que el bucket no pasaría nunca: la configuración no lo admite
+    assert!(config.validate().unwrap_err().to_string().contains("BATCH_MAX_ITEMS (20)"));
+    config.batch_max_items = 8;
+    config.validate().unwrap();
+    let app = app(AppState::new(config.clone()).unwrap());
+
+    // 2 símbolos × 2 timeframes = 4 análisis
+    let response = app
+        .clone()
+        .oneshot(post_json(
+            "/api/batch/analyze",
+            r#"{"symbols":["AAPL","MSFT"],"timeframes":["1d","1w"]}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["ratelimit-remaining"], "4");
+
+    // La confluencia pide 5 análisis y sólo quedan 4
+    let response = app
+        .clone()
+        .oneshot(get_request("/api/confluence/AAPL"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key("retry-after"));
+    assert_eq!(json_body(response).await["error"]["code"], "RATE_LIMITED");
+
+    // La cuota diaria también se cobra por análisis
+    config.daily_quotas.insert("analysis".to_string(), 5);
+    let app = ghost_backend::app(AppState::new(config).unwrap());
+    let batch = r#"{"symbols":["AAPL","MSFT"],"timeframes":["1d","1w"]}"#;
+    let response = app.clone().oneshot(post_json("/api/batch/analyze", batch)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(post_json("/api/batch/analyze", batch)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(json_body(response).await["error"]["code"], "QUOTA_EXCEEDED");
+
+    let response = app.oneshot(get_request("/api/me/usage")).await.unwrap();
+    assert_eq!(json_body(response).await["classes"]["analysis"]["used_today"], 4);
+}
+
+// ===== Confluencia multi-temporal =====
+
+/// inBestia bajista en intradía (30) y alcista en diario y semanal (90), con todos
+/// los timeframes con el mismo peso en su configuración
+fn confluence_upstream() -> Router {
+    Router::new()
+        .route(
+            "/api/v1/analyze",
+            post(|axum::Json(request): axum::Json<serde_json::Value>| async move {
+                if request["symbol"] != "AAPL" {
+                    let body = serde_json::json!({ "message": "Symbol not found", "suggestions": ["AAPL"] });
+                    return (StatusCode::BAD_REQUEST, axum::Json(body)).into_response();
+                }
+                let score = match request["timeframe"].as_str() {
+                    Some("daily") | Some("weekly") => 90.0,
+                    _ => 30.0,
+                };
+                axum::Json(serde_json::json!({
+                    "symbol": "AAPL",
+                    "timeframe": request["timeframe"],
+                    "score": score,
+                    "technical_score": score,
+                    "fundamental_score": null,
+                    "trend_score": score,
+                    "momentum_score": score,
+                    "volatility_score": score,
+                    "volume_score": score,
+                    "suggested_operation": if score > 50.0 { "BUY" } else { "SELL" },
+                    "explanation": "",
+                }))
+                .into_response()
+            }),
+        )
+        .route(
+            "/api/v1/timeframes/config",
+            get(|| async {
+                let timeframes: Vec<serde_json::Value> = ["15m", "1h", "4h", "1d", "1w"]
+                    .iter()
+                    .map(|name| {
+                        serde_json::json!({
+                            "name": name, "display_name": name, "duration_seconds": 60,
+                            "weight": 1.0, "category": "any", "aliases": [],
+                            "recommended_limit": 100, "max_gap_hours": 1
+                        })
+                    })
+                    .collect();
+                axum::Json(serde_json::json!({
+                    "timeframes": timeframes,
+                    "aliases": {},
+                    "categories": {},
+                    "providers": {},
+                    "metadata": {
+                        "version": "1.0.0", "last_updated": "2025-09-23T14:00:00Z",
+                        "total_timeframes": 5, "total_aliases": 0
+                    }
+                }))
+            }),
+        )
+}
+
+#[tokio::test]
+async fn test_confluence_combines_timeframes_with_configured_weights() {
+    let api_base = spawn_upstream(confluence_upstream()).await;
+    let app = app(AppState::new(test_config(&api_base)).unwrap());
+
+    let response = app
+        .clone()
+        .oneshot(get_request("/api/confluence/AAPL"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_body(response).await;
+    // Señales -40 (15m, 1h, 4h) y +80 (1d, 1w) con el mismo peso: (3·-40 + 2·80) / 5
+    assert_eq!(body["score"], 8.0);
+    assert_eq!(body["interpretation"]["label"], "neutral");
+    assert_eq!(body["weights_source"], "inbestia");
+    let timeframes = body["timeframes"].as_array().unwrap();
+    assert_eq!(timeframes.len(), 5);
+    assert_eq!(timeframes[0]["timeframe"], "15m");
+    assert_eq!(timeframes[0]["signal"], -40.0);
+    assert_eq!(timeframes[4]["timeframe"], "1w");
+    assert_eq!(timeframes[4]["suggested_operation"], "BUY");
+
+    // Símbolo desconocido en todos los timeframes: 404 con sugerencias
+    let response = app
+        .clone()
+        .oneshot(get_request("/api/confluence/APPL"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body = json_body(response).await;
+    assert_eq!(body["error"]["code"], "SYMBOL_NOT_FOUND");
+    assert_eq!(body["error"]["suggestions"][0], "AAPL");
+
+    let response = app
+        .oneshot(get_request("/api/confluence/NOT%20A%20TICKER"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_confluence_falls_back_to_default_weights() {
+    // `typed_upstream` no responde a tiempo a la configuración de timeframes
+    let api_base = spawn_upstream(typed_upstream()).await;
+    let app = app(AppState::new(test_config(&api_base)).unwrap());
+
+    let response = app.oneshot(get_request("/api/confluence/AAPL")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_body(response).await;
+    assert_eq!(body["weights_source"], "default");
+    // Puntuación 72.5 en todos los timeframes: señal +45
+    assert_eq!(body["score"], 45.0);
+    assert_eq!(body["interpretation"]["label"], "bullish");
+    assert_eq!(body["timeframes"][4]["weight"], 1.5);
+}
+
+#[test]
+fn test_confluence_scale_and_interpretation() {
+    use ghost_backend::confluence::{combine, interpret, signal};
+
+    assert_eq!(signal(50.0), 0.0);
+    assert_eq!(signal(100.0), 100.0);
+    assert_eq!(signal(0.0), -100.0);
+    assert_eq!(signal(140.0), 100.0);
+    assert_eq!(combine(&[(1.0, 80.0), (3.0, -40.0)]), Some(-10.0));
+    assert_eq!(combine(&[]), None);
+    assert_eq!(interpret(75.0).label, "strong_bullish");
+    assert_eq!(interpret(-20.0).label, "bearish");
+    assert_eq!(interpret(-60.0).label, "strong_bearish");
+}
+
+// ===== Canal en vivo (SSE) =====
+
+/// Lee del stream SSE hasta que el texto acumulado cumple `done` (o falla a los 5 s)
+async fn read_sse_until(
+    body: &mut (impl futures_util::Stream<Item = Result<Bytes, axum::Error>> + Unpin),
+    text: &mut String,
+    done: impl Fn(&str) -> bool,
+) {
+    use futures_util::StreamExt;
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while !done(text) {
+            let chunk = body.next().await.unwrap().unwrap();
+            text.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("SSE stream did not deliver the expected events: {}", text));
+}
+
+/// Ids de los eventos SSE del tema `topic`
+fn sse_ids(text: &str, topic: &str) -> Vec<u64> {
+    text.split("\n\n")
+        .filter(|event| event.lines().any(|line| line == format!("event: {}", topic)))
+        .filter_map(|event| event.lines().find_map(|line| line.strip_prefix("id: ")))
+        .map(|id| id.parse().unwrap())
+        .collect()
+}
+
+/// Upstream del canal en vivo: un proveedor cuya latencia cambia en cada consulta
+fn live_upstream() -> Router {
+    let calls = Arc::new(AtomicUsize::new(0));
+    Router::new()
+        .route("/health", get(|| async { "ok" }))
+        .route(
+            "/api/v1/providers/status",
+            get(move || async move {
+                let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                axum::Json(serde_json::json!([{
+                    "name": "Yahoo Finance",
+                    "type_code": "yahoo",
+                    "available": true,
+                    "active": true,
+                    "rate_limit_remaining": null,
+                    "rate_limit_reset": null,
+                    "response_time_ms": call
+                }]))
+            }),
+        )
+}
+
+#[tokio::test]
+async fn test_live_stream_publishes_subscribed_topics() {
+    let api_base = spawn_upstream(live_upstream()).await;
+    let state = AppState::new(test_config(&api_base)).unwrap();
+    let app = app(state.clone());
+    upstream::check_all(&state).await;
+
+    let response = app
+        .clone()
+        .oneshot(get_request("/api/live?topics=health,providers"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    assert_eq!(state.live.subscribers(), 1);
+
+    // Una sola ronda de sondeo alimenta a todos los suscriptores
+    live::poll(&state).await;
+    let mut body = response.into_body().into_data_stream();
+    let mut text = String::new();
+    read_sse_until(&mut body, &mut text, |text| {
+        text.contains("event: health") && text.contains("event: providers")
+    })
+    .await;
+    assert!(text.starts_with("retry:3000"));
+    assert!(!text.contains("event: metrics"));
+    assert!(text.contains(r#""status":"healthy""#));
+    assert!(text.contains(r#""response_time_ms":1"#));
+
+    // La latencia del proveedor cambió: se publica de nuevo
+    live::poll(&state).await;
+    read_sse_until(&mut body, &mut text, |text| sse_ids(text, "providers").len() == 2).await;
+
+    // Al reconectar con Last-Event-ID se reenvía sólo lo posterior
+    let mut request = get_request("/api/live?topics=providers");
+    request.headers_mut().insert(
+        "last-event-id",
+        sse_ids(&text, "providers")[0].to_string().parse().unwrap(),
+    );
+    let response = app.clone().oneshot(request).await.unwrap();
+    let mut body = response.into_body().into_data_stream();
+    let mut replayed = String::new();
+    read_sse_until(&mut body, &mut replayed, |text| text.contains("event: providers")).await;
+    assert_eq!(sse_ids(&replayed, "providers"), vec![sse_ids(&text, "providers")[1]]);
+    assert!(replayed.contains(r#""response_time_ms":2"#));
+
+    // Sin sesión no hay suscripción
+    let subscribers = state.live.subscribers();
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/live?topics=providers")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(state.live.subscribers(), subscribers);
+
+    let response = app
+        .oneshot(get_request("/api/live?topics=health,prices"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json = json_body(response).await;
+    assert_eq!(json["error"]["fields"][0]["field"], "topics");
+}
+
+#[test]
+fn test_live_hub_deduplicates_and_replays() {
+    let hub = LiveHub::new(2);
+    assert!(hub.publish(Topic::Health, serde_json::json!({ "status": "healthy" })));
+    assert!(!hub.publish(Topic::Health, serde_json::json!({ "status": "healthy" })));
+    assert!(hub.publish(Topic::Metrics, serde_json::json!({ "cpu": 1 })));
+    assert!(hub.publish(Topic::Health, serde_json::json!({ "status": "degraded" })));
+
+    // Sin Last-Event-ID: el último estado de cada tema pedido
+    let (backlog, _) = hub.subscribe(&[Topic::Health, Topic::Providers], None);
+    assert_eq!(backlog.len(), 1);
+    assert_eq!(backlog[0].data["status"], "degraded");
+    let last = backlog[0].id;
+
+    // El buffer de reenvío guarda 2 eventos: el primero ya no se puede reenviar
+    let (backlog, _) = hub.subscribe(&Topic::ALL, Some(last - 1));
+    assert_eq!(backlog.iter().map(|e| e.id).collect::<Vec<_>>(), vec![last]);
+    let (backlog, _) = hub.subscribe(&Topic::ALL, Some(last - 3));
+    assert_eq!(backlog.len(), 2);
+    assert_eq!(backlog[0].topic, Topic::Metrics);
+
+    // Un id de otro proceso (o del futuro) también recibe el estado actual
+    let (backlog, _) = hub.subscribe(&[Topic::Metrics], Some(last + 10));
+    assert_eq!(backlog.len(), 1);
+    let (backlog, _) = hub.subscribe(&Topic::ALL, Some(last));
+    assert!(backlog.is_empty());
+}
+
+// ===== Watchlists (PostgreSQL) =====
+
+/// Base de datos de los tests de persistencia (TEST_DATABASE_URL); sin ella se omiten
+fn test_database_url() -> Option<String> {
+    let url = std::env::var("TEST_DATABASE_URL").ok().filter(|url| !url.is_empty());
+    if url.is_none() {
+        eprintln!("TEST_DATABASE_URL not set: skipping database test");
+    }
+    url
+}
+
+fn watchlist_request(method: &str, uri: &str, username: &str, body: Option<&str>) -> Request<Body> {
+    let builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("authorization", format!("Bearer {}", session_for(username)));
+    match body {
+        Some(body) => builder
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap(),
+        None => builder.body(Body::empty()).unwrap(),
+    }
+}
+
+#[tokio::test]
+async fn test_watchlists_require_a_database() {
+    let (app, _) = create_test_app().await;
+
+    let response = app
+        .clone()
+        .oneshot(Request::get("/api/watchlists").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .oneshot(watchlist_request("GET", "/api/watchlists", "tester", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(json_body(response).await["error"]["code"], "DATABASE_NOT_CONFIGURED");
+}
+
+#[tokio::test]
+async fn test_watchlist_crud_is_persisted_per_user() {
+    let Some(database_url) = test_database_url() else {
+        return;
+    };
+    let api_base = spawn_upstream(mock_upstream()).await;
+    let config = Config {
+        database_url: Some(database_url),
+        ..test_config(&api_base)
+    };
+    let state = AppState::new(config).unwrap();
+    db::migrate(state.db.as_ref().unwrap()).await.unwrap();
+    let app = app(state);
+    // Usuarios únicos: los tests comparten la base de datos
+    let user = format!("alice-{}", uuid::Uuid::new_v4());
+    let other = format!("bob-{}", uuid::Uuid::new_v4());
+
+    let response = app
+        .clone()
+        .oneshot(watchlist_request(
+            "POST",
+            "/api/watchlists",
+            &user,
+            Some(r#"{"name":" Tech ","items":[{"symbol":"aapl","timeframe":"daily","notes":"core"},{"symbol":"MSFT"}]}"#),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created = json_body(response).await;
+    let id = created["id"].as_i64().unwrap();
+    assert_eq!(created["name"], "Tech");
+    assert_eq!(created["items"][0]["symbol"], "AAPL");
+    assert_eq!(created["items"][0]["timeframe"], "1d");
+    assert_eq!(created["items"][0]["notes"], "core");
+    assert_eq!(created["items"][1]["timeframe"], "1d");
+
+    // Mismo nombre, símbolos repetidos o body inválido
+    let response = app
+        .clone()
+        .oneshot(watchlist_request("POST", "/api/watchlists", &user, Some(r#"{"name":"Tech"}"#)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(json_body(response).await["error"]["code"], "WATCHLIST_NAME_TAKEN");
+    let response = app
+        .clone()
+        .oneshot(watchlist_request(
+            "POST",
+            "/api/watchlists",
+            &user,
+            Some(r#"{"name":"Dup","items":[{"symbol":"TSLA"},{"symbol":"tsla"}]}"#),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json_body(response).await["error"]["fields"][0]["field"], "items.1.symbol");
+    let response = app
+        .clone()
+        .oneshot(watchlist_request(
+            "POST",
+            "/api/watchlists",
+            &user,
+            Some(r#"{"items":[{"symbol":"AAPL","timeframe":"2d"}]}"#),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let fields = json_body(response).await["error"]["fields"].clone();
+    let fields: Vec<&str> = fields.as_array().unwrap().iter().map(|f| f["field"].as_str().unwrap()).collect();
+    assert!(fields.contains(&"name") && fields.contains(&"items.0.timeframe"));
+
+    // PUT reemplaza nombre, orden y contenido
+    let uri = format!("/api/watchlists/{}", id);
+    let response = app
+        .clone()
+        .oneshot(watchlist_request(
+            "PUT",
+            &uri,
+            &user,
+            Some(r#"{"name":"Big tech","items":[{"symbol":"MSFT","timeframe":"1w"},{"symbol":"AAPL","notes":"hold"},{"symbol":"NVDA","timeframe":"4h"}]}"#),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .clone()
+        .oneshot(watchlist_request("GET", "/api/watchlists", &user, None))
+        .await
+        .unwrap();
+    let lists = json_body(response).await;
+    assert_eq!(lists.as_array().unwrap().len(), 1);
+    let symbols: Vec<&str> = lists[0]["items"].as_array().unwrap().iter().map(|i| i["symbol"].as_str().unwrap()).collect();
+    assert_eq!(symbols, ["MSFT", "AAPL", "NVDA"]);
+    assert_eq!(lists[0]["name"], "Big tech");
+    assert_eq!(lists[0]["items"][0]["timeframe"], "1w");
+    assert_eq!(lists[0]["items"][1]["notes"], "hold");
+
+    // Las watchlists de otro usuario no existen para él
+    for method in ["GET", "DELETE"] {
+        let response = app
+            .clone()
+            .oneshot(watchlist_request(method, &uri, &other, None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+    let response = app
+        .clone()
+        .oneshot(watchlist_request("GET", "/api/watchlists", &other, None))
+        .await
+        .unwrap();
+    assert_eq!(json_body(response).await, serde_json::json!([]));
+
+    let response = app
+        .clone()
+        .oneshot(watchlist_request("DELETE", &uri, &user, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app
+        .oneshot(watchlist_request("GET", &uri, &user, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(json_body(response).await["error"]["code"], "WATCHLIST_NOT_FOUND");
+}
+
+// ===== Historial de análisis =====
+
+/// inBestia de prueba: `/api/v1/analyze` recomienda BUY, BUY, SELL, BUY... con la
+/// puntuación igual al número de llamada
+fn history_upstream() -> Router {
+    let calls = Arc::new(AtomicUsize::new(0));
+    Router::new().route(
+        "/api/v1/analyze",
+        post(move |axum::Json(request): axum::Json<serde_json::Value>| {
+            let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+            async move {
+                axum::Json(serde_json::json!({
+                    "symbol": request["symbol"],
+                    "timeframe": request["timeframe"],
+                    "score": call as f64,
+                    "technical_score": 50.0,
+                    "fundamental_score": null,
+                    "trend_score": 40.0,
+                    "momentum_score": 30.0,
+                    "volatility_score": 20.0,
+                    "volume_score": 10.0,
+                    "suggested_operation": if call.is_multiple_of(3) { "SELL" } else { "BUY" },
+                    "explanation": "mock"
+                }))
+            }
+        }),
+    )
+}
+
+#[tokio::test]
+async fn test_history_requires_a_database() {
+    let (app, _) = create_test_app().await;
+
+    let response = app
+        .oneshot(watchlist_request("GET", "/api/history/AAPL", "tester", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(json_body(response).await["error"]["code"], "DATABASE_NOT_CONFIGURED");
+}
+
+#[test]
+fn test_manifest_history_requires_analysis_schema() {
+    let routes = parse_manifest(
+        r#"
+        [[routes]]
+        path = "/api/v1/historical"
+        methods = ["POST"]
+        schema = "HistoricalRequest"
+        history = true
+        "#,
+        "routes.toml",
+    )
+    .unwrap();
+    let errors = validate_routes(&routes).unwrap_err();
+    assert!(errors[0].contains("history requires schema"), "{:?}", errors);
+}
+
+#[tokio::test]
+async fn test_analysis_history_records_scores_and_operation_changes() {
+    let Some(database_url) = test_database_url() else {
+        return;
+    };
+    let api_base = spawn_upstream(history_upstream()).await;
+    let config = Config {
+        database_url: Some(database_url),
+        // Cada análisis debe llegar a inBestia
+        cache_enabled: false,
+        max_request_size: 64 * 1024,
+        ..test_config(&api_base)
+    };
+    let state = AppState::new(config).unwrap();
+    db::migrate(state.db.as_ref().unwrap()).await.unwrap();
+    let app = app(state);
+    // Símbolo único: los tests comparten la base de datos
+    let symbol = format!("H{}", &uuid::Uuid::new_v4().simple().to_string()[..12]).to_uppercase();
+
+    // Cuatro análisis diarios (el tercero cambia a SELL y el cuarto vuelve a BUY) y uno de 4h
+    for timeframe in ["daily", "1d", "daily", "1d", "4h"] {
+        let body = format!(r#"{{"symbol":"{}","timeframe":"{}"}}"#, symbol.to_lowercase(), timeframe);
+        let response = app.clone().oneshot(post_json("/api/v1/analyze", &body)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        // El body se transmite entero al cliente; el análisis se guarda al terminar
+        json_body(response).await;
+    }
+
+    // La escritura es asíncrona: se espera a que estén los cinco análisis
+    let uri = format!("/api/history/{}", symbol.to_lowercase());
+    let mut history = serde_json::Value::Null;
+    for _ in 0..100 {
+        let response = app
+            .clone()
+            .oneshot(watchlist_request("GET", &uri, "tester", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        history = json_body(response).await;
+        let recorded: usize = history["series"]
+            .as_object()
+            .unwrap()
+            .values()
+            .map(|series| series["score"].as_array().unwrap().len())
+            .sum();
+        if recorded == 5 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(history["symbol"], symbol);
+    assert_eq!(history["has_more"], false);
+    let daily = &history["series"]["1d"];
+    assert_eq!(daily["score"], serde_json::json!([1.0, 2.0, 3.0, 4.0]));
+    assert_eq!(daily["suggested_operation"], serde_json::json!(["BUY", "BUY", "SELL", "BUY"]));
+    assert_eq!(daily["trend_score"][0], 40.0);
+    assert_eq!(daily["fundamental_score"][0], serde_json::Value::Null);
+    assert_eq!(daily["timestamps"].as_array().unwrap().len(), 4);
+    assert_eq!(history["series"]["4h"]["score"], serde_json::json!([5.0]));
+    let changes = history["operation_changes"].as_array().unwrap();
+    assert_eq!(changes.len(), 2);
+    assert_eq!((&changes[0]["from"], &changes[0]["to"]), (&"BUY".into(), &"SELL".into()));
+    assert_eq!((&changes[1]["from"], &changes[1]["to"]), (&"SELL".into(), &"BUY".into()));
+    assert_eq!(changes[1]["timeframe"], "1d");
+
+    // Filtro por timeframe (admite alias) y paginación: los cambios se detectan aunque
+    // el análisis anterior quede en otra página
+    let response = app
+        .clone()
+        .oneshot(watchlist_request("GET", &format!("{}?timeframe=daily&limit=2&offset=2", uri), "tester", None))
+        .await
+        .unwrap();
+    let page = json_body(response).await;
+    assert_eq!(page["series"].as_object().unwrap().len(), 1);
+    assert_eq!(page["series"]["1d"]["score"], serde_json::json!([3.0, 4.0]));
+    assert_eq!(page["operation_changes"].as_array().unwrap().len(), 2);
+    assert_eq!(page["has_more"], false);
+    let response = app
+        .clone()
+        .oneshot(watchlist_request("GET", &format!("{}?limit=2", uri), "tester", None))
+        .await
+        .unwrap();
+    assert_eq!(json_body(response).await["has_more"], true);
+
+    // Rango temporal: `from` en el futuro deja la serie vacía
+    let response = app
+        .clone()
+        .oneshot(watchlist_request("GET", &format!("{}?from=2999-01-01T00:00:00Z", uri), "tester", None))
+        .await
+        .unwrap();
+    let empty = json_body(response).await;
+    assert_eq!(empty["series"], serde_json::json!({}));
+
+    let response = app
+        .oneshot(watchlist_request(
+            "GET",
+            &format!("{}?from=ayer&timeframe=2d&limit=0&offset=-1", uri),
+            "tester",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let fields = json_body(response).await["error"]["fields"].clone();
+    let fields: Vec<&str> = fields.as_array().unwrap().iter().map(|f| f["field"].as_str().unwrap()).collect();
+    assert_eq!(fields, ["timeframe", "from", "limit", "offset"]);
+}
+
+// ===== Jobs programados =====
+
+fn job(name: &str, kind: JobKind, schedule: &str) -> JobSpec {
+    JobSpec {
+        name: name.to_string(),
+        schedule: schedule.to_string(),
+        kind,
+        symbols: Vec::new(),
+        timeframes: Vec::new(),
+        include_fundamental: false,
+        jitter_secs: 0,
+        max_concurrency: None,
+        enabled: true,
+    }
+}
+
+fn admin_post(uri: &str) -> Request<Body> {
+    Request::post(uri)
+        .header("x-admin-token", "admin-secret")
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// Espera a que `name` tenga `runs` ejecuciones en `GET /api/jobs` y devuelve su estado
+async fn wait_for_runs(app: &Router, name: &str, runs: usize) -> serde_json::Value {
+    for _ in 0..150 {
+        let response = app.clone().oneshot(get_request("/api/jobs")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let jobs = json_body(response).await["jobs"].clone();
+        let job = jobs.as_array().unwrap().iter().find(|job| job["name"] == name).unwrap().clone();
+        if job["runs"].as_array().unwrap().len() >= runs && job["running"] == false {
+            return job;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    panic!("job {} did not run", name);
+}
+
+#[test]
+fn test_example_job_manifest_is_valid() {
+    let manifest = jobs::load_manifest(std::path::Path::new("jobs.example.toml")).unwrap();
+    assert!(!manifest.is_empty());
+    jobs::validate_jobs(&manifest).unwrap();
+
+    // El crontab de 5 campos se ejecuta en el segundo 0; los días por nombre
+    let next = jobs::parse_schedule("*/15 * * * *").unwrap().upcoming(chrono::Utc).next().unwrap();
+    assert_eq!(chrono::Timelike::second(&next), 0);
+    assert_eq!(chrono::Timelike::minute(&next) % 15, 0);
+    let next = jobs::parse_schedule("0 9 * * Mon").unwrap().upcoming(chrono::Utc).next().unwrap();
+    assert_eq!(chrono::Datelike::weekday(&next), chrono::Weekday::Mon);
+}
+
+#[test]
+fn test_crontab_days_of_week_start_on_sunday() {
+    use chrono::{Datelike, TimeZone, Weekday};
+    // Sábado 17 de octubre de 2026
+    let saturday = chrono::Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+    let days = |expression: &str, count: usize| -> Vec<Weekday> {
+        jobs::parse_schedule(expression)
+            .unwrap()
+            .after(&saturday)
+            .take(count)
+            .map(|at| at.weekday())
+            .collect()
+    };
+
+    use Weekday::*;
+    assert_eq!(days("0 9 * * 1-5", 6), [Mon, Tue, Wed, Thu, Fri, Mon]);
+    assert_eq!(days("0 9 * * Mon-Fri", 5), [Mon, Tue, Wed, Thu, Fri]);
+    assert_eq!(days("0 9 * * 0", 2), [Sun, Sun]);
+    assert_eq!(days("0 9 * * 7", 1), [Sun]);
+    assert_eq!(days("0 9 * * 5-7", 4), [Sun, Fri, Sat, Sun]);
+    assert_eq!(days("0 9 * * 1,3/2", 4), [Sun, Mon, Wed, Fri]);
+    assert_eq!(days("0 9 * * */2", 4), [Sun, Tue, Thu, Sat]);
+    // Con 6 campos manda la crate cron: 1 es domingo
+    assert_eq!(days("0 0 9 * * 1", 1), [Sun]);
+
+    assert!(jobs::parse_schedule("0 9 * * 8").is_err());
+    assert!(jobs::parse_schedule("0 9 * * 5-2").is_err());
+}
+
+#[test]
+fn test_job_manifest_reports_every_error() {
+    let manifest = jobs::parse_manifest(
+        r#"
+        [[jobs]]
+        name = "scan"
+        schedule = "every day"
+        kind = "analyze"
+        symbols = ["AAPL", "bad symbol"]
+        timeframes = ["1d", "2d"]
+        max_concurrency = 0
+
+        [[jobs]]
+        name = "scan"
+        schedule = "@daily"
+        kind = "providers_status"
+        symbols = ["AAPL"]
+
+        [[jobs]]
+        name = "empty scan"
+        schedule = "0 * * * *"
+        kind = "analyze"
+        "#,
+        "jobs.toml",
+    )
+    .unwrap();
+    let errors = jobs::validate_jobs(&manifest).unwrap_err();
+    let expected = [
+        "job #1 (scan): invalid schedule 'every day'",
+        "job #1 (scan): max_concurrency must be greater than zero",
+        "job #1 (scan): invalid symbol 'bad symbol'",
+        "job #1 (scan): unknown timeframe '2d'",
+        "job #2 (scan): duplicate job name",
+        "job #2 (scan): symbols and timeframes only apply to analyze jobs",
+        "job #3 (empty scan): name must be non-empty",
+        "job #3 (empty scan): analyze jobs need at least one symbol and one timeframe",
+    ];
+    assert_eq!(errors.len(), expected.len(), "{:#?}", errors);
+    for (error, expected) in errors.iter().zip(expected) {
+        assert!(error.starts_with(expected), "{} != {}", error, expected);
+    }
+
+    let unknown = jobs::parse_manifest("[[jobs]]\nname = \"x\"\nschedule = \"@daily\"\nkind = \"reboot\"", "jobs.toml");
+    assert!(unknown.is_err());
+}
+
+#[tokio::test]
+async fn test_jobs_run_now_and_record_history() {
+    let api_base = spawn_upstream(typed_upstream()).await;
+    let rescan = JobSpec {
+        symbols: vec!["AAPL".to_string(), "MSFT".to_string()],
+        timeframes: vec!["1d".to_string()],
+        max_concurrency: Some(1),
+        ..job("rescan", JobKind::Analyze, "@yearly")
+    };
+    let providers = JobSpec {
+        enabled: false,
+        ..job("providers", JobKind::ProvidersStatus, "@yearly")
+    };
+    let config = Config {
+        jobs: vec![rescan, providers],
+        ..test_config(&api_base)
+    };
+    config.validate().unwrap();
+    let app = app(AppState::new(config).unwrap());
+
+    let response = app.clone().oneshot(get_request("/api/jobs")).await.unwrap();
+    let body = json_body(response).await;
+    assert_eq!(body["jobs"][0]["name"], "rescan");
+    assert_eq!(body["jobs"][0]["kind"], "analyze");
+    assert_eq!(body["jobs"][0]["symbols"], serde_json::json!(["AAPL", "MSFT"]));
+    assert_eq!(body["jobs"][0]["runs"], serde_json::json!([]));
+    assert_eq!(body["jobs"][1]["enabled"], false);
+    let response = app
+        .clone()
+        .oneshot(Request::get("/api/jobs").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Ejecutar a mano exige ADMIN_TOKEN
+    let response = app
+        .clone()
+        .oneshot(Request::post("/api/jobs/rescan/run").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(admin_post("/api/jobs/nope/run")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(json_body(response).await["error"]["code"], "JOB_NOT_FOUND");
+
+    let response = app.clone().oneshot(admin_post("/api/jobs/rescan/run")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let rescan = wait_for_runs(&app, "rescan", 1).await;
+    let run = &rescan["runs"][0];
+    assert_eq!(run["trigger"], "manual");
+    assert_eq!(run["success"], false);
+    assert_eq!(run["summary"]["succeeded"], 1);
+    assert_eq!(run["summary"]["failed"], 1);
+    assert_eq!(run["summary"]["failures"][0]["symbol"], "MSFT");
+    assert_eq!(run["summary"]["failures"][0]["code"], "SYMBOL_NOT_FOUND");
+    assert!(run["duration_ms"].is_u64());
+
+    // Los jobs deshabilitados también se pueden lanzar a mano; el fallo queda registrado
+    let response = app.clone().oneshot(admin_post("/api/jobs/providers/run")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let providers = wait_for_runs(&app, "providers", 1).await;
+    assert_eq!(providers["runs"][0]["success"], false);
+    assert!(providers["runs"][0]["error"].as_str().unwrap().contains("invalid inBestia response"));
+    assert_eq!(providers["next_run"], serde_json::Value::Null);
+}
+
+#[tokio::test]
+async fn test_scheduled_jobs_run_on_their_schedule() {
+    let api_base = spawn_upstream(live_upstream()).await;
+    let config = Config {
+        jobs: vec![job("providers", JobKind::ProvidersStatus, "* * * * * *")],
+        jobs_history_size: 2,
+        ..test_config(&api_base)
+    };
+    let state = AppState::new(config).unwrap();
+    let scheduler = jobs::spawn_scheduler(state.clone());
+    assert_eq!(scheduler.len(), 1);
+    let app = app(state.clone());
+
+    // Cada segundo; sólo se guardan las JOBS_HISTORY_SIZE más recientes
+    let providers = wait_for_runs(&app, "providers", 2).await;
+    assert_eq!(providers["runs"][0]["trigger"], "scheduled");
+    assert_eq!(providers["runs"][0]["success"], true);
+    assert_eq!(providers["runs"][0]["summary"]["stored"], false);
+    assert!(providers["next_run"].is_string());
+
+    // Un job en marcha no vuelve a arrancar
+    let job = state.jobs.get("providers").unwrap();
+    assert!(job.try_begin());
+    let response = app.clone().oneshot(admin_post("/api/jobs/providers/run")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(json_body(response).await["error"]["code"], "JOB_ALREADY_RUNNING");
+    jobs::execute(&state, &job, Trigger::Manual).await;
+    let providers = wait_for_runs(&app, "providers", 2).await;
+    assert_eq!(providers["runs"].as_array().unwrap().len(), 2);
+
+    scheduler.iter().for_each(|handle| handle.abort());
+}
+
+#[tokio::test]
+async fn test_job_snapshots_are_stored() {
+    let Some(database_url) = test_database_url() else {
+        return;
+    };
+    let api_base = spawn_upstream(live_upstream()).await;
+    // Nombre único: los tests comparten la base de datos
+    let name = format!("providers-{}", uuid::Uuid::new_v4());
+    let config = Config {
+        database_url: Some(database_url),
+        jobs: vec![job(&name, JobKind::ProvidersStatus, "@yearly")],
+        ..test_config(&api_base)
+    };
+    let state = AppState::new(config).unwrap();
+    let db = state.db.clone().unwrap();
+    db::migrate(&db).await.unwrap();
+
+    let job = state.jobs.get(&name).unwrap();
+    assert!(job.try_begin());
+    let run = jobs::execute(&state, &job, Trigger::Manual).await;
+    assert!(run.success, "{:?}", run.error);
+    assert_eq!(run.summary["stored"], true);
+
+    let (kind, data): (String, serde_json::Value) =
+        sqlx::query_as("SELECT kind, data FROM job_snapshots WHERE job = $1")
+            .bind(&name)
+            .fetch_one(&db)
+            .await
+            .unwrap();
+    assert_eq!(kind, "providers_status");
+    assert_eq!(data[0]["type_code"], "yahoo");
+}
+
+// ===== Alertas =====
+
+fn analysis(score: f64, operation: &str, volatility: f64) -> ghost_shared::AnalysisResponse {
+    serde_json::from_value(serde_json::json!({
+        "symbol": "AAPL",
+        "timeframe": "1d",
+        "score": score,
+        "technical_score": 50.0,
+        "fundamental_score": null,
+        "trend_score": 40.0,
+        "momentum_score": 30.0,
+        "volatility_score": volatility,
+        "volume_score": 10.0,
+        "suggested_operation": operation,
+        "explanation": "mock"
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_alert_expressions_parse_and_evaluate() {
+    let before = analysis(60.0, "HOLD", 50.0);
+    let after = analysis(75.0, "BUY", 85.0);
+
+    let crosses = expr::parse("score crosses above 70").unwrap();
+    assert!(crosses.matches(&after, Some(&before)));
+    assert!(!crosses.matches(&before, Some(&after)));
+    assert!(!crosses.matches(&after, None));
+    assert!(expr::parse("SCORE Crosses Below 70").unwrap().matches(&before, Some(&after)));
+
+    let changes = expr::parse(r#"suggested_operation changes from "HOLD" to "BUY""#).unwrap();
+    assert!(changes.matches(&after, Some(&before)));
+    assert!(!changes.matches(&before, Some(&after)));
+    assert!(!changes.matches(&after, Some(&after)));
+    assert!(expr::parse("suggested_operation changes").unwrap().matches(&before, Some(&after)));
+
+    let level = expr::parse(
+        r#"volatility_score > 80 and not (trend_score < 40 or suggested_operation == "SELL")"#,
+    )
+    .unwrap();
+    assert!(level.matches(&after, None));
+    assert!(!level.matches(&before, None));
+    // Un campo ausente no cumple ninguna comparación
+    assert!(!expr::parse("fundamental_score >= 0").unwrap().matches(&after, None));
+
+    let error = |source: &str| expr::parse(source).unwrap_err();
+    assert_eq!(error("price > 10").position, 1);
+    assert_eq!(error("score > ").position, 9);
+    assert_eq!(error("score >> 10").position, 8);
+    assert_eq!(error("score > 10 and").position, 15);
+    assert_eq!(error(r#"suggested_operation > "BUY""#).position, 21);
+    assert_eq!(error("score changes").position, 1);
+    assert_eq!(error("suggested_operation crosses above 1").position, 1);
+    assert_eq!(error("(score > 10").position, 12);
+    assert_eq!(error(r#"suggested_operation == "BUY"#).position, 24);
+    assert!(error("score > 10 score").to_string().starts_with("posición 12: "));
+}
+
+#[tokio::test]
+async fn test_alerts_require_a_database() {
+    let (app, _) = create_test_app().await;
+
+    for uri in ["/api/alerts", "/api/alerts/rules", "/api/alerts/rules/1"] {
+        let response = app
+            .clone()
+            .oneshot(watchlist_request("GET", uri, "tester", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE, "{}", uri);
+        assert_eq!(json_body(response).await["error"]["code"], "DATABASE_NOT_CONFIGURED");
+    }
+}
+
+/// inBestia con una secuencia fija de análisis: (score, operación, volatilidad)
+fn scripted_upstream(script: Vec<(f64, &'static str, f64)>) -> Router {
+    let calls = Arc::new(AtomicUsize::new(0));
+    Router::new().route(
+        "/api/v1/analyze",
+        post(move |axum::Json(request): axum::Json<serde_json::Value>| {
+            let (score, operation, volatility) = script[calls.fetch_add(1, Ordering::SeqCst)];
+            async move {
+                let mut response = serde_json::to_value(analysis(score, operation, volatility)).unwrap();
+                response["symbol"] = request["symbol"].clone();
+                response["timeframe"] = request["timeframe"].clone();
+                axum::Json(response)
+            }
+        }),
+    )
+}
+
+#[tokio::test]
+async fn test_alert_rules_fire_on_transitions_with_cooldown() {
+    let Some(database_url) = test_database_url() else {
+        return;
+    };
+    let script = vec![
+        (60.0, "HOLD", 50.0),
+        (75.0, "BUY", 85.0),
+        (65.0, "HOLD", 90.0),
+        (80.0, "BUY", 50.0),
+        (85.0, "BUY", 95.0),
+    ];
+    let api_base = spawn_upstream(scripted_upstream(script.clone())).await;
+    let config = Config {
+        database_url: Some(database_url),
+        // Cada análisis debe llegar a inBestia
+        cache_enabled: false,
+        max_request_size: 64 * 1024,
+        ..test_config(&api_base)
+    };
+    let state = AppState::new(config).unwrap();
+    let db = state.db.clone().unwrap();
+    db::migrate(&db).await.unwrap();
+    let app = app(state);
+    // Símbolo y usuario únicos: los tests comparten la base de datos
+    let symbol = format!("A{}", &uuid::Uuid::new_v4().simple().to_string()[..12]).to_uppercase();
+    let user = format!("alerts-{}", uuid::Uuid::new_v4());
+
+    let create = |body: serde_json::Value| {
+        watchlist_request("POST", "/api/alerts/rules", &user, Some(&body.to_string()))
+    };
+    let response = app
+        .clone()
+        .oneshot(create(serde_json::json!({
+            "name": "  bad  ",
+            "expression": "score crosses 70",
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let fields = json_body(response).await["error"]["fields"].clone();
+    assert_eq!(fields[0]["field"], "expression");
+    assert!(fields[0]["message"].as_str().unwrap().starts_with("posición 15: "), "{}", fields);
+
+    let rules = [
+        serde_json::json!({
+            "name": "cruce",
+            "symbol": symbol.to_lowercase(),
+            "timeframe": "daily",
+            "expression": "score crosses above 70",
+            "cooldown_secs": 0,
+        }),
+        serde_json::json!({
+            "name": "compra",
+            "symbol": symbol,
+            "expression": r#"suggested_operation changes from "HOLD" to "BUY""#,
+        }),
+        serde_json::json!({
+            "name": "volatilidad",
+            "symbol": symbol,
+            "expression": " volatility_score > 80 ",
+            "cooldown_secs": 0,
+        }),
+        serde_json::json!({
+            "name": "horaria",
+            "symbol": symbol,
+            "timeframe": "1h",
+            "expression": "score > 0",
+        }),
+    ];
+    for rule in rules {
+        let response = app.clone().oneshot(create(rule)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+    let response = app.clone().oneshot(create(serde_json::json!({"name": "cruce", "expression": "score > 1"}))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app
+        .clone()
+        .oneshot(watchlist_request("GET", "/api/alerts/rules", &user, None))
+        .await
+        .unwrap();
+    let listed = json_body(response).await;
+    let names: Vec<&str> = listed.as_array().unwrap().iter().map(|r| r["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["compra", "cruce", "horaria", "volatilidad"]);
+    assert_eq!(listed[1]["symbol"], symbol);
+    assert_eq!(listed[1]["timeframe"], "1d");
+    assert_eq!(listed[0]["cooldown_secs"], 3600);
+    assert_eq!(listed[3]["expression"], "volatility_score > 80");
+
+    for (score, _, _) in &script {
+        let body = format!(r#"{{"symbol":"{}","timeframe":"1d"}}"#, symbol);
+        let response = app.clone().oneshot(post_json("/api/v1/analyze", &body)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        json_body(response).await;
+
+        // La evaluación es asíncrona: se espera a que las tres reglas diarias vean el análisis
+        let mut evaluated = 0;
+        for _ in 0..150 {
+            evaluated = sqlx::query_scalar::<_, i64>(
+                "SELECT count(*) FROM alert_state
+                 WHERE symbol = $1 AND (previous->>'score')::float8 = $2",
+            )
+            .bind(&symbol)
+            .bind(score)
+            .fetch_one(&db)
+            .await
+            .unwrap();
+            if evaluated == 3 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(evaluated, 3);
+    }
+
+    let response = app
+        .clone()
+        .oneshot(watchlist_request("GET", "/api/alerts", &user, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let alerts = json_body(response).await["alerts"].clone();
+    let fired: Vec<(&str, f64)> = alerts
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|alert| (alert["rule_name"].as_str().unwrap(), alert["analysis"]["score"].as_f64().unwrap()))
+        .collect();
+    // La más reciente primero; "compra" no vuelve a dispararse en el cuarto análisis por el cooldown
+    assert_eq!(
+        fired,
+        [("volatilidad", 85.0), ("cruce", 80.0), ("volatilidad", 75.0), ("compra", 75.0), ("cruce", 75.0)]
+    );
+    let first = &alerts[4];
+    assert_eq!(first["symbol"], symbol);
+    assert_eq!(first["timeframe"], "1d");
+    assert_eq!(first["previous"]["score"], 60.0);
+
+    let response = app
+        .clone()
+        .oneshot(watchlist_request("GET", "/api/alerts?limit=1&offset=1", &user, None))
+        .await
+        .unwrap();
+    let page = json_body(response).await["alerts"].clone();
+    assert_eq!(page.as_array().unwrap().len(), 1);
+    assert_eq!(page[0]["rule_name"], "cruce");
+
+    // Otro usuario no ve las reglas ni las alertas
+    let other = format!("alerts-{}", uuid::Uuid::new_v4());
+    let response = app
+        .clone()
+        .oneshot(watchlist_request("GET", "/api/alerts", &other, None))
+        .await
+        .unwrap();
+    assert_eq!(json_body(response).await["alerts"], serde_json::json!([]));
+    let id = listed[0]["id"].as_i64().unwrap();
+    let uri = format!("/api/alerts/rules/{}", id);
+    let response = app
+        .clone()
+        .oneshot(watchlist_request("DELETE", &uri, &other, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Al borrar una regla sus alertas se conservan
+    let response = app
+        .clone()
+        .oneshot(watchlist_request("DELETE", &uri, &user, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app
+        .oneshot(watchlist_request("GET", "/api/alerts", &user, None))
+        .await
+        .unwrap();
+    let alerts = json_body(response).await["alerts"].clone();
+    assert_eq!(alerts[3]["rule_name"], "compra");
+    assert_eq!(alerts[3]["rule_id"], serde_json::Value::Null);
+}
+
+#[tokio::test]
+async fn test_concurrent_first_evaluations_fire_once() {
+    let Some(database_url) = test_database_url() else {
+        return;
+    };
+    let api_base = spawn_upstream(mock_upstream()).await;
+    let config = Config {
+        database_url: Some(database_url),
+        ..test_config(&api_base)
+    };
+    let state = AppState::new(config).unwrap();
+    let db = state.db.clone().unwrap();
+    db::migrate(&db).await.unwrap();
+    let symbol = format!("C{}", &uuid::Uuid::new_v4().simple().to_string()[..12]).to_uppercase();
+    let user = format!("alerts-{}", uuid::Uuid::new_v4());
+
+    let rule = serde_json::json!({ "name": "sube", "symbol": symbol, "expression": "score > 50" });
+    let response = app(state.clone())
+        .oneshot(watchlist_request("POST", "/api/alerts/rules", &user, Some(&rule.to_string())))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // Varios análisis del mismo símbolo a la vez, sin estado previo de la regla
+    let response = analysis(80.0, "BUY", 50.0);
+    let now = chrono::Utc::now();
+    let evaluations = (0..8).map(|_| {
+        ghost_backend::alerts::evaluate(&db, &state.webhooks, &symbol, "1d", &response, now)
+    });
+    for result in futures_util::future::join_all(evaluations).await {
+        result.unwrap();
+    }
+
+    let fired: i64 = sqlx::query_scalar("SELECT count(*) FROM fired_alerts WHERE symbol = $1")
+        .bind(&symbol)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert_eq!(fired, 1);
+}
+
+// ===== Webhooks =====
+
+fn webhook(name: &str, url: &str, events: Vec<WebhookEvent>) -> WebhookSpec {
+    WebhookSpec {
+        name: name.to_string(),
+        url: url.to_string(),
+        secret: format!("{}-secret", name).into(),
+        events,
+    }
+}
+
+/// Una petición recibida por el receptor de webhooks de los tests
+#[derive(Clone)]
+struct Received {
+    path: String,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+/// Receptor de webhooks: `/ok` acepta, `/flaky` falla las dos primeras veces y `/down`
+/// responde siempre 503
+fn webhook_receiver(received: Arc<std::sync::Mutex<Vec<Received>>>) -> Router {
+    let flaky_calls = Arc::new(AtomicUsize::new(0));
+    let record = move |path: &'static str, status: StatusCode| {
+        let received = received.clone();
+        move |headers: HeaderMap, body: Bytes| async move {
+            received.lock().unwrap().push(Received {
+                path: path.to_string(),
+                headers,
+                body,
+            });
+            status
+        }
+    };
+    let flaky = record("/flaky", StatusCode::OK);
+    Router::new()
+        .route("/ok", post(record("/ok", StatusCode::OK)))
+        .route("/down", post(record("/down", StatusCode::SERVICE_UNAVAILABLE)))
+        .route(
+            "/flaky",
+            post(move |headers: HeaderMap, body: Bytes| {
+                let fail = flaky_calls.fetch_add(1, Ordering::SeqCst) < 2;
+                let flaky = flaky.clone();
+                async move {
+                    let status = flaky(headers, body).await;
+                    if fail {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    } else {
+                        status
+                    }
+                }
+            }),
+        )
+}
+
+fn admin_get(uri: &str) -> Request<Body> {
+    Request::get(uri)
+        .header("x-admin-token", "admin-secret")
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// Espera a que la entrega de `webhook` quede en `status` y la devuelve
+async fn wait_for_delivery(app: &Router, webhook: &str, status: &str) -> serde_json::Value {
+    let uri = format!("/api/webhooks/deliveries?webhook={}&status={}", webhook, status);
+    for _ in 0..200 {
+        let response = app.clone().oneshot(admin_get(&uri)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let deliveries = json_body(response).await["deliveries"].clone();
+        if let Some(delivery) = deliveries.as_array().unwrap().first() {
+            return delivery.clone();
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    panic!("no {} delivery for webhook {}", status, webhook);
+}
+
+#[test]
+fn test_webhook_manifest_is_validated() {
+    let manifest = webhooks::load_manifest(std::path::Path::new("webhooks.example.toml")).unwrap();
+    assert!(!manifest.is_empty());
+    webhooks::validate_webhooks(&manifest).unwrap();
+
+    let manifest = webhooks::parse_manifest(
+        r#"
+        [[webhooks]]
+        name = "chat"
+        url = "https://chat.example.com/hook"
+        secret = "s"
+        events = ["alert_fired"]
+
+        [[webhooks]]
+        name = "chat"
+        url = "ftp://example.com"
+        secret = " "
+        events = []
+
+        [[webhooks]]
+        name = "bad name"
+        url = "/relative"
+        secret = "s"
+        events = ["provider_status_changed"]
+        "#,
+        "webhooks.toml",
+    )
+    .unwrap();
+    let errors = webhooks::validate_webhooks(&manifest).unwrap_err();
+    assert_eq!(errors.len(), 6, "{:?}", errors);
+    assert!(errors[0].contains("duplicate webhook name"));
+    assert!(errors[1].contains("url must be an absolute http(s) URL"));
+    assert!(errors[2].contains("secret must not be empty"));
+    assert!(errors[3].contains("at least one event"));
+    assert!(errors[4].starts_with("webhook #3 (bad name): name must"));
+
+    let unknown = webhooks::parse_manifest(
+        "[[webhooks]]\nname = \"x\"\nurl = \"http://x\"\nsecret = \"s\"\nevents = [\"job_failed\"]",
+        "webhooks.toml",
+    );
+    assert!(unknown.is_err());
+
+    // Las entregas se encolan en PostgreSQL
+    let config = Config {
+        webhooks: manifest[..1].to_vec(),
+        ..test_config("http://127.0.0.1:8000")
+    };
+    let error = config.validate().unwrap_err().to_string();
+    assert!(error.contains("WEBHOOKS_FILE requires DATABASE_URL"), "{}", error);
+
+    // La firma es el HMAC-SHA256 del body en hexadecimal (vector de RFC 4231, caso 2)
+    assert_eq!(
+        webhooks::sign("Jefe", b"what do ya want for nothing?"),
+        "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
+
+#[tokio::test]
+async fn test_webhook_deliveries_require_admin_token_and_database() {
+    let (app, _) = create_test_app().await;
+
+    let response = app
+        .clone()
+        .oneshot(get_request("/api/webhooks/deliveries"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app.oneshot(admin_get("/api/webhooks/deliveries")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(json_body(response).await["error"]["code"], "DATABASE_NOT_CONFIGURED");
+}
+
+#[tokio::test]
+async fn test_fired_alerts_are_signed_retried_and_dead_lettered() {
+    let Some(database_url) = test_database_url() else {
+        return;
+    };
+    let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let receiver = spawn_upstream(webhook_receiver(received.clone())).await;
+    let api_base = spawn_upstream(history_upstream()).await;
+    // Nombres únicos: los tests comparten la base de datos
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let (flaky, down) = (format!("flaky-{}", suffix), format!("down-{}", suffix));
+    let config = Config {
+        database_url: Some(database_url),
+        cache_enabled: false,
+        max_request_size: 64 * 1024,
+        webhooks: vec![
+            webhook(&flaky, &format!("{}/flaky", receiver), vec![WebhookEvent::AlertFired]),
+            webhook(&down, &format!("{}/down", receiver), vec![WebhookEvent::AlertFired]),
+            // Sin suscripción a alertas: no recibe nada
+            webhook(
+                &format!("providers-{}", suffix),
+                &format!("{}/ok", receiver),
+                vec![WebhookEvent::ProviderStatusChanged],
+            ),
+        ],
+        webhook_max_attempts: 3,
+        webhook_retry_base_delay_ms: 20,
+        webhook_retry_max_delay_ms: 50,
+        ..test_config(&api_base)
+    };
+    let state = AppState::new(config).unwrap();
+    db::migrate(state.db.as_ref().unwrap()).await.unwrap();
+    webhooks::spawn_dispatcher(state.clone()).unwrap();
+    let app = app(state);
+
+    let symbol = format!("W{}", &suffix[..12]).to_uppercase();
+    let user = format!("webhooks-{}", suffix);
+    let rule = serde_json::json!({"name": "any", "symbol": symbol, "expression": "score > 0"});
+    let response = app
+        .clone()
+        .oneshot(watchlist_request("POST", "/api/alerts/rules", &user, Some(&rule.to_string())))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = format!(r#"{{"symbol":"{}","timeframe":"1d"}}"#, symbol);
+    let response = app.clone().oneshot(post_json("/api/v1/analyze", &body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    json_body(response).await;
+
+    // Dos fallos y una entrega correcta, siempre con el mismo id de entrega y firma válida
+    let delivered = wait_for_delivery(&app, &flaky, "delivered").await;
+    assert_eq!(delivered["attempts"], 3);
+    assert_eq!(delivered["last_status"], 200);
+    assert_eq!(delivered["next_attempt_at"], serde_json::Value::Null);
+    assert!(delivered["delivered_at"].is_string());
+    let id = delivered["id"].as_str().unwrap().to_string();
+    let attempts: Vec<Received> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|r| r.path == "/flaky")
+        .cloned()
+        .collect();
+    assert_eq!(attempts.len(), 3);
+    for attempt in &attempts {
+        assert_eq!(attempt.headers["x-ghost-delivery"], id.as_str());
+        assert_eq!(attempt.headers["x-ghost-event"], "alert_fired");
+        assert_eq!(attempt.headers["content-type"], "application/json");
+        assert_eq!(
+            attempt.headers["x-ghost-signature"],
+            webhooks::sign(&format!("{}-secret", flaky), &attempt.body).as_str()
+        );
+    }
+    let payload: serde_json::Value = serde_json::from_slice(&attempts[2].body).unwrap();
+    assert_eq!(payload["event"], "alert_fired");
+    assert_eq!(payload["data"]["owner"], user.as_str());
+    assert_eq!(payload["data"]["rule_name"], "any");
+    assert_eq!(payload["data"]["symbol"], symbol.as_str());
+    assert_eq!(payload["data"]["analysis"]["score"], 1.0);
+    assert_eq!(delivered["payload"], payload);
+
+    // El receptor caído agota los intentos y la entrega pasa a dead letter
+    let dead = wait_for_delivery(&app, &down, "dead").await;
+    assert_eq!(dead["attempts"], 3);
+    assert_eq!(dead["last_status"], 503);
+    assert!(dead["last_error"].as_str().unwrap().contains("503"));
+    assert!(received.lock().unwrap().iter().all(|r| r.path != "/ok"));
+
+    // Reencolar: sólo las muertas, y vuelve a intentarse desde cero
+    let retry = |id: &str| admin_post(&format!("/api/webhooks/deliveries/{}/retry", id));
+    let response = app.clone().oneshot(retry(&id)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(json_body(response).await["error"]["code"], "DELIVERY_NOT_DEAD");
+    let response = app.clone().oneshot(retry(&uuid::Uuid::new_v4().to_string())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let dead_id = dead["id"].as_str().unwrap();
+    let response = app.clone().oneshot(retry(dead_id)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let requeued = json_body(response).await;
+    assert_eq!((&requeued["status"], &requeued["attempts"]), (&"pending".into(), &0.into()));
+    for _ in 0..200 {
+        if received.lock().unwrap().iter().filter(|r| r.path == "/down").count() == 6 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(wait_for_delivery(&app, &down, "dead").await["attempts"], 3);
+
+    let response = app
+        .oneshot(admin_get("/api/webhooks/deliveries?status=lost&limit=0"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+/// Upstream con un proveedor que alterna entre disponible y no disponible
+fn flapping_provider_upstream() -> Router {
+    let calls = Arc::new(AtomicUsize::new(0));
+    Router::new().route(
+        "/api/v1/providers/status",
+        get(move || async move {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            axum::Json(serde_json::json!([{
+                "name": "Yahoo Finance",
+                "type_code": "yahoo",
+                "available": call.is_multiple_of(2),
+                "active": true,
+                "rate_limit_remaining": null,
+                "rate_limit_reset": null,
+                "response_time_ms": 5
+            }]))
+        }),
+    )
+}
+
+#[tokio::test]
+async fn test_provider_status_changes_are_sent_to_webhooks() {
+    let Some(database_url) = test_database_url() else {
+        return;
+    };
+    let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let receiver = spawn_upstream(webhook_receiver(received.clone())).await;
+    let api_base = spawn_upstream(flapping_provider_upstream()).await;
+    let name = format!("providers-{}", uuid::Uuid::new_v4().simple());
+    let config = Config {
+        database_url: Some(database_url),
+        webhooks: vec![webhook(
+            &name,
+            &format!("{}/ok", receiver),
+            vec![WebhookEvent::ProviderStatusChanged],
+        )],
+        webhook_providers_poll_secs: 1,
+        ..test_config(&api_base)
+    };
+    let state = AppState::new(config).unwrap();
+    db::migrate(state.db.as_ref().unwrap()).await.unwrap();
+    webhooks::spawn_dispatcher(state.clone()).unwrap();
+    webhooks::spawn_provider_watch(state.clone()).unwrap();
+    let app = app(state);
+
+    // La primera consulta fija el estado; la segunda (un segundo después) ya cambia
+    let delivered = wait_for_delivery(&app, &name, "delivered").await;
+    assert_eq!(delivered["event"], "provider_status_changed");
+    let data = &delivered["payload"]["data"];
+    assert_eq!(data["changes"].as_array().unwrap().len(), 1);
+    assert_eq!(data["changes"][0]["type_code"], "yahoo");
+    assert_eq!(data["changes"][0]["from"]["available"], true);
+    assert_eq!(data["changes"][0]["to"]["available"], false);
+    assert_eq!(data["providers"][0]["available"], false);
+    let received = received.lock().unwrap();
+    assert_eq!(received[0].headers["x-ghost-event"], "provider_status_changed");
+}
+
+fn env_vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+    vars.iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_config_layers_file_env_and_command_line() {
+    let file = r#"
+        inbestia_api_url = ["http://inbestia-1:8080", "http://inbestia-2:8080"]
+        inbestia_api_key = "from-file"
+        auth_jwt_secret = "a-secret-that-is-at-least-32-bytes-long"
+        request_timeout = 10
+        max_request_size = 4096
+        rate_limits = { default = "60/60", analysis = "5/60" }
+        batch_max_items = 5
+        BIND_ADDR = "127.0.0.1:9000"
+    "#;
+    let sources = ConfigSources::new()
+        .with_file_contents(file, "test.toml")
+        .with_env(env_vars(&[("REQUEST_TIMEOUT", "20"), ("MAX_REQUEST_SIZE", "8192")]))
+        .with_override("max-request-size", "16384");
+    let config = Config::load(&sources).unwrap();
+
+    assert_eq!(
+        config.api_base_urls,
+        vec!["http://inbestia-1:8080", "http://inbestia-2:8080"]
+    );
+    assert_eq!(config.api_key.expose(), "from-file");
+    assert_eq!(config.bind_address, "127.0.0.1:9000");
+    // El entorno gana al fichero y la línea de comandos a ambos
+    assert_eq!(config.request_timeout_secs, 20);
+    assert_eq!(config.max_request_size, 16384);
+    assert_eq!(
+        config.rate_limit_classes["analysis"],
+        RateLimitRule {
+            requests: 5,
+            period_secs: 60
+        }
+    );
+
+    // El fichero de ejemplo es una configuración válida
+    let example = ConfigSources::new().with_file_contents(
+        include_str!("../config.example.toml"),
+        "config.example.toml",
+    );
+    Config::load(&example).unwrap();
+}
+
+#[test]
+fn test_config_reports_every_problem_at_once() {
+    let sources = ConfigSources::new()
+        .with_file_contents("request_timout = 5\nbind_addr = \"localhost\"", "test.toml")
+        .with_env(env_vars(&[
+            ("INBESTIA_API_URL", "http://localhost:8080"),
+            ("INBESTIA_API_KEY", "key"),
+            ("AUTH_JWT_SECRET", "a-secret-that-is-at-least-32-bytes-long"),
+            ("REQUEST_TIMEOUT", "30s"),
+            ("CORS_ALLOWED_ORIGINS", "http://localhost:3001/app"),
+        ]))
+        .with_override("MAX_REQUEST_SIZE", "lots");
+    let message = Config::load(&sources).unwrap_err().to_string();
+
+    assert!(message.contains("5 errors"), "{}", message);
+    assert!(
+        message.contains("REQUEST_TIMEOUT (environment) has an invalid value '30s'"),
+        "{}",
+        message
+    );
+    assert!(
+        message.contains("MAX_REQUEST_SIZE (command line) has an invalid value 'lots'"),
+        "{}",
+        message
+    );
+    assert!(
+        message.contains("REQUEST_TIMOUT (config file) is not a configuration key"),
+        "{}",
+        message
+    );
+    assert!(message.contains("http://localhost:3001/app"), "{}", message);
+    assert!(message.contains("BIND_ADDR must be ip:port"), "{}", message);
+
+    let broken = ConfigSources::new().with_file_contents("request_timeout = ", "broken.toml");
+    let message = Config::load(&broken).unwrap_err().to_string();
+    assert!(message.contains("Invalid config file broken.toml"), "{}", message);
+    assert!(message.contains("INBESTIA_API_KEY is required"), "{}", message);
+}
+
+#[test]
+fn test_config_redacted_hides_secrets() {
+    let config = Config {
+        database_url: Some("postgres://ghost:hunter2@db:5432/ghost".to_string()),
+        client_api_keys: [("mobile".to_string(), "client-key".to_string())].into(),
+        ..test_config("http://127.0.0.1:8000")
+    };
+    let printed = serde_json::to_string(&config.redacted()).unwrap();
+
+    for secret in ["test-key", "admin-secret", TEST_JWT_SECRET, "hunter2", "client-key"] {
+        assert!(!printed.contains(secret), "{} leaked: {}", secret, printed);
+    }
+    assert!(printed.contains("postgres://ghost:***@db:5432/ghost"), "{}", printed);
+    assert!(printed.contains("\"mobile\""), "{}", printed);
+}
+
+#[test]
+fn test_config_debug_hides_secrets() {
+    let config = Config {
+        database_url: Some("postgres://ghost:hunter2@db:5432/ghost".to_string()),
+        client_api_keys: [("mobile".to_string(), "client-key".to_string())].into(),
+        webhooks: vec![webhook("ops", "http://hooks.example/ghost", vec![WebhookEvent::AlertFired])],
+        ..test_config("http://127.0.0.1:8000")
+    };
+
+    for printed in [format!("{:?}", config), format!("{:#?}", config), format!("{:?}", config.webhooks[0])] {
+        for secret in ["test-key", "admin-secret", TEST_JWT_SECRET, "hunter2", "client-key", "ops-secret"] {
+            assert!(!printed.contains(secret), "{} leaked: {}", secret, printed);
+        }
+    }
+    let printed = format!("{:?}", config);
+    assert!(printed.starts_with("Config {"), "{}", printed);
+    assert!(printed.contains("postgres://ghost:***@db:5432/ghost"), "{}", printed);
+    assert!(printed.contains("http://hooks.example/ghost"), "{}", printed);
+}
+
+#[tokio::test]
+async fn test_cors_allows_only_configured_origins() {
+    let config = Config {
+        cors_allowed_origins: vec!["http://localhost:3001".to_string()],
+        ..test_config("http://127.0.0.1:8000")
+    };
+    let app = app(AppState::new(config).unwrap());
+
+    let response = app
+        .clone()
+        .oneshot(preflight("http://localhost:3001"))
+        .await
+        .unwrap();
+    let headers = response.headers();
+    assert_eq!(
+        headers["access-control-allow-origin"],
+        "http://localhost:3001"
+    );
+    assert_eq!(headers["access-control-allow-credentials"], "true");
+
+    let response = app
+        .oneshot(preflight("http://evil.example"))
+        .await
+        .unwrap();
+    assert!(response
+        .headers()
+        .get("access-control-allow-origin")
+        .is_none());
+}
+
+// ===== Recarga de la configuración =====
+
+fn preflight(origin: &str) -> Request<Body> {
+    Request::builder()
+        .method("OPTIONS")
+        .uri("/api/health")
+        .header("origin", origin)
+        .header("access-control-request-method", "GET")
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_config_reload_swaps_settings_and_routes() {
+    let config = test_config("http://127.0.0.1:9");
+    let next = Arc::new(std::sync::Mutex::new(config.clone()));
+    let levels = Arc::new(std::sync::Mutex::new(Vec::<String>::new()));
+    let state = AppState::new(config).unwrap();
+    let reloader = Arc::new(
+        Reloader::new(state.clone(), {
+            let next = next.clone();
+            move || Ok(next.lock().unwrap().clone())
+        })
+        .with_log_level({
+            let levels = levels.clone();
+            move |level| {
+                anyhow::ensure!(!level.contains('['), "invalid filter '{}'", level);
+                levels.lock().unwrap().push(level.to_string());
+                Ok(())
+            }
+        }),
+    );
+    let app = reloader.router();
+    let info = || Request::get("/api/v1/info").body(Body::empty()).unwrap();
+
+    let response = app.clone().oneshot(preflight("http://dashboard.example")).await.unwrap();
+    assert!(response.headers().get("access-control-allow-origin").is_none());
+    let response = app.clone().oneshot(info()).await.unwrap();
+    assert_ne!(response.status(), StatusCode::UNAUTHORIZED);
+
+    {
+        let mut next = next.lock().unwrap();
+        next.cors_allowed_origins = vec!["http://dashboard.example".to_string()];
+        next.admin_token = Some("rotated-secret".to_string());
+        next.request_timeout_secs = 90;
+        next.log_level = "debug".to_string();
+        next.bind_address = "0.0.0.0:9999".to_string();
+        next.routes
+            .iter_mut()
+            .find(|route| route.path == "/api/v1/info")
+            .unwrap()
+            .auth = true;
+    }
+    let reloaded = reloader.reload().unwrap();
+
+    let keys: Vec<&str> = reloaded.changes.iter().map(|c| c.key.as_str()).collect();
+    assert_eq!(
+        keys,
+        ["admin_token", "cors_allowed_origins", "log_level", "request_timeout_secs", "routes"]
+    );
+    let admin = reloaded.changes.iter().find(|c| c.key == "admin_token").unwrap();
+    assert_eq!((admin.from.as_str(), admin.to.as_str()), (Some("***"), Some("***")));
+    // BIND_ADDR necesita reiniciar: se avisa y se conserva el valor en vigor
+    assert_eq!(reloaded.restart_required.len(), 1);
+    assert_eq!(reloaded.restart_required[0].key, "bind_address");
+    let settings = state.settings();
+    assert_eq!(settings.config.bind_address, "127.0.0.1:8085");
+    assert_eq!(settings.config.request_timeout_secs, 90);
+    assert_eq!(*levels.lock().unwrap(), ["debug"]);
+
+    // El router nuevo ya aplica CORS, token de administración y políticas de las rutas
+    let response = app.clone().oneshot(preflight("http://dashboard.example")).await.unwrap();
+    assert_eq!(
+        response.headers()["access-control-allow-origin"],
+        "http://dashboard.example"
+    );
+    let response = app.clone().oneshot(admin_get("/api/admin/cache")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app
+        .clone()
+        .oneshot(
+            Request::get("/api/admin/cache")
+                .header("x-admin-token", "rotated-secret")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(info()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Una configuración inválida no se aplica
+    next.lock().unwrap().api_key = Secret::default();
+    let error = reloader.reload().unwrap_err().to_string();
+    assert!(error.contains("INBESTIA_API_KEY is required"), "{}", error);
+    next.lock().unwrap().api_key = "test-key".into();
+    next.lock().unwrap().log_level = "[".to_string();
+    assert!(reloader.reload().is_err());
+    let settings = state.settings();
+    assert_eq!(settings.config.api_key.expose(), "test-key");
+    assert_eq!(settings.config.log_level, "debug");
+}
+
+#[tokio::test]
+async fn test_config_reload_follows_config_file_changes() {
+    let path = std::env::temp_dir().join(format!("ghost-config-{}.toml", uuid::Uuid::new_v4()));
+    let contents = |timeout: u64| {
+        format!(
+            "inbestia_api_url = \"http://127.0.0.1:9\"\ninbestia_api_key = \"key\"\n\
+             auth_jwt_secret = \"{}\"\nrequest_timeout = {}\n",
+            TEST_JWT_SECRET, timeout
+        )
+    };
+    std::fs::write(&path, contents(30)).unwrap();
+
+    let load = {
+        let path = path.clone();
+        move || Config::load(&ConfigSources::new().with_file(&path))
+    };
+    let state = AppState::new(load().unwrap()).unwrap();
+    let files = ConfigSources::new().with_file(&path).files();
+    assert_eq!(files, vec![path.clone()]);
+    reload::spawn(Arc::new(Reloader::new(state.clone(), load)), files).unwrap();
+
+    std::fs::write(&path, contents(45)).unwrap();
+    for _ in 0..100 {
+        if state.settings().config.request_timeout_secs == 45 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    assert_eq!(state.settings().config.request_timeout_secs, 45);
+
+    // Un fichero roto deja la configuración anterior
+    std::fs::write(&path, "request_timeout = ").unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    assert_eq!(state.settings().config.request_timeout_secs, 45);
+    std::fs::remove_file(&path).unwrap();
+}
+
+// ===== Claves de inBestia =====
+
+#[test]
+fn test_api_key_is_secret_and_loads_from_key_file() {
+    let config = test_config("http://127.0.0.1:9");
+    assert!(!format!("{:?}", config).contains("test-key"));
+    assert!(!serde_json::to_string(&config).unwrap().contains("test-key"));
+    assert_eq!(config.api_key.to_string(), "***");
+    assert_eq!(config.api_key.expose(), "test-key");
+
+    let path = std::env::temp_dir().join(format!("ghost-keys-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&path, "# rotación en curso\nnew-key\n\nold-key\n").unwrap();
+    let env = |extra: &[(&str, &str)]| {
+        let mut vars = env_vars(&[
+            ("INBESTIA_API_URL", "http://127.0.0.1:9"),
+            ("AUTH_JWT_SECRET", TEST_JWT_SECRET),
+            ("INBESTIA_API_KEY_FILE", path.to_str().unwrap()),
+        ]);
+        vars.extend(env_vars(extra));
+        ConfigSources::new().with_env(vars)
+    };
+
+    let sources = env(&[("INBESTIA_API_KEY_FALLBACKS", "older-key")]);
+    assert_eq!(sources.files(), vec![path.clone()]);
+    let config = Config::load(&sources).unwrap();
+    assert_eq!(config.api_key.expose(), "new-key");
+    let fallbacks: Vec<&str> = config.api_key_fallbacks.iter().map(Secret::expose).collect();
+    assert_eq!(fallbacks, ["old-key", "older-key"]);
+
+    let error = Config::load(&env(&[("INBESTIA_API_KEY", "inline")])).unwrap_err();
+    assert!(error.to_string().contains("cannot both be set"), "{}", error);
+    std::fs::write(&path, "# vacío\n").unwrap();
+    let error = Config::load(&env(&[])).unwrap_err();
+    assert!(error.to_string().contains("contains no key"), "{}", error);
+    std::fs::remove_file(&path).unwrap();
+
+    // Una rotación aparece en el diff de la recarga, sin el valor
+    let rotated = Config {
+        api_key: "rotated".into(),
+        ..config.clone()
+    };
+    let changes = reload::diff(&config, &rotated);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].key, "api_key");
+    assert_eq!(changes[0].to, "***");
+}
+
+/// inBestia que sólo acepta la clave de `accepted` y anota la clave y el body de cada llamada
+fn key_checking_upstream(
+    accepted: Arc<std::sync::Mutex<String>>,
+    seen: Arc<std::sync::Mutex<Vec<(String, String)>>>,
+) -> Router {
+    Router::new().fallback(move |headers: HeaderMap, body: String| {
+        let (accepted, seen) = (accepted.clone(), seen.clone());
+        async move {
+            let auth = headers
+                .get("authorization")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+            seen.lock().unwrap().push((auth.clone(), body));
+            if auth != format!("Bearer {}", accepted.lock().unwrap()) {
+                return StatusCode::UNAUTHORIZED.into_response();
+            }
+            axum::Json(serde_json::json!([])).into_response()
+        }
+    })
+}
+
+#[tokio::test]
+async fn test_upstream_key_rotation_falls_back_on_401() {
+    let accepted = Arc::new(std::sync::Mutex::new("old-key".to_string()));
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let api_base = spawn_upstream(key_checking_upstream(accepted.clone(), seen.clone())).await;
+    let config = Config {
+        api_key: "new-key".into(),
+        api_key_fallbacks: vec!["old-key".into()],
+        ..test_config(&api_base)
+    };
+    let state = AppState::new(config).unwrap();
+    let app = app(state.clone());
+    let body = r#"{"symbol":"AAPL","timeframe":"1d"}"#;
+    let keys = |seen: &[(String, String)]| -> Vec<String> {
+        seen.iter().map(|(auth, _)| auth.clone()).collect()
+    };
+
+    // La principal recibe 401 y la de reserva repite la llamada con el mismo body
+    let response = app.clone().oneshot(post_json("/api/v1/analyze", body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    {
+        let seen = seen.lock().unwrap();
+        assert_eq!(keys(&seen), ["Bearer new-key", "Bearer old-key"]);
+        assert!(seen.iter().all(|(_, sent)| sent == body));
+    }
+
+    // La clave aceptada pasa a ser la primera, también para el cliente tipado
+    seen.lock().unwrap().clear();
+    let response = app.clone().oneshot(post_json("/api/v1/analyze", body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    state.inbestia().providers_status().await.unwrap();
+    assert_eq!(keys(&seen.lock().unwrap()), ["Bearer old-key", "Bearer old-key"]);
+
+    // inBestia retira la clave antigua: se vuelve a la principal sin errores
+    *accepted.lock().unwrap() = "new-key".to_string();
+    seen.lock().unwrap().clear();
+    state.inbestia().providers_status().await.unwrap();
+    let response = app.clone().oneshot(get_request("/api/v1/providers/status")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        keys(&seen.lock().unwrap()),
+        ["Bearer old-key", "Bearer new-key", "Bearer new-key"]
+    );
+
+    // Sin más claves que probar, el 401 llega al cliente
+    *accepted.lock().unwrap() = "another-key".to_string();
+    let response = app.oneshot(get_request("/api/v1/providers/status")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 }
@@ -59,16 +59,14 @@ pub struct TimeframesMetadata {
 pub struct TimeframeService {
     config: Option<TimeframesConfigResponse>,
     base_url: String,
-    api_key: String,
 }
 
 impl TimeframeService {
     /// Create new TimeframeService
-    pub fn new(base_url: String, api_key: String) -> Self {
+    pub fn new(base_url: String) -> Self {
         Self {
             config: None,
             base_url,
-            api_key,
         }
     }
 
@@ -363,7 +361,7 @@ mod tests {
     #[test]
     fn test_canonical_to_api_format() {
         use crate::config::AppConfig;
-        let service = TimeframeService::new(AppConfig::TIMEFRAMES_API_URL.to_string(), AppConfig::API_KEY.to_string());
+        let service = TimeframeService::new(AppConfig::TIMEFRAMES_API_URL.to_string());
 
         assert_eq!(service.canonical_to_api_format("5m").unwrap(), "minute5");
         assert_eq!(service.canonical_to_api_format("1h").unwrap(), "hour1");
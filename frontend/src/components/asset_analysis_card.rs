@@ -364,17 +364,8 @@ pub fn AssetAnalysisCard() -> Html {
 /// Mapear formato de timeframe a lo que espera la API de análisis
 /// La API de análisis espera: minute1, minute5, minute15, minute30, hour1, hour4, daily, weekly, monthly
 fn map_to_api_format(timeframe: &str) -> String {
-    match timeframe {
-        "1m" => "minute1".to_string(),
-        "5m" => "minute5".to_string(),
-        "15m" => "minute15".to_string(),
-        "30m" => "minute30".to_string(),
-        "1h" => "hour1".to_string(),
-        "4h" => "hour4".to_string(),
-        "1d" => "daily".to_string(),
-        "1w" => "weekly".to_string(),
-        "1M" => "monthly".to_string(),
+    ghost_shared::timeframes::resolve(timeframe)
+        .map(|tf| tf.analysis.to_string())
         // Fallback: devolver tal como está si no hay mapeo
-        other => other.to_string(),
-    }
+        .unwrap_or_else(|| timeframe.to_string())
 }
\ No newline at end of file
@@ -2,7 +2,8 @@ use yew::prelude::*;
 use yew_router::prelude::*;
 use gloo_storage::{LocalStorage, Storage};
 use web_sys::window;
-use crate::routes::{Route, Home, AssetAnalysis, SimpleApiStatus};
+use crate::routes::{Route, Home, AssetAnalysis, SimpleApiStatus, Login, Watchlists};
+use crate::services::auth_api;
 
 const THEME_KEY: &str = "ghost-theme";
 
@@ -27,6 +28,29 @@ pub fn Layout() -> Html {
 
     let theme_class = if *is_dark_theme { "is-dark" } else { "" };
 
+    // Usuario de la sesión del gateway (None = sin sesión); se refresca al navegar
+    let current_user = use_state(|| None::<String>);
+    {
+        let current_user = current_user.clone();
+        use_effect_with(current_route.clone(), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                current_user.set(auth_api::current_user().await);
+            });
+            || ()
+        });
+    }
+
+    let on_logout = {
+        let current_user = current_user.clone();
+        Callback::from(move |_| {
+            let current_user = current_user.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let _ = auth_api::logout().await;
+                current_user.set(None);
+            });
+        })
+    };
+
     // Aplicar tema al body y html del documento - tanto al inicializar como al cambiar
     use_effect_with(
         *is_dark_theme,
@@ -111,6 +135,24 @@ pub fn Layout() -> Html {
                                     <span>{ "Asset Analysis" }</span>
                                 </Link<Route>>
                             </li>
+                            <li>
+                                <Link<Route> to={Route::Watchlists} classes={if current_route == Route::Watchlists { "is-active" } else { "" }}>
+                                    <span>{ "Watchlists" }</span>
+                                </Link<Route>>
+                            </li>
+                        </ul>
+                        <p class="menu-label">{ "Sesión" }</p>
+                        <ul class="menu-list">
+                            if let Some(username) = (*current_user).clone() {
+                                <li><span class="px-3">{ username }</span></li>
+                                <li><a onclick={on_logout}>{ "Cerrar sesión" }</a></li>
+                            } else {
+                                <li>
+                                    <Link<Route> to={Route::Login} classes={if current_route == Route::Login { "is-active" } else { "" }}>
+                                        <span>{ "Iniciar sesión" }</span>
+                                    </Link<Route>>
+                                </li>
+                            }
                         </ul>
                     </aside>
                 </div>
@@ -131,6 +173,8 @@ fn switch(routes: Route) -> Html {
         Route::Home => html! { <Home /> },
         Route::ApiStatusDirect => html! { <SimpleApiStatus /> },
         Route::AssetAnalysis => html! { <AssetAnalysis /> },
+        Route::Watchlists => html! { <Watchlists /> },
+        Route::Login => html! { <Login /> },
         Route::NotFound => html! {
             <div class="container">
                 <h1 class="title">{ "404 - Page Not Found" }</h1>
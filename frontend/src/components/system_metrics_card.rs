@@ -102,6 +102,49 @@ pub fn SystemMetricsCard() -> Html {
                     </div>
                 } else if let Some(metrics_response) = (*metrics_state).as_ref() {
                     <div class="content">
+                        <h4 class="title is-6">{ "Gateway" }</h4>
+                        <div class="columns is-multiline">
+                            <div class="column is-one-third">
+                                <div class="box">
+                                    <span class="has-text-weight-semibold">{ "CPU del proceso" }</span>
+                                    <p class="is-size-4">{ format_percent(metrics_response.gateway_metrics.cpu.usage_percent) }</p>
+                                    <p class="help">{ format!("Host: {}", format_percent(metrics_response.gateway_metrics.cpu.host_usage_percent)) }</p>
+                                </div>
+                            </div>
+                            <div class="column is-one-third">
+                                <div class="box">
+                                    <span class="has-text-weight-semibold">{ "Memoria (RSS)" }</span>
+                                    <p class="is-size-4">{ format_mb(metrics_response.gateway_metrics.memory.used_mb) }</p>
+                                    <p class="help">{ format!(
+                                        "Host: {} de {} ({} libres)",
+                                        format_mb(metrics_response.gateway_metrics.memory.host_used_mb),
+                                        format_mb(metrics_response.gateway_metrics.memory.host_total_mb),
+                                        format_mb(metrics_response.gateway_metrics.memory.available_mb),
+                                    ) }</p>
+                                </div>
+                            </div>
+                            <div class="column is-one-third">
+                                <div class="box">
+                                    <span class="has-text-weight-semibold">{ "Conexiones activas" }</span>
+                                    <p class="is-size-4">{ metrics_response.gateway_metrics.active_connections }</p>
+                                    <p class="help">{ format!(
+                                        "{} descriptores abiertos",
+                                        metrics_response.gateway_metrics.open_fds.map(|n| n.to_string()).unwrap_or_else(|| "n/d".to_string()),
+                                    ) }</p>
+                                </div>
+                            </div>
+                            <div class="column is-full">
+                                <p class="help">{ format!(
+                                    "Tokio: {} workers, {} tareas vivas, {} en cola global · Uptime {}s",
+                                    metrics_response.gateway_metrics.tokio.workers,
+                                    metrics_response.gateway_metrics.tokio.alive_tasks,
+                                    metrics_response.gateway_metrics.tokio.global_queue_depth,
+                                    metrics_response.gateway_metrics.uptime_secs,
+                                ) }</p>
+                            </div>
+                        </div>
+
+                        <h4 class="title is-6">{ "inBestia" }</h4>
                         <div class="columns is-multiline">
                             // CPU Usage
                             <div class="column is-half">
@@ -192,3 +235,12 @@ pub fn SystemMetricsCard() -> Html {
         </div>
     }
 }
+
+/// Porcentaje con un decimal, o "n/d" si el gateway aún no tiene muestra
+fn format_percent(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.1}%", v)).unwrap_or_else(|| "n/d".to_string())
+}
+
+fn format_mb(value: Option<u64>) -> String {
+    value.map(|v| format!("{} MB", v)).unwrap_or_else(|| "n/d".to_string())
+}
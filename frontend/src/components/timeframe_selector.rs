@@ -29,8 +29,7 @@ pub fn TimeframeSelector(props: &TimeframeSelectorProps) -> Html {
             let timeframes_state = timeframes_state.clone();
             wasm_bindgen_futures::spawn_local(async move {
                 let mut service = TimeframeService::new(
-                    AppConfig::TIMEFRAMES_API_URL.to_string(),
-                    AppConfig::API_KEY.to_string()
+                    AppConfig::TIMEFRAMES_API_URL.to_string()
                 );
 
                 match service.fetch_config().await {
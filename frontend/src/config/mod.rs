@@ -2,18 +2,31 @@
 pub struct AppConfig;
 
 impl AppConfig {
-    /// Base URL for the main inBestia API
-    pub const API_BASE_URL: &'static str = "http://127.0.0.1:8080";
+    /// Base URL of the Ghost gateway. Empty = same origin: Trunk proxies `/api/`
+    /// to the gateway, which holds the inBestia key and injects it upstream.
+    pub const API_BASE_URL: &'static str = "";
 
     /// Base URL for timeframes service (same as main API)
-    pub const TIMEFRAMES_API_URL: &'static str = "http://127.0.0.1:8080";
+    pub const TIMEFRAMES_API_URL: &'static str = "";
 
-    /// API key for authentication
-    pub const API_KEY: &'static str = "inbestia2025key";
-
-    /// Health check endpoint
+    /// Health check endpoint (gateway + inBestia connectivity)
     pub fn health_url() -> String {
-        format!("{}/health", Self::API_BASE_URL)
+        format!("{}/api/health", Self::API_BASE_URL)
+    }
+
+    /// Gateway login endpoint
+    pub fn login_url() -> String {
+        format!("{}/api/auth/login", Self::API_BASE_URL)
+    }
+
+    /// Gateway logout endpoint
+    pub fn logout_url() -> String {
+        format!("{}/api/auth/logout", Self::API_BASE_URL)
+    }
+
+    /// Current session user endpoint
+    pub fn me_url() -> String {
+        format!("{}/api/auth/me", Self::API_BASE_URL)
     }
 
     /// System info endpoint
@@ -22,6 +35,10 @@ impl AppConfig {
     }
 
     /// System metrics endpoint
+    pub fn gateway_metrics_url() -> String {
+        format!("{}/api/metrics/system", Self::API_BASE_URL)
+    }
+
     pub fn metrics_url() -> String {
         format!("{}/api/v1/metrics/system", Self::API_BASE_URL)
     }
@@ -35,4 +52,14 @@ impl AppConfig {
     pub fn analyze_url() -> String {
         format!("{}/api/v1/analyze", Self::API_BASE_URL)
     }
+
+    /// Watchlists of the session user
+    pub fn watchlists_url() -> String {
+        format!("{}/api/watchlists", Self::API_BASE_URL)
+    }
+
+    /// A single watchlist
+    pub fn watchlist_url(id: i64) -> String {
+        format!("{}/api/watchlists/{}", Self::API_BASE_URL, id)
+    }
 }
\ No newline at end of file
@@ -1,26 +1,10 @@
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct AnalysisRequest {
-    pub symbol: String,
-    pub timeframe: String,
-    pub include_fundamental: bool,
-}
+/// Body de `/api/v1/analyze`, compartido con el gateway que lo valida
+pub use ghost_shared::AnalysisRequest;
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
-pub struct AnalysisResponse {
-    pub symbol: String,
-    pub timeframe: String,
-    pub score: f64,
-    pub technical_score: f64,
-    pub fundamental_score: Option<f64>,
-    pub trend_score: f64,
-    pub momentum_score: f64,
-    pub volatility_score: f64,
-    pub volume_score: f64,
-    pub suggested_operation: String,
-    pub explanation: String,
-}
+/// Respuesta de `/api/v1/analyze`
+pub use ghost_shared::AnalysisResponse;
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct TimeframeConfig {
@@ -38,18 +22,9 @@ impl TimeframeConfig {
     /// Convierte el timeframe de la API al formato esperado por el endpoint de análisis
     /// La API de inBestia espera: minute1, minute5, minute15, minute30, hour1, hour4, daily, weekly, monthly
     pub fn to_analysis_format(&self) -> String {
-        match self.name.as_str() {
-            "1m" => "minute1".to_string(),
-            "5m" => "minute5".to_string(),
-            "15m" => "minute15".to_string(),
-            "30m" => "minute30".to_string(),
-            "1h" => "hour1".to_string(),
-            "4h" => "hour4".to_string(),
-            "1d" => "daily".to_string(),
-            "1w" => "weekly".to_string(),
-            "1M" => "monthly".to_string(),
-            _ => self.name.clone(), // Fallback al nombre original
-        }
+        ghost_shared::timeframes::resolve(&self.name)
+            .map(|tf| tf.analysis.to_string())
+            .unwrap_or_else(|| self.name.clone()) // Fallback al nombre original
     }
 }
 
@@ -71,11 +46,7 @@ pub struct TimeframesMetadata {
     pub supported_providers: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct SymbolErrorResponse {
-    pub message: String,
-    pub suggestions: Vec<String>,
-}
+pub use ghost_shared::SymbolErrorResponse;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AnalysisState {
@@ -11,17 +11,7 @@ pub struct SimpleSystemMetrics {
 }
 
 /// Estado de un proveedor de datos - Coincide EXACTAMENTE con la respuesta de InBestia API
-#[derive(Debug, Clone, Deserialize, PartialEq)]
-pub struct ProviderStatus {
-    pub name: String,
-    #[serde(rename = "type_code")]
-    pub type_code: String,
-    pub available: bool,
-    pub active: bool,
-    pub rate_limit_remaining: Option<u32>,
-    pub rate_limit_reset: Option<String>,
-    pub response_time_ms: u64,
-}
+pub use ghost_shared::ProviderStatus;
 
 /// Estado de salud simplificado
 #[derive(Debug, Clone, PartialEq)]
@@ -67,24 +67,39 @@ pub struct ExternalApiMetrics {
     pub active_requests: u32,
 }
 
-/// Métricas del gateway
+/// Métricas del proceso del gateway y de su host (`None` si el gateway no puede medirlas)
 #[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct GatewayMetrics {
     pub active_connections: u32,
     pub cpu: CpuMetrics,
     pub memory: MemoryMetrics,
     pub uptime_secs: u64,
+    pub open_fds: Option<u64>,
+    pub tokio: TokioMetrics,
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct CpuMetrics {
-    pub usage_percent: f64,
+    /// CPU del proceso (100% = un núcleo)
+    pub usage_percent: Option<f64>,
+    /// CPU del host (100% = todos los núcleos)
+    pub host_usage_percent: Option<f64>,
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct MemoryMetrics {
-    pub used_mb: u64,
-    pub available_mb: u64,
+    /// Memoria residente del proceso
+    pub used_mb: Option<u64>,
+    pub available_mb: Option<u64>,
+    pub host_total_mb: Option<u64>,
+    pub host_used_mb: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct TokioMetrics {
+    pub workers: u64,
+    pub alive_tasks: u64,
+    pub global_queue_depth: u64,
 }
 
 /// Respuesta de métricas del sistema
@@ -0,0 +1,84 @@
+use yew::prelude::*;
+use yew_router::prelude::*;
+use web_sys::HtmlInputElement;
+use crate::routes::Route;
+use crate::services::auth_api;
+
+#[function_component]
+pub fn Login() -> Html {
+    let navigator = use_navigator();
+    let username = use_state(String::new);
+    let password = use_state(String::new);
+    let error = use_state(|| None::<String>);
+    let loading = use_state(|| false);
+
+    let on_username = {
+        let username = username.clone();
+        Callback::from(move |e: InputEvent| {
+            username.set(e.target_unchecked_into::<HtmlInputElement>().value());
+        })
+    };
+
+    let on_password = {
+        let password = password.clone();
+        Callback::from(move |e: InputEvent| {
+            password.set(e.target_unchecked_into::<HtmlInputElement>().value());
+        })
+    };
+
+    let on_submit = {
+        let username = username.clone();
+        let password = password.clone();
+        let error = error.clone();
+        let loading = loading.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let username = (*username).clone();
+            let password = (*password).clone();
+            let error = error.clone();
+            let loading = loading.clone();
+            let navigator = navigator.clone();
+            loading.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                match auth_api::login(&username, &password).await {
+                    Ok(_) => {
+                        error.set(None);
+                        if let Some(navigator) = navigator {
+                            navigator.push(&Route::Home);
+                        }
+                    }
+                    Err(message) => error.set(Some(message)),
+                }
+                loading.set(false);
+            });
+        })
+    };
+
+    html! {
+        <div class="container" style="max-width: 400px;">
+            <h1 class="title">{ "Iniciar sesión" }</h1>
+            <form class="box" onsubmit={on_submit}>
+                <div class="field">
+                    <label class="label">{ "Usuario" }</label>
+                    <div class="control">
+                        <input class="input" type="text" autocomplete="username"
+                            value={(*username).clone()} oninput={on_username} />
+                    </div>
+                </div>
+                <div class="field">
+                    <label class="label">{ "Contraseña" }</label>
+                    <div class="control">
+                        <input class="input" type="password" autocomplete="current-password"
+                            value={(*password).clone()} oninput={on_password} />
+                    </div>
+                </div>
+                if let Some(message) = (*error).clone() {
+                    <p class="help is-danger mb-3">{ message }</p>
+                }
+                <button class={classes!("button", "is-primary", (*loading).then_some("is-loading"))} type="submit">
+                    { "Entrar" }
+                </button>
+            </form>
+        </div>
+    }
+}
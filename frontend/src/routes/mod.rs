@@ -1,9 +1,13 @@
 pub mod dashboard;
 pub mod asset;
+pub mod login;
 pub mod route;
 pub mod simple_api_status;
+pub mod watchlists;
 
 pub use dashboard::Home;
 pub use asset::{AssetAnalysisWorking, AssetAnalysis};
 pub use simple_api_status::SimpleApiStatus;
+pub use login::Login;
 pub use route::Route;
+pub use watchlists::Watchlists;
@@ -8,6 +8,10 @@ pub enum Route {
     ApiStatusDirect,
     #[at("/asset-analysis")]
     AssetAnalysis,
+    #[at("/watchlists")]
+    Watchlists,
+    #[at("/login")]
+    Login,
     #[not_found]
     #[at("/404")]
     NotFound,
@@ -3,6 +3,7 @@ use crate::components::{
     info_card::InfoCard,
     simple_health_status_card::SimpleHealthStatusCard,
     simple_system_metrics_card::SimpleSystemMetricsCard,
+    system_metrics_card::SystemMetricsCard,
     simple_providers_status_card::SimpleProvidersStatusCard,
 };
 
@@ -26,6 +27,11 @@ pub fn SimpleApiStatus() -> Html {
                         <SimpleSystemMetricsCard />
                     </div>
 
+                    // Métricas del propio gateway
+                    <div class="column is-full">
+                        <SystemMetricsCard />
+                    </div>
+
                     // Providers Status (Direct)
                     <div class="column is-full">
                         <SimpleProvidersStatusCard />
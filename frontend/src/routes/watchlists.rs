@@ -0,0 +1,336 @@
+use yew::prelude::*;
+use yew_router::prelude::*;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use ghost_shared::responses::{Watchlist, WatchlistItem};
+use ghost_shared::timeframes::TIMEFRAMES;
+use crate::routes::Route;
+use crate::services::auth_api::SESSION_REQUIRED;
+use crate::services::watchlists_api;
+
+/// Guarda `watchlist` en el gateway y la sustituye en `lists` con la versión guardada
+fn persist(watchlist: Watchlist, lists: UseStateHandle<Vec<Watchlist>>, error: UseStateHandle<Option<String>>) {
+    wasm_bindgen_futures::spawn_local(async move {
+        match watchlists_api::save_watchlist(&watchlist).await {
+            Ok(saved) => {
+                let updated = (*lists)
+                    .iter()
+                    .map(|list| if list.id == saved.id { saved.clone() } else { list.clone() })
+                    .collect();
+                lists.set(updated);
+                error.set(None);
+            }
+            Err(message) => error.set(Some(message)),
+        }
+    });
+}
+
+/// Cambio sobre los símbolos de la watchlist seleccionada
+enum Edit {
+    Add(WatchlistItem),
+    Remove(usize),
+    MoveUp(usize),
+    MoveDown(usize),
+    Timeframe(usize, String),
+    Notes(usize, String),
+}
+
+impl Edit {
+    fn apply(self, items: &mut Vec<WatchlistItem>) {
+        match self {
+            Edit::Add(item) => items.push(item),
+            Edit::Remove(i) => {
+                items.remove(i);
+            }
+            Edit::MoveUp(i) => items.swap(i, i - 1),
+            Edit::MoveDown(i) => items.swap(i, i + 1),
+            Edit::Timeframe(i, timeframe) => items[i].timeframe = timeframe,
+            Edit::Notes(i, notes) => items[i].notes = notes,
+        }
+    }
+}
+
+fn timeframe_options(selected: &str) -> Html {
+    TIMEFRAMES
+        .iter()
+        .map(|tf| html! { <option value={tf.canonical} selected={tf.canonical == selected}>{ tf.canonical }</option> })
+        .collect()
+}
+
+#[function_component]
+pub fn Watchlists() -> Html {
+    let lists = use_state(Vec::<Watchlist>::new);
+    let selected = use_state(|| None::<i64>);
+    let error = use_state(|| None::<String>);
+    let loading = use_state(|| true);
+    let new_name = use_state(String::new);
+    let new_symbol = use_state(String::new);
+    let new_timeframe = use_state(|| "1d".to_string());
+    let new_notes = use_state(String::new);
+
+    {
+        let lists = lists.clone();
+        let selected = selected.clone();
+        let error = error.clone();
+        let loading = loading.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                match watchlists_api::list_watchlists().await {
+                    Ok(loaded) => {
+                        selected.set(loaded.first().map(|list| list.id));
+                        lists.set(loaded);
+                    }
+                    Err(message) => error.set(Some(message)),
+                }
+                loading.set(false);
+            });
+            || ()
+        });
+    }
+
+    let current = (*selected).and_then(|id| lists.iter().find(|list| list.id == id).cloned());
+
+    let on_new_name = {
+        let new_name = new_name.clone();
+        Callback::from(move |e: InputEvent| new_name.set(e.target_unchecked_into::<HtmlInputElement>().value()))
+    };
+
+    let on_create = {
+        let lists = lists.clone();
+        let selected = selected.clone();
+        let error = error.clone();
+        let new_name = new_name.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let name = (*new_name).trim().to_string();
+            if name.is_empty() {
+                return;
+            }
+            let lists = lists.clone();
+            let selected = selected.clone();
+            let error = error.clone();
+            let new_name = new_name.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match watchlists_api::create_watchlist(&name).await {
+                    Ok(created) => {
+                        let mut updated = (*lists).clone();
+                        selected.set(Some(created.id));
+                        updated.push(created);
+                        updated.sort_by(|a, b| a.name.cmp(&b.name));
+                        lists.set(updated);
+                        new_name.set(String::new());
+                        error.set(None);
+                    }
+                    Err(message) => error.set(Some(message)),
+                }
+            });
+        })
+    };
+
+    let on_delete = {
+        let lists = lists.clone();
+        let selected = selected.clone();
+        let error = error.clone();
+        Callback::from(move |id: i64| {
+            let lists = lists.clone();
+            let selected = selected.clone();
+            let error = error.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match watchlists_api::delete_watchlist(id).await {
+                    Ok(()) => {
+                        let remaining: Vec<Watchlist> = (*lists).iter().filter(|list| list.id != id).cloned().collect();
+                        selected.set(remaining.first().map(|list| list.id));
+                        lists.set(remaining);
+                        error.set(None);
+                    }
+                    Err(message) => error.set(Some(message)),
+                }
+            });
+        })
+    };
+
+    // Cambios sobre la watchlist seleccionada: se guarda la lista entera
+    let on_change = {
+        let lists = lists.clone();
+        let error = error.clone();
+        let current = current.clone();
+        Callback::from(move |edit: Edit| {
+            if let Some(mut watchlist) = current.clone() {
+                edit.apply(&mut watchlist.items);
+                persist(watchlist, lists.clone(), error.clone());
+            }
+        })
+    };
+
+    let on_add = {
+        let on_change = on_change.clone();
+        let new_symbol = new_symbol.clone();
+        let new_timeframe = new_timeframe.clone();
+        let new_notes = new_notes.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let symbol = (*new_symbol).trim().to_uppercase();
+            if symbol.is_empty() {
+                return;
+            }
+            let item = WatchlistItem {
+                symbol,
+                timeframe: (*new_timeframe).clone(),
+                notes: (*new_notes).clone(),
+            };
+            on_change.emit(Edit::Add(item));
+            new_symbol.set(String::new());
+            new_notes.set(String::new());
+        })
+    };
+
+    let on_new_symbol = {
+        let new_symbol = new_symbol.clone();
+        Callback::from(move |e: InputEvent| new_symbol.set(e.target_unchecked_into::<HtmlInputElement>().value()))
+    };
+    let on_new_timeframe = {
+        let new_timeframe = new_timeframe.clone();
+        Callback::from(move |e: Event| new_timeframe.set(e.target_unchecked_into::<HtmlSelectElement>().value()))
+    };
+    let on_new_notes = {
+        let new_notes = new_notes.clone();
+        Callback::from(move |e: InputEvent| new_notes.set(e.target_unchecked_into::<HtmlInputElement>().value()))
+    };
+
+    let session_required = (*error).as_deref() == Some(SESSION_REQUIRED);
+
+    html! {
+        <div class="container">
+            <h1 class="title">{ "Watchlists" }</h1>
+
+            if session_required {
+                <div class="notification is-warning is-light">
+                    { "Inicia sesión para ver tus watchlists. " }
+                    <Link<Route> to={Route::Login}>{ "Iniciar sesión" }</Link<Route>>
+                </div>
+            } else if let Some(message) = (*error).clone() {
+                <div class="notification is-danger is-light">{ message }</div>
+            }
+
+            if *loading {
+                <progress class="progress is-small is-primary" max="100"></progress>
+            } else if !session_required {
+                <div class="columns">
+                    <div class="column is-one-quarter">
+                        <div class="box">
+                            <aside class="menu">
+                                <ul class="menu-list">
+                                    { for lists.iter().map(|list| {
+                                        let id = list.id;
+                                        let selected = selected.clone();
+                                        let active = *selected == Some(id);
+                                        html! {
+                                            <li>
+                                                <a class={classes!(active.then_some("is-active"))}
+                                                    onclick={Callback::from(move |_| selected.set(Some(id)))}>
+                                                    { &list.name }
+                                                    <span class="tag is-light ml-2">{ list.items.len() }</span>
+                                                </a>
+                                            </li>
+                                        }
+                                    }) }
+                                </ul>
+                            </aside>
+                            <form class="mt-4" onsubmit={on_create}>
+                                <div class="field has-addons">
+                                    <div class="control is-expanded">
+                                        <input class="input is-small" type="text" placeholder="Nueva watchlist"
+                                            value={(*new_name).clone()} oninput={on_new_name} />
+                                    </div>
+                                    <div class="control">
+                                        <button class="button is-small is-primary" type="submit">{ "Crear" }</button>
+                                    </div>
+                                </div>
+                            </form>
+                        </div>
+                    </div>
+
+                    <div class="column">
+                        if let Some(watchlist) = current {
+                            <div class="box">
+                                <div class="is-flex is-justify-content-space-between is-align-items-center mb-4">
+                                    <h2 class="title is-4 mb-0">{ &watchlist.name }</h2>
+                                    <button class="button is-small is-danger is-outlined"
+                                        onclick={let on_delete = on_delete.clone(); let id = watchlist.id; Callback::from(move |_| on_delete.emit(id))}>
+                                        { "Eliminar" }
+                                    </button>
+                                </div>
+
+                                <table class="table is-fullwidth is-hoverable">
+                                    <thead>
+                                        <tr>
+                                            <th>{ "Símbolo" }</th>
+                                            <th>{ "Timeframe" }</th>
+                                            <th>{ "Notas" }</th>
+                                            <th></th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        { for watchlist.items.iter().enumerate().map(|(i, item)| {
+                                            let last = watchlist.items.len() - 1;
+                                            let edit = |edit: fn(usize) -> Edit| on_change.reform(move |_: MouseEvent| edit(i));
+                                            let on_timeframe = on_change.reform(move |e: Event| {
+                                                Edit::Timeframe(i, e.target_unchecked_into::<HtmlSelectElement>().value())
+                                            });
+                                            let on_notes = on_change.reform(move |e: Event| {
+                                                Edit::Notes(i, e.target_unchecked_into::<HtmlInputElement>().value())
+                                            });
+                                            html! {
+                                                <tr key={item.symbol.clone()}>
+                                                    <td class="has-text-weight-semibold">{ &item.symbol }</td>
+                                                    <td>
+                                                        <div class="select is-small">
+                                                            <select onchange={on_timeframe}>{ timeframe_options(&item.timeframe) }</select>
+                                                        </div>
+                                                    </td>
+                                                    <td>
+                                                        <input class="input is-small" type="text" value={item.notes.clone()} onchange={on_notes} />
+                                                    </td>
+                                                    <td class="has-text-right" style="white-space: nowrap;">
+                                                        <button class="button is-small is-ghost" disabled={i == 0} title="Subir"
+                                                            onclick={edit(Edit::MoveUp)}>{ "↑" }</button>
+                                                        <button class="button is-small is-ghost" disabled={i == last} title="Bajar"
+                                                            onclick={edit(Edit::MoveDown)}>{ "↓" }</button>
+                                                        <button class="delete is-small ml-2" title="Quitar"
+                                                            onclick={edit(Edit::Remove)}></button>
+                                                    </td>
+                                                </tr>
+                                            }
+                                        }) }
+                                    </tbody>
+                                </table>
+
+                                <form onsubmit={on_add}>
+                                    <div class="field is-grouped">
+                                        <div class="control">
+                                            <input class="input is-small" type="text" placeholder="Símbolo (AAPL)"
+                                                value={(*new_symbol).clone()} oninput={on_new_symbol} />
+                                        </div>
+                                        <div class="control">
+                                            <div class="select is-small">
+                                                <select onchange={on_new_timeframe}>{ timeframe_options(&new_timeframe) }</select>
+                                            </div>
+                                        </div>
+                                        <div class="control is-expanded">
+                                            <input class="input is-small" type="text" placeholder="Notas"
+                                                value={(*new_notes).clone()} oninput={on_new_notes} />
+                                        </div>
+                                        <div class="control">
+                                            <button class="button is-small is-primary" type="submit">{ "Añadir" }</button>
+                                        </div>
+                                    </div>
+                                </form>
+                            </div>
+                        } else {
+                            <div class="notification is-light">{ "Crea una watchlist para empezar a seguir símbolos." }</div>
+                        }
+                    </div>
+                </div>
+            }
+        </div>
+    }
+}
@@ -1,6 +1,7 @@
 use crate::domain::analysis_types::{AnalysisRequest, AnalysisResponse, TimeframesConfigResponse, SymbolErrorResponse};
 use gloo_net::http::Request;
 use crate::config::AppConfig;
+use crate::services::auth_api::ensure_authorized;
 
 pub async fn analyze_asset(request: AnalysisRequest) -> Result<AnalysisResponse, String> {
     let request_body = serde_json::to_value(&request)
@@ -17,6 +18,8 @@ pub async fn analyze_asset(request: AnalysisRequest) -> Result<AnalysisResponse,
         .await
         .map_err(|e| format!("Error de conexión: {}", e))?;
 
+    ensure_authorized(&response)?;
+
     if response.ok() {
         let analysis_response: AnalysisResponse = response
             .json()
@@ -34,16 +37,36 @@ pub async fn analyze_asset(request: AnalysisRequest) -> Result<AnalysisResponse,
             }
         }
 
+        // El gateway rechaza los bodies inválidos con los errores de cada campo
+        if status == 422 {
+            if let Some(message) = validation_message(&error_text) {
+                return Err(message);
+            }
+        }
+
         Err(format!("Error del servidor ({}): {}", status, error_text))
     }
 }
 
+/// `campo: mensaje; ...` a partir de un error `VALIDATION_FAILED` del gateway
+fn validation_message(error_text: &str) -> Option<String> {
+    let body: serde_json::Value = serde_json::from_str(error_text).ok()?;
+    let fields = body["error"]["fields"].as_array()?;
+    let details: Vec<String> = fields
+        .iter()
+        .map(|f| format!("{}: {}", f["field"].as_str().unwrap_or("body"), f["message"].as_str().unwrap_or_default()))
+        .collect();
+    Some(format!("Petición inválida: {}", details.join("; ")))
+}
+
 pub async fn fetch_timeframes_config() -> Result<TimeframesConfigResponse, String> {
     let response = Request::get(&AppConfig::timeframes_config_url())
         .send()
         .await
         .map_err(|e| format!("Error de conexión: {}", e))?;
 
+    ensure_authorized(&response)?;
+
     if response.ok() {
         let timeframes_response: TimeframesConfigResponse = response
             .json()
@@ -1,6 +1,7 @@
 use gloo_net::http::Request;
-use crate::domain::types::{ApiInfo, ApiHealth, SystemMetricsResponse};
+use crate::domain::types::{ApiInfo, ApiHealth, GatewayMetrics, SystemMetricsResponse};
 use crate::config::AppConfig;
+use crate::services::auth_api::ensure_authorized;
 
 /// Obtiene información de la API
 pub async fn fetch_info() -> Result<ApiInfo, String> {
@@ -14,57 +15,54 @@ pub async fn check_api_health() -> Result<ApiHealth, String> {
     let response = Request::get(&AppConfig::health_url())
         .send().await.map_err(|e| e.to_string())?;
 
-    let text = response.text().await.map_err(|e| e.to_string())?;
+    let health: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
 
-    // El endpoint /health devuelve texto plano, lo convertimos a estructura
-    let status = if text.contains("funcionando correctamente") { "healthy" } else { "unhealthy" };
+    // `/api/health` del gateway: healthy | degraded | unhealthy | unknown
+    let status = health["status"].as_str().unwrap_or("unhealthy");
 
     Ok(ApiHealth {
         status: status.to_string(),
         external_api: crate::domain::types::ExternalApiStatus {
-            response_time_ms: 0, // No disponible desde este endpoint
+            // Latencia del último health check en segundo plano
+            response_time_ms: health["external_api"]["response_time_ms"].as_u64().unwrap_or(0),
             status: if status == "healthy" { "available".to_string() } else { "unavailable".to_string() },
         },
         gateway: crate::domain::types::GatewayStatus {
-            status: status.to_string(),
-            version: "1.0.0".to_string(), // Valor por defecto
-            uptime_secs: 0, // No disponible desde este endpoint
+            status: health["gateway"]["status"].as_str().unwrap_or("healthy").to_string(),
+            version: health["gateway"]["version"].as_str().unwrap_or("unknown").to_string(),
+            uptime_secs: health["gateway"]["uptime_secs"].as_u64().unwrap_or(0),
         },
-        timestamp: chrono::Utc::now().to_rfc3339(),
-        trace_id: None,
+        timestamp: health["timestamp"].as_str().map(str::to_string)
+            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+        // trace_id de W3C Trace Context: el mismo que ven los logs del gateway y de inBestia
+        trace_id: health["trace_id"].as_str().map(str::to_string),
     })
 }
 
-/// Obtiene las métricas del sistema de inBestia
+/// Obtiene las métricas del gateway y, a través de él, las de inBestia
 pub async fn fetch_system_metrics() -> Result<SystemMetricsResponse, String> {
-    let response = Request::get(&AppConfig::metrics_url())
-        .header("Authorization", &AppConfig::API_KEY)
+    let response = Request::get(&AppConfig::gateway_metrics_url())
         .send().await.map_err(|e| e.to_string())?;
+    ensure_authorized(&response)?;
 
-    // La API devuelve métricas simples, las adaptamos al formato esperado
-    let metrics: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    let body: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    let gateway_metrics: GatewayMetrics = serde_json::from_value(body["gateway_metrics"].clone())
+        .map_err(|e| e.to_string())?;
 
+    // inBestia devuelve métricas simples (o `null` si no respondió), las adaptamos
+    let external = &body["external_api_metrics"];
     Ok(SystemMetricsResponse {
-        status: "success".to_string(),
+        status: body["status"].as_str().unwrap_or("partial").to_string(),
         external_api_metrics: crate::domain::types::ExternalApiMetrics {
-            cpu_usage: metrics["cpu_usage"].as_f64().unwrap_or(0.0),
-            memory_usage: metrics["memory_usage"].as_f64().unwrap_or(0.0),
-            database_connections: metrics["database_connections"].as_i64().unwrap_or(0) as u32,
-            cache_hit_ratio: metrics["cache_hit_ratio"].as_f64().unwrap_or(0.0),
-            active_requests: metrics["active_requests"].as_i64().unwrap_or(0) as u32,
+            cpu_usage: external["cpu_usage"].as_f64().unwrap_or(0.0),
+            memory_usage: external["memory_usage"].as_f64().unwrap_or(0.0),
+            database_connections: external["database_connections"].as_i64().unwrap_or(0) as u32,
+            cache_hit_ratio: external["cache_hit_ratio"].as_f64().unwrap_or(0.0),
+            active_requests: external["active_requests"].as_i64().unwrap_or(0) as u32,
         },
-        gateway_metrics: crate::domain::types::GatewayMetrics {
-            active_connections: 0, // No disponible desde este endpoint
-            cpu: crate::domain::types::CpuMetrics {
-                usage_percent: 0.0, // No disponible desde este endpoint
-            },
-            memory: crate::domain::types::MemoryMetrics {
-                used_mb: 0, // No disponible desde este endpoint
-                available_mb: 0, // No disponible desde este endpoint
-            },
-            uptime_secs: 0, // No disponible desde este endpoint
-        },
-        timestamp: chrono::Utc::now().to_rfc3339(),
-        trace_id: None,
+        gateway_metrics,
+        timestamp: body["timestamp"].as_str().map(str::to_string)
+            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+        trace_id: body["trace_id"].as_str().map(str::to_string),
     })
 }
@@ -0,0 +1,63 @@
+use gloo_net::http::{Request, Response};
+use serde::Deserialize;
+use serde_json::json;
+use crate::config::AppConfig;
+
+/// Error que devuelven los servicios cuando el gateway responde 401
+pub const SESSION_REQUIRED: &str = "SESSION_REQUIRED";
+
+#[derive(Debug, Clone, Deserialize)]
+struct SessionUser {
+    username: String,
+}
+
+/// Inicia sesión en el gateway. La sesión queda en una cookie HttpOnly que el
+/// navegador envía solo; el frontend nunca ve la API key de inBestia.
+pub async fn login(username: &str, password: &str) -> Result<String, String> {
+    let response = Request::post(&AppConfig::login_url())
+        .json(&json!({ "username": username, "password": password }))
+        .map_err(|e| format!("Error creando request: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("Error de conexión: {}", e))?;
+
+    if response.status() == 401 {
+        return Err("Usuario o contraseña incorrectos".to_string());
+    }
+    if !response.ok() {
+        return Err(format!("Error del servidor ({})", response.status()));
+    }
+
+    response
+        .json::<SessionUser>()
+        .await
+        .map(|user| user.username)
+        .map_err(|e| format!("Error parseando respuesta: {}", e))
+}
+
+/// Cierra la sesión actual
+pub async fn logout() -> Result<(), String> {
+    Request::post(&AppConfig::logout_url())
+        .send()
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Error de conexión: {}", e))
+}
+
+/// Usuario de la sesión actual, si la hay
+pub async fn current_user() -> Option<String> {
+    let response = Request::get(&AppConfig::me_url()).send().await.ok()?;
+    if !response.ok() {
+        return None;
+    }
+    response.json::<SessionUser>().await.ok().map(|user| user.username)
+}
+
+/// Convierte un 401 del gateway en el error `SESSION_REQUIRED`
+pub fn ensure_authorized(response: &Response) -> Result<(), String> {
+    if response.status() == 401 {
+        Err(SESSION_REQUIRED.to_string())
+    } else {
+        Ok(())
+    }
+}
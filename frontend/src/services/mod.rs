@@ -1,3 +1,5 @@
 pub mod api;
 pub mod analysis_api;
+pub mod auth_api;
 pub mod simple_api;
+pub mod watchlists_api;
@@ -1,44 +1,42 @@
 use gloo_net::http::Request;
 use crate::domain::simple_types::{SimpleSystemMetrics, ProviderStatus, SimpleHealthStatus, ApiInfo};
 use crate::config::AppConfig;
+use crate::services::auth_api::ensure_authorized;
 
-/// Obtiene métricas del sistema DIRECTAMENTE de InBestia API
+/// Obtiene métricas del sistema de InBestia a través del gateway
 /// Respuesta coincide exactamente con el formato de la API real
 pub async fn get_system_metrics() -> Result<SimpleSystemMetrics, String> {
-    Request::get(&AppConfig::metrics_url())
-        .header("Authorization", &AppConfig::API_KEY)
-        .send().await.map_err(|e| format!("Network error: {}", e))?
-        .json::<SimpleSystemMetrics>().await.map_err(|e| format!("JSON parse error: {}", e))
+    let response = Request::get(&AppConfig::metrics_url())
+        .send().await.map_err(|e| format!("Network error: {}", e))?;
+    ensure_authorized(&response)?;
+    response.json::<SimpleSystemMetrics>().await.map_err(|e| format!("JSON parse error: {}", e))
 }
 
-/// Obtiene estado de proveedores DIRECTAMENTE de InBestia API
+/// Obtiene estado de proveedores de InBestia a través del gateway
 /// Respuesta es un array directo de ProviderStatus
 pub async fn get_providers_status() -> Result<Vec<ProviderStatus>, String> {
-    Request::get(&format!("{}/api/v1/providers/status", AppConfig::API_BASE_URL))
-        .header("Authorization", &AppConfig::API_KEY)
-        .send().await.map_err(|e| format!("Network error: {}", e))?
-        .json::<Vec<ProviderStatus>>().await.map_err(|e| format!("JSON parse error: {}", e))
+    let response = Request::get(&format!("{}/api/v1/providers/status", AppConfig::API_BASE_URL))
+        .send().await.map_err(|e| format!("Network error: {}", e))?;
+    ensure_authorized(&response)?;
+    response.json::<Vec<ProviderStatus>>().await.map_err(|e| format!("JSON parse error: {}", e))
 }
 
-/// Obtiene estado de salud DIRECTAMENTE de InBestia API
-/// Convierte respuesta de texto plano a estructura simple
+/// Obtiene estado de salud de InBestia desde `/api/health` del gateway
 pub async fn get_health_status() -> Result<SimpleHealthStatus, String> {
-    let response = Request::get(&AppConfig::health_url())
-        .send().await.map_err(|e| format!("Network error: {}", e))?;
+    let health = Request::get(&AppConfig::health_url())
+        .send().await.map_err(|e| format!("Network error: {}", e))?
+        .json::<serde_json::Value>().await.map_err(|e| format!("JSON parse error: {}", e))?;
 
-    let text = response.text().await.map_err(|e| format!("Text parse error: {}", e))?;
+    let status = health["status"].as_str().unwrap_or("unhealthy").to_string();
+    let message = health["message"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("inBestia: {}", health["external_api"]["status"].as_str().unwrap_or("unknown")));
 
-    Ok(SimpleHealthStatus {
-        status: if text.contains("funcionando correctamente") {
-            "healthy".to_string()
-        } else {
-            "unhealthy".to_string()
-        },
-        message: text,
-    })
+    Ok(SimpleHealthStatus { status, message })
 }
 
-/// Obtiene información de la API DIRECTAMENTE de InBestia API
+/// Obtiene información de la API a través del gateway
 /// Ya funciona correctamente, lo reutilizamos
 pub async fn get_api_info() -> Result<ApiInfo, String> {
     Request::get(&AppConfig::info_url())
@@ -0,0 +1,96 @@
+use ghost_shared::responses::Watchlist;
+use ghost_shared::{WatchlistItemRequest, WatchlistRequest};
+use gloo_net::http::{Request, Response};
+use crate::config::AppConfig;
+use crate::services::auth_api::ensure_authorized;
+
+/// Watchlists del usuario de la sesión
+pub async fn list_watchlists() -> Result<Vec<Watchlist>, String> {
+    let response = Request::get(&AppConfig::watchlists_url())
+        .send()
+        .await
+        .map_err(|e| format!("Error de conexión: {}", e))?;
+    parse(response).await
+}
+
+pub async fn create_watchlist(name: &str) -> Result<Watchlist, String> {
+    let request = WatchlistRequest {
+        name: name.to_string(),
+        items: Vec::new(),
+    };
+    let response = Request::post(&AppConfig::watchlists_url())
+        .json(&request)
+        .map_err(|e| format!("Error creando request: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("Error de conexión: {}", e))?;
+    parse(response).await
+}
+
+/// Guarda la watchlist entera: el gateway reemplaza nombre y símbolos
+pub async fn save_watchlist(watchlist: &Watchlist) -> Result<Watchlist, String> {
+    let request = WatchlistRequest {
+        name: watchlist.name.clone(),
+        items: watchlist
+            .items
+            .iter()
+            .map(|item| WatchlistItemRequest {
+                symbol: item.symbol.clone(),
+                timeframe: item.timeframe.clone(),
+                notes: item.notes.clone(),
+            })
+            .collect(),
+    };
+    let response = Request::put(&AppConfig::watchlist_url(watchlist.id))
+        .json(&request)
+        .map_err(|e| format!("Error creando request: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("Error de conexión: {}", e))?;
+    parse(response).await
+}
+
+pub async fn delete_watchlist(id: i64) -> Result<(), String> {
+    let response = Request::delete(&AppConfig::watchlist_url(id))
+        .send()
+        .await
+        .map_err(|e| format!("Error de conexión: {}", e))?;
+    ensure_authorized(&response)?;
+    if response.ok() {
+        Ok(())
+    } else {
+        Err(error_message(response).await)
+    }
+}
+
+async fn parse<T: serde::de::DeserializeOwned>(response: Response) -> Result<T, String> {
+    ensure_authorized(&response)?;
+    if !response.ok() {
+        return Err(error_message(response).await);
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Error parseando respuesta: {}", e))
+}
+
+/// Mensaje del error estándar del gateway, con el detalle de cada campo si es un 422
+async fn error_message(response: Response) -> String {
+    let status = response.status();
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    let message = body["error"]["message"].as_str().unwrap_or("Error desconocido");
+    let fields: Vec<String> = body["error"]["fields"]
+        .as_array()
+        .map(|fields| {
+            fields
+                .iter()
+                .map(|f| format!("{}: {}", f["field"].as_str().unwrap_or("body"), f["message"].as_str().unwrap_or_default()))
+                .collect()
+        })
+        .unwrap_or_default();
+    if fields.is_empty() {
+        format!("{} ({})", message, status)
+    } else {
+        format!("{}: {}", message, fields.join("; "))
+    }
+}
@@ -0,0 +1,9 @@
+pub mod requests;
+pub mod responses;
+pub mod timeframes;
+
+pub use requests::{
+    AlertRuleRequest, AnalysisRequest, BatchAnalysisRequest, CompareRequest, HistoricalRequest,
+    IndicatorsRequest, WatchlistItemRequest, WatchlistRequest,
+};
+pub use responses::{AnalysisResponse, ProviderStatus, SymbolErrorResponse};
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+
+/// Tickers tal como los acepta inBestia: `AAPL`, `BRK.B`, `BTC-USD`, `^GSPC`, `EURUSD=X`
+pub const SYMBOL_PATTERN: &str = r"^[A-Za-z0-9.\-^=]{1,20}$";
+
+/// ¿Cumple `symbol` con `SYMBOL_PATTERN`?
+pub fn is_valid_symbol(symbol: &str) -> bool {
+    (1..=20).contains(&symbol.len())
+        && symbol
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'-' | b'^' | b'='))
+}
+
+/// Body de `POST /api/v1/analyze`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct AnalysisRequest {
+    #[cfg_attr(feature = "schema", schemars(regex(pattern = SYMBOL_PATTERN)))]
+    pub symbol: String,
+    #[cfg_attr(feature = "schema", schemars(with = "TimeframeName"))]
+    pub timeframe: String,
+    #[serde(default)]
+    pub include_fundamental: bool,
+}
+
+/// Body de `POST /api/v1/historical`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct HistoricalRequest {
+    #[cfg_attr(feature = "schema", schemars(regex(pattern = SYMBOL_PATTERN)))]
+    pub symbol: String,
+    #[cfg_attr(feature = "schema", schemars(with = "TimeframeName"))]
+    pub timeframe: String,
+    /// Número de velas
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "schema", schemars(range(min = 1, max = 5000)))]
+    pub limit: Option<u32>,
+}
+
+/// Body de `POST /api/v1/indicators`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct IndicatorsRequest {
+    #[cfg_attr(feature = "schema", schemars(regex(pattern = SYMBOL_PATTERN)))]
+    pub symbol: String,
+    #[cfg_attr(feature = "schema", schemars(with = "TimeframeName"))]
+    pub timeframe: String,
+    /// Indicadores a calcular (todos si se omite)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "schema", schemars(length(min = 1)))]
+    pub indicators: Option<Vec<String>>,
+}
+
+/// Body de `POST /api/v1/compare`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct CompareRequest {
+    #[cfg_attr(
+        feature = "schema",
+        schemars(length(min = 2, max = 10), inner(regex(pattern = SYMBOL_PATTERN)))
+    )]
+    pub symbols: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "schema", schemars(with = "TimeframeName"))]
+    pub timeframe: Option<String>,
+}
+
+/// Body de `POST /api/batch/analyze`: se analiza cada símbolo en cada timeframe
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct BatchAnalysisRequest {
+    #[cfg_attr(
+        feature = "schema",
+        schemars(length(min = 1), inner(regex(pattern = SYMBOL_PATTERN)))
+    )]
+    pub symbols: Vec<String>,
+    #[cfg_attr(
+        feature = "schema",
+        schemars(with = "Vec<TimeframeName>", length(min = 1))
+    )]
+    pub timeframes: Vec<String>,
+    #[serde(default)]
+    pub include_fundamental: bool,
+}
+
+/// Body de `POST /api/watchlists` y `PUT /api/watchlists/{id}`. Los símbolos se guardan
+/// en el orden del array; un PUT reemplaza la lista entera.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct WatchlistRequest {
+    #[cfg_attr(feature = "schema", schemars(length(min = 1, max = 100)))]
+    pub name: String,
+    #[serde(default)]
+    #[cfg_attr(feature = "schema", schemars(length(max = 200)))]
+    pub items: Vec<WatchlistItemRequest>,
+}
+
+/// Un símbolo de una watchlist con su timeframe por defecto y notas
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct WatchlistItemRequest {
+    #[cfg_attr(feature = "schema", schemars(regex(pattern = SYMBOL_PATTERN)))]
+    pub symbol: String,
+    #[serde(default = "default_watchlist_timeframe")]
+    #[cfg_attr(feature = "schema", schemars(with = "TimeframeName"))]
+    pub timeframe: String,
+    #[serde(default)]
+    #[cfg_attr(feature = "schema", schemars(length(max = 2000)))]
+    pub notes: String,
+}
+
+fn default_watchlist_timeframe() -> String {
+    "1d".to_string()
+}
+
+/// Body de `POST /api/alerts/rules` y `PUT /api/alerts/rules/{id}`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct AlertRuleRequest {
+    #[cfg_attr(feature = "schema", schemars(length(min = 1, max = 100)))]
+    pub name: String,
+    /// Sólo los análisis de este símbolo (todos si se omite)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "schema", schemars(regex(pattern = SYMBOL_PATTERN)))]
+    pub symbol: Option<String>,
+    /// Sólo los análisis de este timeframe (todos si se omite)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<TimeframeName>"))]
+    pub timeframe: Option<String>,
+    /// Condición sobre los campos del análisis, p.ej. `score crosses above 70`
+    #[cfg_attr(feature = "schema", schemars(length(min = 1, max = 1000)))]
+    pub expression: String,
+    /// Tiempo mínimo entre dos disparos de la regla para un mismo símbolo y timeframe
+    #[serde(default = "default_alert_cooldown")]
+    #[cfg_attr(feature = "schema", schemars(range(max = 2592000)))]
+    pub cooldown_secs: u32,
+    #[serde(default = "default_alert_enabled")]
+    pub enabled: bool,
+}
+
+fn default_alert_cooldown() -> u32 {
+    3600
+}
+
+fn default_alert_enabled() -> bool {
+    true
+}
+
+/// Esquema de un campo de timeframe: cualquier nombre canónico, de análisis o alias
+#[cfg(feature = "schema")]
+pub struct TimeframeName;
+
+#[cfg(feature = "schema")]
+impl JsonSchema for TimeframeName {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Timeframe".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "enum": crate::timeframes::all_names(),
+        })
+    }
+}
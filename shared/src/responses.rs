@@ -0,0 +1,282 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Respuesta de `POST /api/v1/analyze`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AnalysisResponse {
+    pub symbol: String,
+    pub timeframe: String,
+    pub score: f64,
+    pub technical_score: f64,
+    pub fundamental_score: Option<f64>,
+    pub trend_score: f64,
+    pub momentum_score: f64,
+    pub volatility_score: f64,
+    pub volume_score: f64,
+    pub suggested_operation: String,
+    pub explanation: String,
+}
+
+/// Error 400 de inBestia cuando no reconoce el símbolo
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SymbolErrorResponse {
+    pub message: String,
+    pub suggestions: Vec<String>,
+}
+
+/// Resultado de un símbolo × timeframe de `POST /api/batch/analyze`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchItem {
+    pub symbol: String,
+    pub timeframe: String,
+    #[serde(flatten)]
+    pub outcome: BatchOutcome,
+}
+
+/// `{"status": "ok", "result": ...}` o `{"status": "error", "error": ...}`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum BatchOutcome {
+    Ok { result: AnalysisResponse },
+    Error { error: BatchItemError },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchItemError {
+    pub code: String,
+    pub message: String,
+    /// Símbolos parecidos cuando inBestia no reconoce el pedido
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+}
+
+/// Documento agregado de `POST /api/batch/analyze`, con los elementos en el orden pedido
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchAnalysisResponse {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub items: Vec<BatchItem>,
+}
+
+/// Respuesta de `GET /api/confluence/{symbol}`: análisis multi-temporal combinado
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConfluenceResponse {
+    pub symbol: String,
+    /// Confluencia ponderada de -100 (bajista) a +100 (alcista)
+    pub score: f64,
+    pub interpretation: ConfluenceInterpretation,
+    /// `inbestia` si los pesos vienen de `/api/v1/timeframes/config`, `default` si no
+    pub weights_source: String,
+    pub timeframes: Vec<TimeframeSignal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConfluenceInterpretation {
+    /// `strong_bullish`, `bullish`, `neutral`, `bearish` o `strong_bearish`
+    pub label: String,
+    pub description: String,
+}
+
+/// Aportación de un timeframe a la confluencia
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimeframeSignal {
+    /// Nombre canónico (15m, 1h...)
+    pub timeframe: String,
+    pub weight: f64,
+    /// Puntuación de inBestia (0-100)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// Puntuación llevada a -100..+100
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggested_operation: Option<String>,
+    /// Por qué el timeframe no cuenta en la confluencia
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<BatchItemError>,
+}
+
+/// Una watchlist del usuario (`/api/watchlists`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Watchlist {
+    pub id: i64,
+    pub name: String,
+    /// Símbolos en el orden elegido por el usuario
+    pub items: Vec<WatchlistItem>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WatchlistItem {
+    pub symbol: String,
+    /// Timeframe con el que se analiza el símbolo por defecto
+    pub timeframe: String,
+    pub notes: String,
+}
+
+/// Respuesta de `GET /api/history/{symbol}`: evolución de las puntuaciones guardadas
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AnalysisHistoryResponse {
+    pub symbol: String,
+    /// Series de cada timeframe, en orden cronológico
+    pub series: HashMap<String, ScoreSeries>,
+    /// Cambios de `suggested_operation` entre análisis consecutivos del mismo timeframe
+    pub operation_changes: Vec<OperationChange>,
+    pub limit: u32,
+    pub offset: u32,
+    /// Hay más análisis en el rango pedido (siguiente página con `offset + limit`)
+    pub has_more: bool,
+}
+
+/// Serie temporal en columnas: el elemento `i` de cada vector es el análisis de `timestamps[i]`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ScoreSeries {
+    pub timestamps: Vec<DateTime<Utc>>,
+    pub score: Vec<f64>,
+    pub technical_score: Vec<f64>,
+    pub fundamental_score: Vec<Option<f64>>,
+    pub trend_score: Vec<f64>,
+    pub momentum_score: Vec<f64>,
+    pub volatility_score: Vec<f64>,
+    pub volume_score: Vec<f64>,
+    pub suggested_operation: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OperationChange {
+    pub at: DateTime<Utc>,
+    pub timeframe: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// Una regla de alerta del usuario (`/api/alerts/rules`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AlertRule {
+    pub id: i64,
+    pub name: String,
+    pub symbol: Option<String>,
+    pub timeframe: Option<String>,
+    pub expression: String,
+    pub cooldown_secs: u32,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Una alerta disparada (`GET /api/alerts`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FiredAlert {
+    pub id: i64,
+    /// `None` si la regla se eliminó después
+    pub rule_id: Option<i64>,
+    pub rule_name: String,
+    /// La expresión tal como estaba al dispararse
+    pub expression: String,
+    pub symbol: String,
+    pub timeframe: String,
+    pub fired_at: DateTime<Utc>,
+    /// El análisis que disparó la regla
+    pub analysis: AnalysisResponse,
+    /// El análisis anterior del mismo símbolo y timeframe, si lo había
+    pub previous: Option<AnalysisResponse>,
+}
+
+/// Respuesta de `POST /api/v1/historical`. inBestia no documenta el formato de las
+/// series: se conservan tal cual en `data`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoricalResponse {
+    pub symbol: String,
+    pub timeframe: String,
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
+}
+
+/// Respuesta de `POST /api/v1/indicators`; los valores de cada indicador van en `data`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IndicatorsResponse {
+    pub symbol: String,
+    pub timeframe: String,
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
+}
+
+/// Respuesta de `POST /api/v1/compare`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompareResponse {
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
+}
+
+/// Estado de un proveedor de datos (`GET /api/v1/providers/status` devuelve un array)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProviderStatus {
+    pub name: String,
+    pub type_code: String,
+    pub available: bool,
+    pub active: bool,
+    pub rate_limit_remaining: Option<u32>,
+    pub rate_limit_reset: Option<String>,
+    pub response_time_ms: u64,
+}
+
+/// Respuesta de `GET /api/v1/metrics/system`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SystemMetrics {
+    pub cpu_usage: f64,
+    pub memory_usage: f64,
+    pub database_connections: u32,
+    pub cache_hit_ratio: f64,
+    pub active_requests: u32,
+}
+
+/// Respuesta de `GET /api/v1/metrics/reconciliation`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReconciliationMetrics {
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
+}
+
+/// Respuesta de `GET /api/v1/metrics/data_quality`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DataQualityMetrics {
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
+}
+
+/// Respuesta de `GET /api/v1/timeframes/config`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimeframesConfig {
+    pub timeframes: Vec<TimeframeInfo>,
+    pub aliases: HashMap<String, String>,
+    pub categories: HashMap<String, Vec<String>>,
+    /// Formato de cada timeframe por proveedor
+    pub providers: HashMap<String, HashMap<String, String>>,
+    pub metadata: TimeframesMetadata,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimeframeInfo {
+    pub name: String,
+    pub display_name: String,
+    pub duration_seconds: u64,
+    pub weight: f64,
+    pub category: String,
+    pub aliases: Vec<String>,
+    pub recommended_limit: u32,
+    pub max_gap_hours: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimeframesMetadata {
+    pub version: String,
+    pub last_updated: String,
+    pub total_timeframes: u32,
+    pub total_aliases: u32,
+    #[serde(default)]
+    pub supported_providers: Vec<String>,
+}
@@ -0,0 +1,50 @@
+/// Un timeframe de inBestia: nombre canónico, nombre del endpoint de análisis y alias
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timeframe {
+    pub canonical: &'static str,
+    /// Nombre que espera `/api/v1/analyze` (minute1, hour4, daily...)
+    pub analysis: &'static str,
+    pub aliases: &'static [&'static str],
+    /// Peso en la confluencia multi-temporal si inBestia no publica el suyo
+    pub weight: f64,
+}
+
+/// Timeframes soportados por inBestia (`/api/v1/timeframes/config`)
+#[rustfmt::skip]
+pub const TIMEFRAMES: [Timeframe; 9] = [
+    Timeframe { canonical: "1m", analysis: "minute1", aliases: &["1min"], weight: 0.1 },
+    Timeframe { canonical: "5m", analysis: "minute5", aliases: &["5min"], weight: 0.2 },
+    Timeframe { canonical: "15m", analysis: "minute15", aliases: &["15min"], weight: 0.3 },
+    Timeframe { canonical: "30m", analysis: "minute30", aliases: &["30min"], weight: 0.4 },
+    Timeframe { canonical: "1h", analysis: "hour1", aliases: &["hourly"], weight: 0.5 },
+    Timeframe { canonical: "4h", analysis: "hour4", aliases: &["4hours"], weight: 0.7 },
+    Timeframe { canonical: "1d", analysis: "daily", aliases: &[], weight: 1.0 },
+    Timeframe { canonical: "1w", analysis: "weekly", aliases: &[], weight: 1.5 },
+    Timeframe { canonical: "1M", analysis: "monthly", aliases: &[], weight: 2.0 },
+];
+
+impl Timeframe {
+    /// Todos los nombres que identifican al timeframe
+    pub fn names(&self) -> impl Iterator<Item = &'static str> {
+        [self.canonical, self.analysis]
+            .into_iter()
+            .chain(self.aliases.iter().copied())
+    }
+}
+
+/// Busca un timeframe por su nombre canónico, de análisis o alias.
+/// Distingue mayúsculas: `1m` es un minuto y `1M` un mes.
+pub fn resolve(name: &str) -> Option<&'static Timeframe> {
+    TIMEFRAMES.iter().find(|tf| tf.names().any(|n| n == name))
+}
+
+/// Todos los nombres aceptados, sin repetir
+pub fn all_names() -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = Vec::new();
+    for name in TIMEFRAMES.iter().flat_map(Timeframe::names) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}